@@ -10,6 +10,7 @@ import * as readline from "readline";
 const DEVNET = true;
 
 const EOB_CHAIN_ID = DEVNET ? 33133 : 33033;
+const SOLANA_CHAIN_ID = DEVNET ? "100000000000000000000" : "11100000000000000501";
 const ROOT = utf8.encode("r0");
 const GOV_CONSENSUS_TARGET_RATE = 6000;
 const GOV_PROTOCOL_ID = Buffer.from(
@@ -49,6 +50,10 @@ module.exports = async function (provider: anchor.AnchorProvider) {
         [ROOT, utf8.encode("PROTOCOL"), GOV_PROTOCOL_ID],
         program.programId
     )[0];
+    const programData = web3.PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    )[0];
     let eob_master_contract = ethers.utils.defaultAbiCoder.encode(["address"], [MSC]);
     let eob_master_contract_buf: Buffer = new Buffer(eob_master_contract.substring(2), "hex");
     console.log(
@@ -58,6 +63,7 @@ module.exports = async function (provider: anchor.AnchorProvider) {
         )
     );
     console.log("Network:", DEVNET ? "Devnet" : "Mainnet");
+    console.log("Solana chain id:", SOLANA_CHAIN_ID);
     console.log("Owner account:", owner.publicKey.toBase58());
     console.log("Gov executor:", govExecutor1.publicKey.toBase58());
     console.log("Config account:", config.toBase58());
@@ -73,13 +79,15 @@ module.exports = async function (provider: anchor.AnchorProvider) {
             eob_master_contract_buf,
             new anchor.BN(GOV_CONSENSUS_TARGET_RATE),
             transmitters,
-            [govExecutor1.publicKey]
+            [govExecutor1.publicKey],
+            new anchor.BN(SOLANA_CHAIN_ID)
         )
         .accounts({
             admin: owner.publicKey,
             protocolInfo,
             config,
             systemProgram: web3.SystemProgram.programId,
+            programData,
         })
         .signers([owner])
         .rpc();
@@ -0,0 +1,141 @@
+//! The `abi` module provides a minimal reader for Ethereum ABI encoded data used by the governance
+//! protocol of the Photon cross-chain messaging layer.
+//!
+//! ## Overview
+//! Governance calldata arrives from the Entangle Oracle Blockchain as a single ABI encoded tuple. Only a
+//! handful of types are ever used there: `bytes32`, `uint256`, `bytes` and `address[]`. Instead of building
+//! a generic token tree, `AbiReader` resolves head slots and offsets directly over the original buffer
+//! and returns borrowed slices, so no heap allocations are made while decoding inside the program.
+//!
+//! ## Decoding rules
+//! The reader follows the same bounds rules as `ethabi::decode`: offsets and lengths must fit into
+//! four bytes, dynamic data must lie within the buffer, and trailing data is ignored. Any violation is
+//! reported as `CustomError::InvalidProtoMsg` rather than a panic.
+//!
+//! ## Measurements
+//! The program built with the `measure-cu` feature decodes the calldata of every executed gov operation
+//! twice, by `ethabi::decode` as it was decoded before the reader and by the reader, and logs the remaining
+//! compute units around each of them with `sol_log_compute_units`:
+//!
+//! ```text
+//! anchor build -- --features measure-cu && anchor test --skip-build
+//! ```
+//!
+//! `tests/photon.ts` prints the units spent on each decoding of the gov operations it runs.
+//!
+//! ## Example
+//! ```ignore
+//! // (bytes32 protocolId, bytes executor)
+//! let tuple = AbiReader::new(calldata).tuple(0)?;
+//! let protocol_id = tuple.bytes32(0)?;
+//! let executor = tuple.bytes(1)?;
+//! ```
+use crate::{
+    error::CustomError,
+    util::{Bytes32, EthAddress},
+};
+
+const WORD_SIZE: usize = 32;
+const ADDRESS_OFFSET: usize = WORD_SIZE - 20;
+
+/// Reads ABI encoded values from a borrowed buffer. Head slots are addressed by their index.
+#[derive(Clone, Copy, Debug)]
+pub struct AbiReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AbiReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Returns a reader positioned at the dynamic tuple referenced by the offset in `slot`.
+    pub fn tuple(&self, slot: usize) -> Result<AbiReader<'a>, CustomError> {
+        let offset = self.offset(slot)?;
+        let data = self.data.get(offset..).ok_or(CustomError::InvalidProtoMsg)?;
+        Ok(AbiReader { data })
+    }
+
+    /// Reads a `bytes32` value.
+    pub fn bytes32(&self, slot: usize) -> Result<&'a Bytes32, CustomError> {
+        self.word_at(slot.checked_mul(WORD_SIZE).ok_or(CustomError::InvalidProtoMsg)?)
+    }
+
+    /// Reads a `uint256` value that is expected to fit into `u64`.
+    pub fn uint64(&self, slot: usize) -> Result<u64, CustomError> {
+        let word = self.bytes32(slot)?;
+        if word[..WORD_SIZE - 8].iter().any(|x| *x != 0) {
+            return Err(CustomError::InvalidProtoMsg);
+        }
+        Ok(u64::from_be_bytes(word[WORD_SIZE - 8..].try_into().expect("8 bytes slice")))
+    }
+
+    /// Reads a `uint256` value that is expected to fit into `u128`.
+    pub fn uint128(&self, slot: usize) -> Result<u128, CustomError> {
+        let word = self.bytes32(slot)?;
+        if word[..WORD_SIZE - 16].iter().any(|x| *x != 0) {
+            return Err(CustomError::InvalidProtoMsg);
+        }
+        Ok(u128::from_be_bytes(word[WORD_SIZE - 16..].try_into().expect("16 bytes slice")))
+    }
+
+    /// Reads a dynamic `bytes` value.
+    pub fn bytes(&self, slot: usize) -> Result<&'a [u8], CustomError> {
+        let offset = self.offset(slot)?;
+        let len = as_usize(self.word_at(offset)?)?;
+        let start = offset.checked_add(WORD_SIZE).ok_or(CustomError::InvalidProtoMsg)?;
+        let end = start.checked_add(len).ok_or(CustomError::InvalidProtoMsg)?;
+        self.data.get(start..end).ok_or(CustomError::InvalidProtoMsg)
+    }
+
+    /// Reads a dynamic `address[]` value. All elements are bounds checked up front.
+    pub fn addresses(&self, slot: usize) -> Result<AbiAddresses<'a>, CustomError> {
+        let offset = self.offset(slot)?;
+        let len = as_usize(self.word_at(offset)?)?;
+        let start = offset.checked_add(WORD_SIZE).ok_or(CustomError::InvalidProtoMsg)?;
+        let size = len.checked_mul(WORD_SIZE).ok_or(CustomError::InvalidProtoMsg)?;
+        let end = start.checked_add(size).ok_or(CustomError::InvalidProtoMsg)?;
+        let data = self.data.get(start..end).ok_or(CustomError::InvalidProtoMsg)?;
+        Ok(AbiAddresses { data })
+    }
+
+    fn offset(&self, slot: usize) -> Result<usize, CustomError> {
+        as_usize(self.bytes32(slot)?)
+    }
+
+    fn word_at(&self, pos: usize) -> Result<&'a Bytes32, CustomError> {
+        let end = pos.checked_add(WORD_SIZE).ok_or(CustomError::InvalidProtoMsg)?;
+        let word = self.data.get(pos..end).ok_or(CustomError::InvalidProtoMsg)?;
+        Ok(word.try_into().expect("32 bytes slice"))
+    }
+}
+
+/// A borrowed view of an ABI encoded `address[]`.
+#[derive(Clone, Copy, Debug)]
+pub struct AbiAddresses<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> AbiAddresses<'a> {
+    pub fn len(&self) -> usize {
+        self.data.len() / WORD_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = EthAddress> + 'a {
+        self.data
+            .chunks_exact(WORD_SIZE)
+            .map(|x| x[ADDRESS_OFFSET..].try_into().expect("20 bytes slice"))
+    }
+}
+
+/// Offsets and lengths are limited to four bytes as it is done by `ethabi`.
+fn as_usize(word: &Bytes32) -> Result<usize, CustomError> {
+    if word[..WORD_SIZE - 4].iter().any(|x| *x != 0) {
+        return Err(CustomError::InvalidProtoMsg);
+    }
+    Ok(u32::from_be_bytes(word[WORD_SIZE - 4..].try_into().expect("4 bytes slice")) as usize)
+}
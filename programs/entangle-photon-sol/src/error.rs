@@ -29,6 +29,23 @@ use anchor_lang::error_code;
 /// * `MaxTransmittersExceeded` - The maximum number of transmitters has been exceeded.
 /// * `MaxExecutorsExceeded` - The maximum number of executors has been exceeded.
 /// * `MaxProposersExceeded` - The maximum number of proposers has been exceeded.
+/// * `AccountNotMigrated` - The account is stored in an outdated layout and has to be migrated first.
+/// * `InvalidAccountLayout` - The account data does not match the layout expected by the migration.
+/// * `InvalidMigrationAuthority` - The migration is called neither by the admin nor by a gov executor.
+/// * `ExecutorIsNotAssigned` - Another executor is in charge of the operation for the current turn.
+/// * `InvalidProtocolOwner` - The signer is not the owner of the protocol.
+/// * `OwnerLimitExceeded` - The owner action exceeds the limits set by gov for the protocol owner.
+/// * `ProtocolOwnerNotAllowed` - The gov protocol can't have an owner.
+/// * `GovChangeIsLocked` - The timelock of the pending gov change is not expired yet.
+/// * `InvalidGuardian` - The signer is not the guardian of the gov timelock.
+/// * `TooManyTimelockedOperations` - The number of timelocked gov operations exceeds the limit.
+/// * `IsNotUpgradeAuthority` - The signer is not the upgrade authority of the program.
+/// * `InvalidProtocolAddress` - The protocol address is invalid.
+/// * `ProtocolAddressIsAlreadyAllowed` - The protocol address is already allowed.
+/// * `MaxProtocolAddressesExceeded` - The maximum number of protocol addresses has been exceeded.
+/// * `InvalidFunctionSelector` - The function selector is malformed or not initialized.
+/// * `InvalidLoader` - The loader account doesn't match the one recorded in the operation info.
+/// * `ChainIdNotSet` - The chain ID of Solana is zero, it has to be set in the config.
 ///
 /// # Usage
 ///
@@ -98,4 +115,38 @@ pub enum CustomError {
     ConsensusTargetRateTooHigh,
     #[msg("SelectorTooBig")]
     SelectorTooBig,
+    #[msg("AccountNotMigrated")]
+    AccountNotMigrated,
+    #[msg("InvalidAccountLayout")]
+    InvalidAccountLayout,
+    #[msg("InvalidMigrationAuthority")]
+    InvalidMigrationAuthority,
+    #[msg("ExecutorIsNotAssigned")]
+    ExecutorIsNotAssigned,
+    #[msg("InvalidProtocolOwner")]
+    InvalidProtocolOwner,
+    #[msg("OwnerLimitExceeded")]
+    OwnerLimitExceeded,
+    #[msg("ProtocolOwnerNotAllowed")]
+    ProtocolOwnerNotAllowed,
+    #[msg("GovChangeIsLocked")]
+    GovChangeIsLocked,
+    #[msg("InvalidGuardian")]
+    InvalidGuardian,
+    #[msg("TooManyTimelockedOperations")]
+    TooManyTimelockedOperations,
+    #[msg("IsNotUpgradeAuthority")]
+    IsNotUpgradeAuthority,
+    #[msg("InvalidProtocolAddress")]
+    InvalidProtocolAddress,
+    #[msg("ProtocolAddressIsAlreadyAllowed")]
+    ProtocolAddressIsAlreadyAllowed,
+    #[msg("MaxProtocolAddressesExceeded")]
+    MaxProtocolAddressesExceeded,
+    #[msg("InvalidFunctionSelector")]
+    InvalidFunctionSelector,
+    #[msg("InvalidLoader")]
+    InvalidLoader,
+    #[msg("ChainIdNotSet")]
+    ChainIdNotSet,
 }
@@ -0,0 +1,120 @@
+//! The `gas_vault` module reimburses executors for processing the operations of a protocol.
+//!
+//! ## Overview
+//! Every protocol has a gas vault derived from `[ROOT, b"GAS_VAULT", protocol_id]`. The vault is a plain
+//! system account, so it is funded by a regular transfer from anyone. Once an operation is executed, the
+//! executor that loaded it is paid out of the vault the rent it has spent on the operation account in
+//! `load_operation`, the executor that executed it gets the reward set by the `SetExecutorReward` gov
+//! operation, and `ExecutorReimbursed` is emitted.
+//!
+//! ## Insufficient funds
+//! The vault keeps its rent exempt minimum, only the lamports above it are paid out. A vault that is
+//! drained or was never funded pays as much as it holds and never fails the execution, so protocols that
+//! don't fund a vault keep working as before with executors paying for the operations themselves.
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::photon::ROOT;
+
+/// The lamports paid out of the gas vault for an executed operation.
+///
+/// # Fields
+///
+/// * `rent` - The reimbursed rent of the operation account.
+/// * `reward` - The reward set for the protocol.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Payout {
+    pub rent: u64,
+    pub reward: u64,
+}
+
+impl Payout {
+    /// Splits the lamports the vault can spare between the rent and the reward, the rent goes first
+    pub fn new(available: u64, rent: u64, reward: u64) -> Self {
+        let rent = rent.min(available);
+        Self {
+            rent,
+            reward: reward.min(available - rent),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.rent + self.reward
+    }
+}
+
+/// Returns the address of the protocol gas vault
+pub fn gas_vault_address(protocol_id: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[ROOT, b"GAS_VAULT", protocol_id], &crate::ID).0
+}
+
+/// Transfers the rent of the operation account to the loader and the reward to the executor as far
+/// as the vault balance allows, returns the payout made.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn reimburse_executor<'info>(
+    gas_vault: &SystemAccount<'info>,
+    executor: &Signer<'info>,
+    loader: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    protocol_id: &[u8],
+    bump: u8,
+    rent: u64,
+    reward: u64,
+) -> Result<Payout> {
+    let available = gas_vault.lamports().saturating_sub(Rent::get()?.minimum_balance(0));
+    let payout = Payout::new(available, rent, reward);
+    let pay = |to: AccountInfo<'info>, lamports: u64| {
+        if lamports == 0 {
+            return Ok(());
+        }
+        let accounts = Transfer {
+            from: gas_vault.to_account_info(),
+            to,
+        };
+        transfer(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                accounts,
+                &[&[ROOT, b"GAS_VAULT", protocol_id, &[bump]]],
+            ),
+            lamports,
+        )
+    };
+    pay(loader.to_account_info(), payout.rent)?;
+    pay(executor.to_account_info(), payout.reward)?;
+    Ok(payout)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_payout() {
+        assert_eq!(Payout::new(0, 2_000_000, 5000), Payout::default());
+        assert_eq!(
+            Payout::new(10_000_000, 2_000_000, 5000),
+            Payout {
+                rent: 2_000_000,
+                reward: 5000
+            }
+        );
+        assert_eq!(
+            Payout::new(2_001_000, 2_000_000, 5000),
+            Payout {
+                rent: 2_000_000,
+                reward: 1000
+            }
+        );
+        assert_eq!(
+            Payout::new(1_000_000, 2_000_000, 5000),
+            Payout {
+                rent: 1_000_000,
+                reward: 0
+            }
+        );
+        assert_eq!(Payout::new(1_000_000, 2_000_000, 5000).total(), 1_000_000);
+    }
+}
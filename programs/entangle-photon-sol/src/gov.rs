@@ -20,6 +20,16 @@
 //!   securely and only by authorized entities, preventing unauthorized changes.
 //! - **Consensus Management**: Facilitates adjustments to consensus parameters, ensuring the layer adapts to
 //!   evolving operational needs.
+//! - **Receiver Programs**: Adds and removes the programs allowed to receive the operations of a protocol,
+//!   up to `MAX_PROTOCOL_ADDRESSES` of them, so a protocol may be split across several programs.
+//! - **Rate Limits**: Sets the per-protocol limits of executed operations and of the value they carry.
+//! - **Executor Rewards**: Sets the reward paid to executors out of the protocol gas vault.
+//! - **Protocol Owner**: Sets the owner key allowed to manage proposers of the protocol directly on
+//!   Solana up to the given limit, see `owner`.
+//! - **Timelock**: The operations selected by the admin are queued and applied only after a delay unless
+//!   vetoed by the guardian, see `timelock`.
+//! - **Audit Trail**: Every applied operation emits `GovOperationApplied` carrying the old and the new
+//!   values of the changed protocol settings along with the hash of the gov operation.
 //!
 //! ## Public Interfaces
 //! - **Propose Event Emission**: Supports the broadcasting of propose events to signal changes in governance
@@ -39,16 +49,16 @@
 //! with or building on top of the Photon cross-chain messaging layer. It abstracts away internal implementations
 //! to provide a clear view of the module's capabilities and use cases
 use anchor_lang::prelude::*;
-use ethabi::{ethereum_types::U256, ParamType, Token};
 use num_enum::TryFromPrimitive;
 
 use crate::{
+    abi::{AbiAddresses, AbiReader},
     error::CustomError,
-    protocol_data::{FunctionSelector, OperationData, GOV_PROTOCOL_ID},
-    require_ok,
-    util::EthAddress,
-    Config, ProposeEvent, ProtocolInfo, MAX_EXECUTORS, MAX_PROPOSERS, MAX_TRANSMITTERS,
-    RATE_DECIMALS, SOLANA_CHAIN_ID,
+    protocol_data::{OperationData, GOV_PROTOCOL_ID},
+    rate_limit::RateLimit,
+    util::{u128_to_bytes32, Bytes32, EthAddress},
+    Config, GovOperationApplied, ProposeEvent, ProtocolInfo, MAX_EXECUTORS, MAX_PROPOSERS,
+    MAX_PROTOCOL_ADDRESSES, MAX_TRANSMITTERS, RATE_DECIMALS,
 };
 
 /// Enumerates government operations with their corresponding unique operation codes,
@@ -56,7 +66,7 @@ use crate::{
 ///
 /// This approach enables clearer and more maintainable code by replacing arbitrary numerical codes
 /// with descriptive enum variants, each associated with a specific governance action.
-#[derive(TryFromPrimitive)]
+#[derive(Clone, Copy, Debug, TryFromPrimitive)]
 #[repr(u32)]
 pub enum GovOperation {
     AddAllowedProtocol = 0x45a004b9,
@@ -70,57 +80,460 @@ pub enum GovOperation {
     RemoveTransmitters = 0x5206da70,
     UpdateTransmitters = 0x654b46e1,
     SetConsensusTargetRate = 0x970b6109,
+    SetRateLimit = 0x0865a088,
+    SetExclusiveWindow = 0xd148aa82,
+    SetExecutorReward = 0xfc21b554,
+    SetProtocolOwner = 0xbf170129,
 }
 
 const U32_SIZE: usize = 4;
-const HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR: &[u8] = &[
+const HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR: &Bytes32 = &[
     0xba, 0x96, 0x6e, 0x5f, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0,
 ];
 
+/// Calldata of `AddAllowedProtocol`: `(bytes32 protocolId, uint256 consensusTargetRate, address[] transmitters)`
+#[derive(Debug)]
+pub struct AddAllowedProtocolParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub consensus_target_rate: u64,
+    pub transmitters: AbiAddresses<'a>,
+}
+
+impl<'a> AddAllowedProtocolParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata).tuple(0)?;
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            consensus_target_rate: tuple.uint64(1)?,
+            transmitters: tuple.addresses(2)?,
+        })
+    }
+}
+
+/// Calldata of the operations managing a single address of the protocol, i.e. protocol address,
+/// proposer or executor: `(bytes32 protocolId, bytes address)`
+#[derive(Debug)]
+pub struct AddressParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub address: &'a [u8],
+}
+
+impl<'a> AddressParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata).tuple(0)?;
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            address: tuple.bytes(1)?,
+        })
+    }
+
+    fn pubkey(&self) -> std::result::Result<Pubkey, CustomError> {
+        Ok(Pubkey::new_from_array(self.address.try_into().map_err(|_| CustomError::InvalidGovMsg)?))
+    }
+}
+
+/// Calldata of `AddTransmitters` and `RemoveTransmitters`: `(bytes32 protocolId, address[] transmitters)`
+#[derive(Debug)]
+pub struct TransmittersParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub transmitters: AbiAddresses<'a>,
+}
+
+impl<'a> TransmittersParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata).tuple(0)?;
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            transmitters: tuple.addresses(1)?,
+        })
+    }
+}
+
+/// Calldata of `UpdateTransmitters`: `(bytes32 protocolId, address[] toAdd, address[] toRemove)`
+#[derive(Debug)]
+pub struct UpdateTransmittersParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub to_add: AbiAddresses<'a>,
+    pub to_remove: AbiAddresses<'a>,
+}
+
+impl<'a> UpdateTransmittersParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata).tuple(0)?;
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            to_add: tuple.addresses(1)?,
+            to_remove: tuple.addresses(2)?,
+        })
+    }
+}
+
+/// Calldata of `SetConsensusTargetRate`: `(bytes32 protocolId, uint256 targetRate)`.
+/// It is a static tuple so it is encoded in place.
+#[derive(Debug)]
+pub struct ConsensusTargetRateParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub consensus_target_rate: u64,
+}
+
+impl<'a> ConsensusTargetRateParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata);
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            consensus_target_rate: tuple.uint64(1)?,
+        })
+    }
+}
+
+/// Calldata of `SetRateLimit`:
+/// `(bytes32 protocolId, uint256 windowSlots, uint256 maxOps, uint256 maxValue, uint256 valueOffset)`.
+/// It is a static tuple so it is encoded in place.
+#[derive(Debug)]
+pub struct RateLimitParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub window_slots: u64,
+    pub max_ops: u64,
+    pub max_value: u128,
+    pub value_offset: u32,
+}
+
+impl<'a> RateLimitParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata);
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            window_slots: tuple.uint64(1)?,
+            max_ops: tuple.uint64(2)?,
+            max_value: tuple.uint128(3)?,
+            value_offset: tuple.uint64(4)?.try_into().map_err(|_| CustomError::InvalidProtoMsg)?,
+        })
+    }
+}
+
+/// Calldata of `SetExclusiveWindow`: `(bytes32 protocolId, uint256 windowSlots)`.
+/// It is a static tuple so it is encoded in place.
+#[derive(Debug)]
+pub struct ExclusiveWindowParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub window_slots: u32,
+}
+
+impl<'a> ExclusiveWindowParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata);
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            window_slots: tuple.uint64(1)?.try_into().map_err(|_| CustomError::InvalidProtoMsg)?,
+        })
+    }
+}
+
+/// Calldata of `SetExecutorReward`: `(bytes32 protocolId, uint256 reward)`.
+/// It is a static tuple so it is encoded in place.
+#[derive(Debug)]
+pub struct ExecutorRewardParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub reward: u64,
+}
+
+impl<'a> ExecutorRewardParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata);
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            reward: tuple.uint64(1)?,
+        })
+    }
+}
+
+/// Calldata of `SetProtocolOwner`:
+/// `(bytes32 protocolId, bytes owner, uint256 maxProposers)`.
+/// The empty owner removes the owner of the protocol.
+#[derive(Debug)]
+pub struct ProtocolOwnerParams<'a> {
+    pub protocol_id: &'a Bytes32,
+    pub owner: &'a [u8],
+    pub max_proposers: u64,
+}
+
+impl<'a> ProtocolOwnerParams<'a> {
+    pub fn decode(calldata: &'a [u8]) -> std::result::Result<Self, CustomError> {
+        let tuple = AbiReader::new(calldata).tuple(0)?;
+        Ok(Self {
+            protocol_id: tuple.bytes32(0)?,
+            owner: tuple.bytes(1)?,
+            max_proposers: tuple.uint64(2)?,
+        })
+    }
+}
+
+/// The limits of a protocol as they are set by `SetRateLimit`, without the state of the window.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct RateLimitSettings {
+    pub window_slots: u64,
+    pub max_ops: u64,
+    pub max_value: u128,
+    pub value_offset: u32,
+}
+
+impl From<&RateLimit> for RateLimitSettings {
+    fn from(rate_limit: &RateLimit) -> Self {
+        Self {
+            window_slots: rate_limit.window_slots(),
+            max_ops: rate_limit.max_ops(),
+            max_value: rate_limit.max_value(),
+            value_offset: rate_limit.value_offset(),
+        }
+    }
+}
+
+/// The protocol owner along with its limits as they are set by `SetProtocolOwner`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct ProtocolOwnerSettings {
+    pub owner: Option<Pubkey>,
+    pub max_proposers: u32,
+}
+
+impl From<&ProtocolInfo> for ProtocolOwnerSettings {
+    fn from(info: &ProtocolInfo) -> Self {
+        Self {
+            owner: info.owner(),
+            max_proposers: info.owner_max_proposers,
+        }
+    }
+}
+
+/// The change of the protocol settings made by a gov operation, there is a variant for every
+/// [GovOperation] carrying the old and the new values of the settings it changes.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum GovChange {
+    AddAllowedProtocol {
+        old_consensus_target_rate: u64,
+        new_consensus_target_rate: u64,
+        old_transmitters: Vec<EthAddress>,
+        new_transmitters: Vec<EthAddress>,
+    },
+    AddAllowedProtocolAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveAllowedProtocolAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddAllowedProposerAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveAllowedProposerAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddExecutor {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveExecutor {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    RemoveTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    UpdateTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    SetConsensusTargetRate {
+        old: u64,
+        new: u64,
+    },
+    SetRateLimit {
+        old: RateLimitSettings,
+        new: RateLimitSettings,
+    },
+    SetExclusiveWindow {
+        old: u32,
+        new: u32,
+    },
+    SetExecutorReward {
+        old: u64,
+        new: u64,
+    },
+    SetProtocolOwner {
+        old: ProtocolOwnerSettings,
+        new: ProtocolOwnerSettings,
+    },
+}
+
+/// Decodes the gov operation from the first four bytes of the code
+pub fn gov_operation_by_code(code: &[u8]) -> std::result::Result<GovOperation, CustomError> {
+    let code: [u8; U32_SIZE] = code
+        .get(..U32_SIZE)
+        .ok_or(CustomError::InvalidMethodSelector)?
+        .try_into()
+        .expect("Checked above");
+    GovOperation::try_from(u32::from_be_bytes(code)).map_err(|_| CustomError::InvalidMethodSelector)
+}
+
 pub(super) fn handle_gov_operation(
     config: &mut Config,
     target_protocol_info: &mut ProtocolInfo,
-    code: Vec<u8>,
+    op_hash: Vec<u8>,
+    gov_operation: GovOperation,
     op_data: &OperationData,
 ) -> Result<()> {
-    if code.len() < U32_SIZE {
-        return Err(CustomError::InvalidMethodSelector.into());
-    }
-    let selector_u32 = u32::from_be_bytes(code[..U32_SIZE].try_into().expect("Checked above"));
-
-    let gov_operation =
-        require_ok!(GovOperation::try_from(selector_u32), CustomError::InvalidMethodSelector);
+    #[cfg(feature = "measure-cu")]
+    log_decoding_compute_units(gov_operation, &op_data.params);
+    let change = apply_gov_operation(gov_operation, config, target_protocol_info, op_data)?;
+    emit!(GovOperationApplied {
+        op_hash,
+        protocol_id: decode_protocol_id(gov_operation, &op_data.params)?.to_vec(),
+        change
+    });
+    Ok(())
+}
 
+/// Applies the gov operation to the target protocol info and returns the change it has made
+fn apply_gov_operation(
+    gov_operation: GovOperation,
+    config: &mut Config,
+    info: &mut ProtocolInfo,
+    op_data: &OperationData,
+) -> Result<GovChange> {
     let calldata = &op_data.params;
-    match gov_operation {
+    Ok(match gov_operation {
         GovOperation::AddAllowedProtocol => {
-            add_allowed_protocol(calldata, target_protocol_info, config)?
+            let (old_consensus_target_rate, old_transmitters) =
+                (info.consensus_target_rate, info.transmitters());
+            add_allowed_protocol(calldata, info, config)?;
+            GovChange::AddAllowedProtocol {
+                old_consensus_target_rate,
+                new_consensus_target_rate: info.consensus_target_rate,
+                old_transmitters,
+                new_transmitters: info.transmitters(),
+            }
         }
         GovOperation::AddAllowedProtocolAddress => {
-            add_allowed_protocol_address(calldata, target_protocol_info)?
+            let old = info.protocol_addresses();
+            add_allowed_protocol_address(calldata, info)?;
+            GovChange::AddAllowedProtocolAddress {
+                old,
+                new: info.protocol_addresses(),
+            }
         }
         GovOperation::RemoveAllowedProtocolAddress => {
-            remove_allowed_protocol_address(target_protocol_info)
+            let old = info.protocol_addresses();
+            remove_allowed_protocol_address(calldata, info)?;
+            GovChange::RemoveAllowedProtocolAddress {
+                old,
+                new: info.protocol_addresses(),
+            }
         }
         GovOperation::AddAllowedProposerAddress => {
-            add_allowed_proposer_address(calldata, target_protocol_info)?
+            let old = info.proposers();
+            add_allowed_proposer_address(calldata, info)?;
+            GovChange::AddAllowedProposerAddress {
+                old,
+                new: info.proposers(),
+            }
         }
         GovOperation::RemoveAllowedProposerAddress => {
-            remove_allowed_proposer_address(calldata, target_protocol_info)?
+            let old = info.proposers();
+            remove_allowed_proposer_address(calldata, info)?;
+            GovChange::RemoveAllowedProposerAddress {
+                old,
+                new: info.proposers(),
+            }
+        }
+        GovOperation::AddExecutor => {
+            let old = info.executors();
+            add_executor(calldata, info)?;
+            GovChange::AddExecutor {
+                old,
+                new: info.executors(),
+            }
         }
-        GovOperation::AddExecutor => add_executor(calldata, target_protocol_info)?,
         GovOperation::RemoveExecutor => {
-            remove_executor(calldata, &op_data.protocol_id, target_protocol_info)?
+            let old = info.executors();
+            remove_executor(calldata, &op_data.protocol_id, info)?;
+            GovChange::RemoveExecutor {
+                old,
+                new: info.executors(),
+            }
+        }
+        GovOperation::AddTransmitters => {
+            let old = info.transmitters();
+            add_transmitters(calldata, info)?;
+            GovChange::AddTransmitters {
+                old,
+                new: info.transmitters(),
+            }
+        }
+        GovOperation::RemoveTransmitters => {
+            let old = info.transmitters();
+            remove_transmitters(calldata, info)?;
+            GovChange::RemoveTransmitters {
+                old,
+                new: info.transmitters(),
+            }
+        }
+        GovOperation::UpdateTransmitters => {
+            let old = info.transmitters();
+            update_transmitters(calldata, info)?;
+            GovChange::UpdateTransmitters {
+                old,
+                new: info.transmitters(),
+            }
         }
-        GovOperation::AddTransmitters => add_transmitters(calldata, target_protocol_info)?,
-        GovOperation::RemoveTransmitters => remove_transmitters(calldata, target_protocol_info)?,
-        GovOperation::UpdateTransmitters => update_transmitters(calldata, target_protocol_info)?,
         GovOperation::SetConsensusTargetRate => {
-            set_consensus_target_rate(calldata, target_protocol_info)?
+            let old = info.consensus_target_rate;
+            set_consensus_target_rate(calldata, info)?;
+            GovChange::SetConsensusTargetRate {
+                old,
+                new: info.consensus_target_rate,
+            }
         }
-    }
-    Ok(())
+        GovOperation::SetRateLimit => {
+            let old = RateLimitSettings::from(info.rate_limit());
+            set_rate_limit(calldata, info)?;
+            GovChange::SetRateLimit {
+                old,
+                new: info.rate_limit().into(),
+            }
+        }
+        GovOperation::SetExclusiveWindow => {
+            let old = info.exclusive_window_slots;
+            set_exclusive_window(calldata, info)?;
+            GovChange::SetExclusiveWindow {
+                old,
+                new: info.exclusive_window_slots,
+            }
+        }
+        GovOperation::SetExecutorReward => {
+            let old = info.executor_reward;
+            set_executor_reward(calldata, info)?;
+            GovChange::SetExecutorReward {
+                old,
+                new: info.executor_reward,
+            }
+        }
+        GovOperation::SetProtocolOwner => {
+            let old = ProtocolOwnerSettings::from(&*info);
+            set_protocol_owner(calldata, info)?;
+            GovChange::SetProtocolOwner {
+                old,
+                new: ProtocolOwnerSettings::from(&*info),
+            }
+        }
+    })
 }
 
 pub(super) fn add_allowed_protocol(
@@ -128,98 +541,99 @@ pub(super) fn add_allowed_protocol(
     target_protocol_info: &mut ProtocolInfo,
     config: &mut Config,
 ) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32),                      // protocolId
-            ParamType::Uint(256),                           // consensusTargetRate
-            ParamType::Array(Box::new(ParamType::Address)), // transmitters
-        ]),
-    )?;
-
-    let consensus_target_rate =
-        params[1].clone().into_uint().ok_or(CustomError::InvalidGovMsg)?.as_u64();
-
-    check_consensus_target_rate(consensus_target_rate)?;
-
-    let transmitters: Vec<ethabi::Address> = params[2]
-        .clone()
-        .into_array()
-        .ok_or(CustomError::InvalidGovMsg)?
-        .into_iter()
-        .map(|x| x.into_address().expect("always address"))
-        .collect();
-    target_protocol_info.is_init = true;
-    target_protocol_info.consensus_target_rate = consensus_target_rate;
-    for (i, k) in transmitters.into_iter().enumerate() {
-        target_protocol_info.transmitters[i] = k.into();
+    let params = AddAllowedProtocolParams::decode(calldata)?;
+    check_consensus_target_rate(params.consensus_target_rate)?;
+    require!(params.transmitters.len() <= MAX_TRANSMITTERS, CustomError::MaxTransmittersExceeded);
+
+    target_protocol_info.is_init = 1;
+    target_protocol_info.consensus_target_rate = params.consensus_target_rate;
+    for (i, k) in params.transmitters.iter().enumerate() {
+        target_protocol_info.transmitters[i] = k;
     }
-    propose_handle_add_allowed_protocol(params, config)?;
+    propose_handle_add_allowed_protocol(params.protocol_id, config);
     Ok(())
 }
 
-fn propose_handle_add_allowed_protocol(params: Vec<Token>, config: &mut Config) -> Result<()> {
+fn propose_handle_add_allowed_protocol(protocol_id: &Bytes32, config: &mut Config) {
     let nonce = config.nonce;
     config.nonce += 1;
-    let mut function_selector = vec![0_u8, 32];
-    function_selector.extend_from_slice(&ethabi::encode(&[Token::FixedBytes(
-        HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR.to_vec(),
-    )]));
-    let protocol_id = params[0].clone().into_fixed_bytes().ok_or(CustomError::InvalidGovMsg)?;
-    let params = ethabi::encode(&[Token::Tuple(vec![
-        Token::FixedBytes(protocol_id),
-        Token::Uint(U256::from(SOLANA_CHAIN_ID)),
-    ])]);
+    // ABI encoding of the bytes32 selector and of the static `(bytes32, uint256)` tuple
+    // is just a concatenation of words
+    let function_selector = [&[0_u8, 32][..], HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR].concat();
+    let params = [&protocol_id[..], &u128_to_bytes32(config.chain_id)].concat();
     emit!(ProposeEvent {
         protocol_id: GOV_PROTOCOL_ID.to_vec(),
         nonce,
         dst_chain_id: config.eob_chain_id as u128,
         protocol_address: config.eob_master_smart_contract.to_vec(),
         function_selector,
-        params
+        params,
+        // The gov protocol proposes by itself, so the sender is the photon program
+        proposer: crate::ID,
+        caller_program: None
     });
-    Ok(())
 }
 
 fn add_allowed_protocol_address(calldata: &[u8], protocol_info: &mut ProtocolInfo) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // protocolAddr
-        ]),
-    )?;
-    let protocol_address = params[1].clone().into_bytes().ok_or(CustomError::InvalidGovMsg)?;
-    protocol_info.protocol_address = Pubkey::new_from_array(
-        protocol_address.try_into().map_err(|_| CustomError::InvalidGovMsg)?,
-    );
+    let protocol_address = AddressParams::decode(calldata)?.pubkey()?;
+    let mut protocol_addresses = protocol_info.protocol_addresses();
+
+    if protocol_address == Pubkey::default() {
+        return Err(CustomError::InvalidProtocolAddress.into());
+    }
+
+    if protocol_addresses.contains(&protocol_address) {
+        return Err(CustomError::ProtocolAddressIsAlreadyAllowed.into());
+    }
+
+    if protocol_addresses.len() >= MAX_PROTOCOL_ADDRESSES {
+        return Err(CustomError::MaxProtocolAddressesExceeded.into());
+    }
+
+    protocol_addresses.push(protocol_address);
+    set_protocol_addresses(protocol_addresses, protocol_info);
     Ok(())
 }
 
-fn remove_allowed_protocol_address(target_protocol_info: &mut ProtocolInfo) {
+fn remove_allowed_protocol_address(
+    calldata: &[u8],
+    target_protocol_info: &mut ProtocolInfo,
+) -> Result<()> {
+    let protocol_address = AddressParams::decode(calldata)?.pubkey()?;
+    let protocol_addresses: Vec<_> = target_protocol_info
+        .protocol_addresses()
+        .into_iter()
+        .filter(|x| x != &protocol_address)
+        .collect();
+    set_protocol_addresses(protocol_addresses, target_protocol_info);
+    Ok(())
+}
+
+fn set_protocol_addresses(
+    protocol_addresses: Vec<Pubkey>,
+    target_protocol_info: &mut ProtocolInfo,
+) {
     target_protocol_info.protocol_address = Pubkey::default();
+    target_protocol_info.extra_protocol_addresses = Default::default();
+    for (i, k) in protocol_addresses.into_iter().enumerate() {
+        match i {
+            0 => target_protocol_info.protocol_address = k,
+            _ => target_protocol_info.extra_protocol_addresses[i - 1] = k,
+        }
+    }
 }
 
 fn add_allowed_proposer_address(
     calldata: &[u8],
     target_protocol_info: &mut ProtocolInfo,
 ) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // proposerAddr
-        ]),
-    )?;
-
-    let proposer = Pubkey::new_from_array(
-        params[1]
-            .clone()
-            .into_bytes()
-            .ok_or(CustomError::InvalidGovMsg)?
-            .try_into()
-            .map_err(|_| CustomError::InvalidGovMsg)?,
-    );
+    add_proposer(AddressParams::decode(calldata)?.pubkey()?, target_protocol_info)
+}
+
+pub(super) fn add_proposer(
+    proposer: Pubkey,
+    target_protocol_info: &mut ProtocolInfo,
+) -> Result<()> {
     let mut proposers: Vec<_> = target_protocol_info.proposers();
 
     if proposer == Pubkey::default() {
@@ -247,48 +661,21 @@ fn remove_allowed_proposer_address(
     calldata: &[u8],
     target_protocol_info: &mut ProtocolInfo,
 ) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // proposerAddr
-        ]),
-    )?;
-
-    let proposer = Pubkey::new_from_array(
-        params[1]
-            .clone()
-            .into_bytes()
-            .ok_or(CustomError::InvalidGovMsg)?
-            .try_into()
-            .map_err(|_| CustomError::InvalidGovMsg)?,
-    );
+    remove_proposer(AddressParams::decode(calldata)?.pubkey()?, target_protocol_info);
+    Ok(())
+}
+
+pub(super) fn remove_proposer(proposer: Pubkey, target_protocol_info: &mut ProtocolInfo) {
     let proposers: Vec<_> =
         target_protocol_info.proposers().into_iter().filter(|x| x != &proposer).collect();
     target_protocol_info.proposers = Default::default();
     for (i, k) in proposers.into_iter().enumerate() {
         target_protocol_info.proposers[i] = k;
     }
-    Ok(())
 }
 
 fn add_executor(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // executor
-        ]),
-    )?;
-
-    let executor = Pubkey::new_from_array(
-        params[1]
-            .clone()
-            .into_bytes()
-            .ok_or(CustomError::InvalidGovMsg)?
-            .try_into()
-            .map_err(|_| CustomError::InvalidGovMsg)?,
-    );
+    let executor = AddressParams::decode(calldata)?.pubkey()?;
 
     if executor == Pubkey::default() {
         return Err(CustomError::InvalidExecutorAddress.into());
@@ -315,22 +702,7 @@ fn remove_executor(
     protocol_id: &[u8],
     target_protocol_info: &mut ProtocolInfo,
 ) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // executor
-        ]),
-    )?;
-
-    let executor = Pubkey::new_from_array(
-        params[1]
-            .clone()
-            .into_bytes()
-            .ok_or(CustomError::InvalidGovMsg)?
-            .try_into()
-            .map_err(|_| CustomError::InvalidGovMsg)?,
-    );
+    let executor = AddressParams::decode(calldata)?.pubkey()?;
     let executors: Vec<_> =
         target_protocol_info.executors().into_iter().filter(|x| x != &executor).collect();
 
@@ -346,21 +718,14 @@ fn remove_executor(
 }
 
 fn add_transmitters(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
-    let params = decode_abi_params(calldata, abi_decode_scheme(GovOperation::AddTransmitters))?;
-    let transmitters = get_transmitters_to_add(&params[1])?;
+    let params = TransmittersParams::decode(calldata)?;
+    let transmitters = get_transmitters_to_add(params.transmitters);
     require!(!transmitters.is_empty(), CustomError::NoTransmittersAllowed);
     add_transmitters_impl(transmitters, target_protocol_info)
 }
 
-fn get_transmitters_to_add(params: &Token) -> Result<Vec<EthAddress>> {
-    Ok(params
-        .clone()
-        .into_array()
-        .ok_or(CustomError::InvalidGovMsg)?
-        .into_iter()
-        .filter_map(|x| x.into_address().map(|x| x.to_fixed_bytes()))
-        .filter(|x| x != &EthAddress::default())
-        .collect())
+fn get_transmitters_to_add(transmitters: AbiAddresses) -> Vec<EthAddress> {
+    transmitters.iter().filter(|x| x != &EthAddress::default()).collect()
 }
 
 fn add_transmitters_impl(
@@ -381,27 +746,16 @@ fn add_transmitters_impl(
 }
 
 fn remove_transmitters(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
-    let params = decode_abi_params(calldata, abi_decode_scheme(GovOperation::RemoveTransmitters))?;
-    let to_remove = get_transmitters_to_remove(&params[1])?;
-    remove_transmitters_impl(to_remove, target_protocol_info);
+    let params = TransmittersParams::decode(calldata)?;
+    remove_transmitters_impl(params.transmitters, target_protocol_info);
     Ok(())
 }
 
-fn get_transmitters_to_remove(params: &Token) -> std::result::Result<Vec<EthAddress>, CustomError> {
-    params
-        .clone()
-        .into_array()
-        .ok_or(CustomError::InvalidGovMsg)?
-        .into_iter()
-        .map(|x| x.into_address().map(|x| x.to_fixed_bytes()).ok_or(CustomError::InvalidGovMsg))
-        .collect()
-}
-
-fn remove_transmitters_impl(to_remove: Vec<EthAddress>, target_protocol_info: &mut ProtocolInfo) {
+fn remove_transmitters_impl(to_remove: AbiAddresses, target_protocol_info: &mut ProtocolInfo) {
     let total_transmitters: Vec<_> = target_protocol_info
         .transmitters()
         .into_iter()
-        .filter(|x| !to_remove.contains(x))
+        .filter(|x| !to_remove.iter().any(|r| &r == x))
         .collect();
     target_protocol_info.transmitters = Default::default();
     for (i, k) in total_transmitters.into_iter().enumerate() {
@@ -410,14 +764,13 @@ fn remove_transmitters_impl(to_remove: Vec<EthAddress>, target_protocol_info: &m
 }
 
 fn update_transmitters(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
-    let params = decode_abi_params(calldata, abi_decode_scheme(GovOperation::UpdateTransmitters))?;
+    let params = UpdateTransmittersParams::decode(calldata)?;
 
-    let to_remove = get_transmitters_to_remove(&params[2])?;
-    if !to_remove.is_empty() {
-        remove_transmitters_impl(to_remove, target_protocol_info);
+    if !params.to_remove.is_empty() {
+        remove_transmitters_impl(params.to_remove, target_protocol_info);
     }
 
-    let to_add: Vec<EthAddress> = get_transmitters_to_add(&params[1])?;
+    let to_add: Vec<EthAddress> = get_transmitters_to_add(params.to_add);
     if !to_add.is_empty() {
         add_transmitters_impl(to_add, target_protocol_info)?;
     }
@@ -429,18 +782,44 @@ fn set_consensus_target_rate(
     calldata: &[u8],
     target_protocol_info: &mut ProtocolInfo,
 ) -> Result<()> {
-    let params = decode_abi_params(
-        calldata,
-        ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Uint(256),      // target rate
-        ]),
-    )?;
-
-    let consensus_target_rate =
-        params[1].clone().into_uint().ok_or(CustomError::InvalidGovMsg)?.as_u64();
-    check_consensus_target_rate(consensus_target_rate)?;
-    target_protocol_info.consensus_target_rate = consensus_target_rate;
+    let params = ConsensusTargetRateParams::decode(calldata)?;
+    check_consensus_target_rate(params.consensus_target_rate)?;
+    target_protocol_info.consensus_target_rate = params.consensus_target_rate;
+    Ok(())
+}
+
+/// Replaces the limits of the protocol, the state of the window is reset as well
+fn set_rate_limit(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
+    let params = RateLimitParams::decode(calldata)?;
+    target_protocol_info.rate_limit =
+        RateLimit::new(params.window_slots, params.max_ops, params.max_value, params.value_offset);
+    Ok(())
+}
+
+/// Zero window disables the assignment of executors to operations
+fn set_exclusive_window(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
+    let params = ExclusiveWindowParams::decode(calldata)?;
+    target_protocol_info.exclusive_window_slots = params.window_slots;
+    Ok(())
+}
+
+/// The reward is paid in lamports out of the protocol gas vault along with the rent reimbursement
+fn set_executor_reward(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
+    let params = ExecutorRewardParams::decode(calldata)?;
+    target_protocol_info.executor_reward = params.reward;
+    Ok(())
+}
+
+/// The owner limit can't exceed the capacity of the protocol info, the gov protocol has no owner
+fn set_protocol_owner(calldata: &[u8], target_protocol_info: &mut ProtocolInfo) -> Result<()> {
+    let params = ProtocolOwnerParams::decode(calldata)?;
+    require!(params.protocol_id != GOV_PROTOCOL_ID, CustomError::ProtocolOwnerNotAllowed);
+    require!(params.max_proposers <= MAX_PROPOSERS as u64, CustomError::MaxProposersExceeded);
+    target_protocol_info.owner = match params.owner.len() {
+        0 => Pubkey::default(),
+        _ => Pubkey::try_from(params.owner).map_err(|_| CustomError::InvalidGovMsg)?,
+    };
+    target_protocol_info.owner_max_proposers = params.max_proposers as u32;
     Ok(())
 }
 
@@ -455,101 +834,659 @@ fn check_consensus_target_rate(consensus_target_rate: u64) -> Result<()> {
     Ok(())
 }
 
-pub(super) fn target_protocol(function_selector: &FunctionSelector, params: &[u8]) -> Vec<u8> {
-    let FunctionSelector::ByCode(code) = function_selector else {
-        panic!("Unexpected function selector");
-    };
-
-    let Ok(target_protocol) = target_protocol_by_code(code, params) else {
-        panic!("Failed to get target_protocol");
-    };
-    target_protocol
+/// Resolves the target protocol id while validating the `ReceivePhotonMsg` accounts.
+/// Malformed data yields the zeroed id, the operation itself is rejected later by the handler
+/// decoding the same calldata.
+pub(super) fn target_protocol(code: &[u8], params: &[u8]) -> Bytes32 {
+    target_protocol_by_code(code, params).copied().unwrap_or_default()
 }
 
-pub(super) fn target_protocol_by_code(
+/// Commonly used in the `gov-extension` to get the target protocol id from encoded `calldata`
+/// based on the gov operation `code`.
+pub fn target_protocol_by_code<'a>(
     code: &[u8],
-    params: &[u8],
-) -> std::result::Result<Vec<u8>, String> {
-    if code.len() < U32_SIZE {
-        return Err("Selector to short".to_string());
-    }
-    let selector_u32 =
-        u32::from_be_bytes(code[..U32_SIZE].try_into().map_err(|_| "Checked above".to_string())?);
-    let gov_operation = GovOperation::try_from(selector_u32)
-        .map_err(|_| "Failed to get gov_operation from selector".to_string())?;
-    let params = decode_abi_params(params, abi_decode_scheme(gov_operation))
-        .map_err(|_| "Failed to decode abi params".to_string())?;
-    params
-        .first()
-        .ok_or_else(|| "Failed to get first decoded abi param".to_string())?
-        .clone()
-        .into_fixed_bytes()
-        .ok_or_else(|| "Failed to convert first decoded abi param as fixed_bytes".to_string())
-}
-
-/// Commonly used in the `gov-extension` to extract accounts from encoded `calldata` based on the `param_type`.
-pub fn decode_abi_params(calldata: &[u8], param_type: ParamType) -> Result<Vec<Token>> {
-    let decoded =
-        ethabi::decode(&[param_type], calldata).map_err(|_| CustomError::InvalidProtoMsg)?;
-
-    Ok(decoded
-        .first()
-        .ok_or(CustomError::InvalidProtoMsg)?
-        .clone()
-        .into_tuple()
-        .ok_or(CustomError::InvalidProtoMsg)?)
-}
-
-/// A shortcut to retrieve the decoding schema based on the provided GovOperation.
-pub fn abi_decode_scheme(gov_operation: GovOperation) -> ParamType {
+    params: &'a [u8],
+) -> std::result::Result<&'a Bytes32, CustomError> {
+    decode_protocol_id(gov_operation_by_code(code)?, params)
+}
+
+/// Decodes the calldata of the given gov operation and returns the id of the protocol it targets.
+pub fn decode_protocol_id(
+    gov_operation: GovOperation,
+    calldata: &[u8],
+) -> std::result::Result<&Bytes32, CustomError> {
+    Ok(match gov_operation {
+        GovOperation::AddAllowedProtocol => AddAllowedProtocolParams::decode(calldata)?.protocol_id,
+        GovOperation::AddAllowedProtocolAddress
+        | GovOperation::RemoveAllowedProtocolAddress
+        | GovOperation::AddAllowedProposerAddress
+        | GovOperation::RemoveAllowedProposerAddress
+        | GovOperation::AddExecutor
+        | GovOperation::RemoveExecutor => AddressParams::decode(calldata)?.protocol_id,
+        GovOperation::AddTransmitters | GovOperation::RemoveTransmitters => {
+            TransmittersParams::decode(calldata)?.protocol_id
+        }
+        GovOperation::UpdateTransmitters => UpdateTransmittersParams::decode(calldata)?.protocol_id,
+        GovOperation::SetConsensusTargetRate => {
+            ConsensusTargetRateParams::decode(calldata)?.protocol_id
+        }
+        GovOperation::SetRateLimit => RateLimitParams::decode(calldata)?.protocol_id,
+        GovOperation::SetExclusiveWindow => ExclusiveWindowParams::decode(calldata)?.protocol_id,
+        GovOperation::SetExecutorReward => ExecutorRewardParams::decode(calldata)?.protocol_id,
+        GovOperation::SetProtocolOwner => ProtocolOwnerParams::decode(calldata)?.protocol_id,
+    })
+}
+
+/// Returns the ABI types of the gov operation calldata, the way it was decoded by `ethabi` before the
+/// ABI reader
+#[cfg(any(test, feature = "measure-cu"))]
+fn abi_decode_scheme(gov_operation: GovOperation) -> Vec<ethabi::ParamType> {
+    use ethabi::ParamType;
+    let addresses = || ParamType::Array(Box::new(ParamType::Address));
     match gov_operation {
         GovOperation::AddAllowedProtocol => {
-            ParamType::Tuple(vec![
-                ParamType::FixedBytes(32), // protocolId
-                ParamType::Uint(256),      // consensusTargetRate
-                ParamType::Array(Box::new(ParamType::Address)),
-            ])
-        }
-        GovOperation::AddAllowedProtocolAddress => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // protocolAddr
-        ]),
-        GovOperation::RemoveAllowedProtocolAddress => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // protocolAddr
-        ]),
-        GovOperation::AddAllowedProposerAddress => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // proposerAddr
-        ]),
-        GovOperation::RemoveAllowedProposerAddress => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // proposerAddr
-        ]),
-        GovOperation::AddExecutor => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // executor
-        ]),
-        GovOperation::RemoveExecutor => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Bytes,          // executor
-        ]),
-        GovOperation::AddTransmitters => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32),                      // protocolId
-            ParamType::Array(Box::new(ParamType::Address)), // transmitters
-        ]),
-        GovOperation::RemoveTransmitters => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32),                      // protocolId
-            ParamType::Array(Box::new(ParamType::Address)), // transmitters
-        ]),
-        GovOperation::UpdateTransmitters => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32),                      // protocolId
-            ParamType::Array(Box::new(ParamType::Address)), // transmitters to add
-            ParamType::Array(Box::new(ParamType::Address)), // transmitters to remove
-        ]),
-        GovOperation::SetConsensusTargetRate => ParamType::Tuple(vec![
-            ParamType::FixedBytes(32), // protocolId
-            ParamType::Uint(256),      // target rate
-        ]),
+            vec![ParamType::FixedBytes(32), ParamType::Uint(256), addresses()]
+        }
+        GovOperation::AddAllowedProtocolAddress
+        | GovOperation::RemoveAllowedProtocolAddress
+        | GovOperation::AddAllowedProposerAddress
+        | GovOperation::RemoveAllowedProposerAddress
+        | GovOperation::AddExecutor
+        | GovOperation::RemoveExecutor => vec![ParamType::FixedBytes(32), ParamType::Bytes],
+        GovOperation::AddTransmitters | GovOperation::RemoveTransmitters => {
+            vec![ParamType::FixedBytes(32), addresses()]
+        }
+        GovOperation::UpdateTransmitters => {
+            vec![ParamType::FixedBytes(32), addresses(), addresses()]
+        }
+        GovOperation::SetConsensusTargetRate
+        | GovOperation::SetExclusiveWindow
+        | GovOperation::SetExecutorReward => {
+            vec![ParamType::FixedBytes(32), ParamType::Uint(256)]
+        }
+        GovOperation::SetRateLimit => vec![
+            ParamType::FixedBytes(32),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+        ],
+        GovOperation::SetProtocolOwner => {
+            vec![
+                ParamType::FixedBytes(32),
+                ParamType::Bytes,
+                ParamType::Uint(256),
+            ]
+        }
+    }
+}
+
+/// Logs the compute units left before and after decoding the gov operation calldata by `ethabi`,
+/// as it was decoded before the ABI reader, and then by the reader. The difference of each pair
+/// less the cost of the log call itself is the cost of the decoding, see the `abi` module.
+#[cfg(feature = "measure-cu")]
+fn log_decoding_compute_units(gov_operation: GovOperation, calldata: &[u8]) {
+    use anchor_lang::solana_program::log::sol_log_compute_units;
+    msg!("Decoding {:?} by ethabi", gov_operation);
+    sol_log_compute_units();
+    let tokens =
+        ethabi::decode(&[ethabi::ParamType::Tuple(abi_decode_scheme(gov_operation))], calldata)
+            .ok()
+            .and_then(|x| x.first().cloned())
+            .and_then(ethabi::Token::into_tuple);
+    sol_log_compute_units();
+    std::hint::black_box(tokens);
+    msg!("Decoding {:?} by the ABI reader", gov_operation);
+    sol_log_compute_units();
+    let protocol_id = decode_protocol_id(gov_operation, calldata);
+    sol_log_compute_units();
+    std::hint::black_box(protocol_id.ok());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{protocol_data::FunctionSelector, util::SplitMix64};
+    use bytemuck::Zeroable;
+    use ethabi::{ParamType, Token, Uint};
+
+    fn random_uint(rng: &mut SplitMix64) -> Token {
+        let mut word = [0_u8; 32];
+        match rng.below(3) {
+            0 => word[24..].copy_from_slice(&rng.next().to_be_bytes()),
+            1 => word[24..]
+                .copy_from_slice(&(rng.below(RATE_DECIMALS as usize) as u64).to_be_bytes()),
+            _ => word.copy_from_slice(&rng.bytes(32)),
+        }
+        Token::Uint(Uint::from_big_endian(&word))
+    }
+
+    fn random_addresses(rng: &mut SplitMix64) -> Token {
+        let len = rng.below(MAX_TRANSMITTERS + 5);
+        Token::Array(
+            (0..len).map(|_| Token::Address(ethabi::Address::from_slice(&rng.bytes(20)))).collect(),
+        )
+    }
+
+    fn random_bytes32(rng: &mut SplitMix64) -> Token {
+        Token::FixedBytes(rng.bytes(32))
+    }
+
+    fn random_bytes(rng: &mut SplitMix64) -> Token {
+        let len = rng.below(70);
+        Token::Bytes(rng.bytes(len))
+    }
+
+    fn mutate(rng: &mut SplitMix64, data: &[u8]) -> Vec<u8> {
+        let mut data = data.to_vec();
+        match rng.below(4) {
+            0 => data.truncate(rng.below(data.len() + 1)),
+            1 if !data.is_empty() => {
+                let pos = rng.below(data.len());
+                data[pos] = rng.next() as u8;
+            }
+            2 if data.len() >= 32 => {
+                // Offsets and lengths are the most interesting spots to corrupt
+                let pos = rng.below(data.len() / 32) * 32;
+                let value = rng.below(data.len() + 64) as u64;
+                data[pos..pos + 32].fill(0);
+                data[pos + 24..pos + 32].copy_from_slice(&value.to_be_bytes());
+            }
+            _ => {
+                let len = rng.below(64);
+                data.extend(rng.bytes(len))
+            }
+        }
+        data
+    }
+
+    fn reference_decode(data: &[u8], types: Vec<ParamType>) -> Option<Vec<Token>> {
+        let tokens = ethabi::decode(&[ParamType::Tuple(types)], data).ok()?;
+        tokens.into_iter().next()?.into_tuple()
+    }
+
+    fn reference_u64(token: &Token) -> Option<u64> {
+        let value = token.clone().into_uint().expect("Expected uint");
+        (value <= Uint::from(u64::MAX)).then(|| value.as_u64())
+    }
+
+    fn reference_addresses(token: &Token) -> Vec<EthAddress> {
+        token
+            .clone()
+            .into_array()
+            .expect("Expected array")
+            .into_iter()
+            .map(|x| x.into_address().expect("Expected address").to_fixed_bytes())
+            .collect()
+    }
+
+    fn check_add_allowed_protocol(data: &[u8]) {
+        let types = vec![
+            ParamType::FixedBytes(32),
+            ParamType::Uint(256),
+            ParamType::Array(Box::new(ParamType::Address)),
+        ];
+        let decoded = AddAllowedProtocolParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let Some(rate) = reference_u64(&tokens[1]) else {
+            assert!(decoded.is_err(), "Expected u64 overflow for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.consensus_target_rate, rate);
+        assert_eq!(
+            decoded.transmitters.iter().collect::<Vec<_>>(),
+            reference_addresses(&tokens[2])
+        );
+        let code = (GovOperation::AddAllowedProtocol as u32).to_be_bytes();
+        assert!(matches!(target_protocol_by_code(&code, data), Ok(x) if x == decoded.protocol_id));
+    }
+
+    fn check_address(data: &[u8]) {
+        let types = vec![ParamType::FixedBytes(32), ParamType::Bytes];
+        let decoded = AddressParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.address.to_vec(), tokens[1].clone().into_bytes().unwrap());
+    }
+
+    fn check_transmitters(data: &[u8]) {
+        let types = vec![
+            ParamType::FixedBytes(32),
+            ParamType::Array(Box::new(ParamType::Address)),
+        ];
+        let decoded = TransmittersParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(
+            decoded.transmitters.iter().collect::<Vec<_>>(),
+            reference_addresses(&tokens[1])
+        );
+    }
+
+    fn check_update_transmitters(data: &[u8]) {
+        let types = vec![
+            ParamType::FixedBytes(32),
+            ParamType::Array(Box::new(ParamType::Address)),
+            ParamType::Array(Box::new(ParamType::Address)),
+        ];
+        let decoded = UpdateTransmittersParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.to_add.iter().collect::<Vec<_>>(), reference_addresses(&tokens[1]));
+        assert_eq!(decoded.to_remove.iter().collect::<Vec<_>>(), reference_addresses(&tokens[2]));
+    }
+
+    fn check_consensus_target_rate(data: &[u8]) {
+        let types = vec![ParamType::FixedBytes(32), ParamType::Uint(256)];
+        let decoded = ConsensusTargetRateParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let Some(rate) = reference_u64(&tokens[1]) else {
+            assert!(decoded.is_err(), "Expected u64 overflow for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.consensus_target_rate, rate);
+    }
+
+    fn check_rate_limit(data: &[u8]) {
+        let types = vec![
+            ParamType::FixedBytes(32),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+        ];
+        let decoded = RateLimitParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let uint = |i: usize| tokens[i].clone().into_uint().expect("Expected uint");
+        let (Some(window_slots), Some(max_ops)) =
+            (reference_u64(&tokens[1]), reference_u64(&tokens[2]))
+        else {
+            assert!(decoded.is_err(), "Expected u64 overflow for {}", hex::encode(data));
+            return;
+        };
+        if uint(3) > Uint::from(u128::MAX) || uint(4) > Uint::from(u32::MAX) {
+            assert!(decoded.is_err(), "Expected overflow for {}", hex::encode(data));
+            return;
+        }
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.window_slots, window_slots);
+        assert_eq!(decoded.max_ops, max_ops);
+        assert_eq!(decoded.max_value, uint(3).as_u128());
+        assert_eq!(decoded.value_offset, uint(4).as_u32());
+    }
+
+    fn check_exclusive_window(data: &[u8]) {
+        let types = vec![ParamType::FixedBytes(32), ParamType::Uint(256)];
+        let decoded = ExclusiveWindowParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let window_slots = tokens[1].clone().into_uint().expect("Expected uint");
+        if window_slots > Uint::from(u32::MAX) {
+            assert!(decoded.is_err(), "Expected u32 overflow for {}", hex::encode(data));
+            return;
+        }
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.window_slots, window_slots.as_u32());
+    }
+
+    fn check_executor_reward(data: &[u8]) {
+        let types = vec![ParamType::FixedBytes(32), ParamType::Uint(256)];
+        let decoded = ExecutorRewardParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let Some(reward) = reference_u64(&tokens[1]) else {
+            assert!(decoded.is_err(), "Expected u64 overflow for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.reward, reward);
+    }
+
+    fn check_protocol_owner(data: &[u8]) {
+        let types = vec![
+            ParamType::FixedBytes(32),
+            ParamType::Bytes,
+            ParamType::Uint(256),
+        ];
+        let decoded = ProtocolOwnerParams::decode(data);
+        let Some(tokens) = reference_decode(data, types) else {
+            assert!(decoded.is_err(), "Expected error for {}", hex::encode(data));
+            return;
+        };
+        let Some(max_proposers) = reference_u64(&tokens[2]) else {
+            assert!(decoded.is_err(), "Expected u64 overflow for {}", hex::encode(data));
+            return;
+        };
+        let decoded = decoded.expect("Expected to be decoded as by ethabi");
+        assert_eq!(decoded.protocol_id.to_vec(), tokens[0].clone().into_fixed_bytes().unwrap());
+        assert_eq!(decoded.owner.to_vec(), tokens[1].clone().into_bytes().unwrap());
+        assert_eq!(decoded.max_proposers, max_proposers);
+    }
+
+    type Sample = (Vec<Token>, fn(&[u8]));
+
+    #[test]
+    fn test_fuzz_against_ethabi() {
+        let mut rng = SplitMix64(0x5eed);
+        for _ in 0..2000 {
+            let protocol_id = random_bytes32(&mut rng);
+            let samples: [Sample; 9] = [
+                (
+                    vec![
+                        protocol_id.clone(),
+                        random_uint(&mut rng),
+                        random_addresses(&mut rng),
+                    ],
+                    check_add_allowed_protocol,
+                ),
+                (vec![protocol_id.clone(), random_bytes(&mut rng)], check_address),
+                (vec![protocol_id.clone(), random_addresses(&mut rng)], check_transmitters),
+                (
+                    vec![
+                        protocol_id.clone(),
+                        random_addresses(&mut rng),
+                        random_addresses(&mut rng),
+                    ],
+                    check_update_transmitters,
+                ),
+                (vec![protocol_id.clone(), random_uint(&mut rng)], check_consensus_target_rate),
+                (vec![protocol_id.clone(), random_uint(&mut rng)], check_exclusive_window),
+                (vec![protocol_id.clone(), random_uint(&mut rng)], check_executor_reward),
+                (
+                    vec![
+                        protocol_id.clone(),
+                        random_bytes(&mut rng),
+                        random_uint(&mut rng),
+                    ],
+                    check_protocol_owner,
+                ),
+                (
+                    vec![
+                        protocol_id,
+                        random_uint(&mut rng),
+                        random_uint(&mut rng),
+                        random_uint(&mut rng),
+                        random_uint(&mut rng),
+                    ],
+                    check_rate_limit,
+                ),
+            ];
+            for (tokens, check) in samples {
+                let encoded = ethabi::encode(&[Token::Tuple(tokens)]);
+                check(&encoded);
+                let mut mutated = encoded;
+                for _ in 0..16 {
+                    mutated = mutate(&mut rng, &mutated);
+                    check(&mutated);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_target_protocol() {
+        let code = GovOperation::AddExecutor as u32;
+        assert!(matches!(
+            target_protocol_by_code(&code.to_be_bytes(), &[0; 16]),
+            Err(CustomError::InvalidProtoMsg)
+        ));
+        assert!(matches!(
+            target_protocol_by_code(&[0xde, 0xad, 0xbe, 0xef], &[]),
+            Err(CustomError::InvalidMethodSelector)
+        ));
+        assert!(matches!(
+            target_protocol_by_code(&[0x45], &[]),
+            Err(CustomError::InvalidMethodSelector)
+        ));
+        assert_eq!(target_protocol(b"add_executor", &[]), Bytes32::default());
+        assert_eq!(target_protocol(&code.to_be_bytes(), &[1; 64]), Bytes32::default());
+    }
+
+    #[test]
+    fn test_gov_change() {
+        let mut config = Config::default();
+        let mut info = ProtocolInfo::zeroed();
+        let executor = Pubkey::new_unique();
+        let op_data = |code: GovOperation, params: Vec<Token>| OperationData {
+            protocol_id: GOV_PROTOCOL_ID.to_vec(),
+            function_selector: FunctionSelector::ByCode((code as u32).to_be_bytes().to_vec()),
+            params: ethabi::encode(&[Token::Tuple(params)]),
+            ..Default::default()
+        };
+        let protocol_id = Token::FixedBytes(vec![1; 32]);
+
+        let add_executor = op_data(
+            GovOperation::AddExecutor,
+            vec![
+                protocol_id.clone(),
+                Token::Bytes(executor.to_bytes().to_vec()),
+            ],
+        );
+        let change =
+            apply_gov_operation(GovOperation::AddExecutor, &mut config, &mut info, &add_executor);
+        assert_eq!(
+            change.unwrap(),
+            GovChange::AddExecutor {
+                old: vec![],
+                new: vec![executor]
+            }
+        );
+
+        let set_rate = op_data(
+            GovOperation::SetConsensusTargetRate,
+            vec![protocol_id.clone(), Token::Uint(Uint::from(6000))],
+        );
+        let change = apply_gov_operation(
+            GovOperation::SetConsensusTargetRate,
+            &mut config,
+            &mut info,
+            &set_rate,
+        );
+        assert_eq!(change.unwrap(), GovChange::SetConsensusTargetRate { old: 0, new: 6000 });
+        info.consensus_target_rate = 5000;
+        let change = apply_gov_operation(
+            GovOperation::SetConsensusTargetRate,
+            &mut config,
+            &mut info,
+            &set_rate,
+        );
+        assert_eq!(
+            change.unwrap(),
+            GovChange::SetConsensusTargetRate {
+                old: 5000,
+                new: 6000
+            }
+        );
+
+        // Failed operations don't make any change
+        assert!(apply_gov_operation(
+            GovOperation::AddExecutor,
+            &mut config,
+            &mut info,
+            &add_executor
+        )
+        .is_err());
+        assert_eq!(info.executors(), vec![executor]);
+
+        let owner = Pubkey::new_unique();
+        let set_owner = |protocol_id: Token, owner: Vec<u8>, max_proposers: u64| {
+            op_data(
+                GovOperation::SetProtocolOwner,
+                vec![
+                    protocol_id,
+                    Token::Bytes(owner),
+                    Token::Uint(Uint::from(max_proposers)),
+                ],
+            )
+        };
+        let change = apply_gov_operation(
+            GovOperation::SetProtocolOwner,
+            &mut config,
+            &mut info,
+            &set_owner(protocol_id.clone(), owner.to_bytes().to_vec(), 5),
+        );
+        assert_eq!(
+            change.unwrap(),
+            GovChange::SetProtocolOwner {
+                old: ProtocolOwnerSettings::default(),
+                new: ProtocolOwnerSettings {
+                    owner: Some(owner),
+                    max_proposers: 5,
+                }
+            }
+        );
+        for invalid in [
+            set_owner(protocol_id.clone(), vec![1; 20], 5),
+            set_owner(protocol_id.clone(), owner.to_bytes().to_vec(), MAX_PROPOSERS as u64 + 1),
+            set_owner(Token::FixedBytes(GOV_PROTOCOL_ID.to_vec()), owner.to_bytes().to_vec(), 5),
+        ] {
+            assert!(apply_gov_operation(
+                GovOperation::SetProtocolOwner,
+                &mut config,
+                &mut info,
+                &invalid
+            )
+            .is_err());
+        }
+        let change = apply_gov_operation(
+            GovOperation::SetProtocolOwner,
+            &mut config,
+            &mut info,
+            &set_owner(protocol_id, vec![], 0),
+        );
+        assert!(matches!(
+            change.unwrap(),
+            GovChange::SetProtocolOwner { new, .. } if new.owner.is_none()
+        ));
+    }
+
+    #[test]
+    fn test_protocol_addresses() {
+        let mut info = ProtocolInfo::zeroed();
+        let address_op = |code: GovOperation, address: &Pubkey| OperationData {
+            protocol_id: GOV_PROTOCOL_ID.to_vec(),
+            function_selector: FunctionSelector::ByCode((code as u32).to_be_bytes().to_vec()),
+            params: ethabi::encode(&[Token::Tuple(vec![
+                Token::FixedBytes(vec![1; 32]),
+                Token::Bytes(address.to_bytes().to_vec()),
+            ])]),
+            ..Default::default()
+        };
+        let apply = |info: &mut ProtocolInfo, code: GovOperation, address: &Pubkey| {
+            apply_gov_operation(code, &mut Config::default(), info, &address_op(code, address))
+        };
+        let addresses: Vec<_> = (0..MAX_PROTOCOL_ADDRESSES).map(|_| Pubkey::new_unique()).collect();
+
+        for (i, address) in addresses.iter().enumerate() {
+            let change = apply(&mut info, GovOperation::AddAllowedProtocolAddress, address);
+            assert_eq!(
+                change.unwrap(),
+                GovChange::AddAllowedProtocolAddress {
+                    old: addresses[..i].to_vec(),
+                    new: addresses[..=i].to_vec()
+                }
+            );
+        }
+        for invalid in [Pubkey::new_unique(), addresses[1], Pubkey::default()] {
+            assert!(apply(&mut info, GovOperation::AddAllowedProtocolAddress, &invalid).is_err());
+        }
+
+        let change = apply(&mut info, GovOperation::RemoveAllowedProtocolAddress, &addresses[0]);
+        assert_eq!(
+            change.unwrap(),
+            GovChange::RemoveAllowedProtocolAddress {
+                old: addresses.clone(),
+                new: addresses[1..].to_vec()
+            }
+        );
+        assert_eq!(info.protocol_address, addresses[1]);
+        assert_eq!(info.extra_protocol_addresses[MAX_PROTOCOL_ADDRESSES - 2], Pubkey::default());
+        assert!(apply(&mut info, GovOperation::AddAllowedProtocolAddress, &addresses[0]).is_ok());
+        assert_eq!(info.protocol_addresses().last(), Some(&addresses[0]));
+    }
+
+    #[test]
+    fn test_abi_decode_scheme() {
+        let mut rng = SplitMix64(17);
+        let operations = [
+            GovOperation::AddAllowedProtocol,
+            GovOperation::AddAllowedProtocolAddress,
+            GovOperation::RemoveAllowedProtocolAddress,
+            GovOperation::AddAllowedProposerAddress,
+            GovOperation::RemoveAllowedProposerAddress,
+            GovOperation::AddExecutor,
+            GovOperation::RemoveExecutor,
+            GovOperation::AddTransmitters,
+            GovOperation::RemoveTransmitters,
+            GovOperation::UpdateTransmitters,
+            GovOperation::SetConsensusTargetRate,
+            GovOperation::SetRateLimit,
+            GovOperation::SetExclusiveWindow,
+            GovOperation::SetExecutorReward,
+            GovOperation::SetProtocolOwner,
+        ];
+        for operation in operations {
+            let types = abi_decode_scheme(operation);
+            let tokens: Vec<_> = types
+                .iter()
+                .map(|x| match x {
+                    ParamType::FixedBytes(_) => random_bytes32(&mut rng),
+                    ParamType::Uint(_) => Token::Uint(Uint::from(rng.below(1000))),
+                    ParamType::Bytes => Token::Bytes(vec![2; 32]),
+                    _ => Token::Array(vec![Token::Address(ethabi::Address::repeat_byte(3))]),
+                })
+                .collect();
+            let calldata = ethabi::encode(&[Token::Tuple(tokens.clone())]);
+            // The scheme has to describe the calldata the reader decodes
+            assert_eq!(reference_decode(&calldata, types), Some(tokens), "{:?}", operation);
+            assert!(decode_protocol_id(operation, &calldata).is_ok(), "{:?}", operation);
+        }
+    }
+
+    #[test]
+    fn test_propose_params_encoding() {
+        let protocol_id = [7_u8; 32];
+        let chain_id = 100000000000000000000_u128;
+        let reference = ethabi::encode(&[Token::Tuple(vec![
+            Token::FixedBytes(protocol_id.to_vec()),
+            Token::Uint(Uint::from(chain_id)),
+        ])]);
+        assert_eq!([&protocol_id[..], &u128_to_bytes32(chain_id)].concat(), reference);
+        let reference = ethabi::encode(&[Token::FixedBytes(
+            HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR.to_vec(),
+        )]);
+        assert_eq!(HANDLE_ADD_ALLOWED_PROTOCOL_SELECTOR.to_vec(), reference);
     }
 }
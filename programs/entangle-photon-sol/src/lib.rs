@@ -139,6 +139,11 @@
 //! Subsequently, the remaining accounts follow the executor account and are passed through the photon layer as they are.
 //! These accounts are deliberately managed by an extension using a function_selector and params according to the specific expectations and business requirements.
 //!
+//! `get_op_accounts`
+//!
+//! Provides the accounts derived from the operation hash, they follow the accounts of `get_accounts`.
+//! The `gov_extension` passes the pending change account of the gov timelock this way.
+//!
 //! [`sign_operation`](../src/transmitter_common/protocol_extension.rs.html#15-21)
 //!
 //! Provides additional signatures when the transaction contains AccountMetas marked as signers.
@@ -165,6 +170,12 @@
 //!    proposer: ctx.accounts.proposer.to_account_info(),
 //!    config: ctx.accounts.config.to_account_info(),
 //!    protocol_info: ctx.accounts.protocol_info.to_account_info(),
+//!    // Pass the payer, outbox and system program accounts to store the proposal in the outbox
+//!    payer: None,
+//!    outbox: None,
+//!    system_program: None,
+//!    // Pass the instructions sysvar to report the calling program in the event
+//!    instructions: None,
 //!};
 //!let bump = [ctx.bumps.proposer];
 //!let proposer_seeds = [ROOT, b"PROPOSER", &bump[..]];
@@ -182,47 +193,47 @@
 //! ```
 //!
 
+pub mod abi;
 pub mod error;
+pub mod gas_vault;
 pub mod gov;
 mod interface;
+pub mod migration;
+pub mod outbox;
+pub mod owner;
 pub mod protocol_data;
+pub mod rate_limit;
+pub mod timelock;
 pub mod util;
+pub mod view;
 
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::bpf_loader_upgradeable};
 use error::CustomError;
 use protocol_data::{
     gov_protocol_id, FunctionSelector, OpStatus, OperationData, TransmitterSignature,
 };
-use util::EthAddress;
+use rate_limit::RateLimit;
+use std::cell::{Ref, RefMut};
+use timelock::{GovTimelock, PendingGovChange};
+use util::{Bytes32, EthAddress};
 
 declare_id!("pccm961CjaR7T7Hcht9omrXQb9w54ntJo95FFT7N9AJ");
 
-#[cfg(not(any(feature = "devnet", feature = "localnet", feature = "mainnet")))]
-compile_error!("Either feature \"devnet\", \"localnet\" or \"mainnet\" must be defined");
-
-#[cfg(feature = "devnet")]
-pub const DEPLOYER: &str = "843CN5krDmv6ffi2cJ3W2HxQBTzJwEtvuTBsPX7rRsa2";
-
-#[cfg(feature = "localnet")]
-pub const DEPLOYER: &str = "J85q2bNo4FadDqDmUYPLKav14QRexShwEQXxhbkuvEP2";
-
-#[cfg(feature = "mainnet")]
-pub const DEPLOYER: &str = "7M681DVV9eg5jihLwbL4yvcwtPJTpih99f94c9K8Sipx";
-
 /// The `photon` module encapsulates all operations related to cross-chain messaging on the Solana blockchain,
 /// leveraging the capabilities of the Photon cross-chain messaging layer. It defines the governance and
 /// operational structure necessary to initiate, approve, and execute operations across blockchains.
 ///
 /// ## Constants
-/// - `SOLANA_CHAIN_ID`: Unique identifier for the Solana chain, used for validation.
 /// - `RATE_DECIMALS`: Used for calculations involving rate percentages in consensus processes.
 /// - `ROOT`: A byte string used as a base for seed generation in account addresses.
 /// - `MAX_TRANSMITTERS`, `MAX_EXECUTORS`, `MAX_PROPOSERS`: Define the maximum allowable numbers of transmitters,
 ///   executors, and proposers respectively to ensure the system's scalability and manageability.
+/// - `MAX_PROTOCOL_ADDRESSES`: Defines the maximum number of programs receiving the operations of a protocol.
 ///
 /// ## Key Operations
 /// - **Initialize**: Sets up the initial configuration for protocols, defining administrators, chain IDs,
-///   smart contracts, and operational parameters such as rate and role-based limitations.
+///   smart contracts, and operational parameters such as rate and role-based limitations. The chain ID of
+///   Solana is stored in the config, so the same build is deployed to every cluster.
 /// - **Load Operation**: The first step in operation execution, verifying the operation's integrity and
 ///   preparing it for further processing by setting its initial state.
 /// - **Sign Operation**: Involves validating signatures to achieve consensus among transmitters, updating
@@ -234,6 +245,12 @@ pub const DEPLOYER: &str = "7M681DVV9eg5jihLwbL4yvcwtPJTpih99f94c9K8Sipx";
 ///   managing these proposals through events that ensure transparency and traceability.
 /// - **Receive Photon Message**: Specialized in handling operations directed at the governance protocol,
 ///   executing code-based operations that affect the system's governance structure.
+/// - **Gov Timelock**: Queues the gov operations selected by the admin, they are applied by `apply_gov_change`
+///   after the delay unless vetoed by the guardian with `veto_gov_change`.
+/// - **Migrate Account**: Upgrades config, protocol and operation accounts stored in an outdated layout
+///   to the current version in place.
+/// - **Views**: Return the operation status, the protocol settings and the next proposal nonce through
+///   the return data, for off-chain tools simulating the transaction and for protocols calling by CPI.
 ///
 /// ## Structs and Contexts
 /// - `Initialize`, `LoadOperation`, `SignOperation`, `ExecuteOperation`: Context structs designed to facilitate
@@ -256,16 +273,6 @@ pub const DEPLOYER: &str = "7M681DVV9eg5jihLwbL4yvcwtPJTpih99f94c9K8Sipx";
 /// with other chains, leveraging the Photon system's capabilities to enhance their applications' reach and functionalities.
 #[program]
 pub mod photon {
-    #[cfg(not(any(feature = "devnet", feature = "localnet", feature = "mainnet")))]
-    compile_error!("Either feature \"devnet\", \"localnet\" or \"mainnet\" must be defined");
-
-    /// Unique identifier for the Solana chain used within the Photon cross-chain messaging layer.
-    /// This constant helps ensure operations are validated specifically for the Solana blockchain.
-    #[cfg(any(feature = "devnet", feature = "localnet"))]
-    pub const SOLANA_CHAIN_ID: u128 = 100000000000000000000;
-    #[cfg(feature = "mainnet")]
-    pub const SOLANA_CHAIN_ID: u128 = 11100000000000000501;
-
     /// Represents the umber of decimal places used in rate calculations for consensus mechanisms.
     /// This precision is necessary for accurate calculations when determining the consensus rate.
     pub const RATE_DECIMALS: u64 = 10000;
@@ -286,6 +293,10 @@ pub mod photon {
     /// Proposers are authorized to initiate new operations that may affect multiple chains.
     pub const MAX_PROPOSERS: usize = 20;
 
+    /// The maximum number of receiver programs allowed for a protocol.
+    /// A protocol split across several programs receives operations into any of them.
+    pub const MAX_PROTOCOL_ADDRESSES: usize = 8;
+
     use self::{
         gov::handle_gov_operation,
         interface::{PhotonMsg, PhotonMsgWithSelector},
@@ -309,6 +320,8 @@ pub mod photon {
     /// * `consensus_target_rate` - The rate of signing operations to be executed.
     /// * `gov_transmitters` - A vector of Ethereum addresses representing the transmitters for the governance.
     /// * `gov_executors` - A vector of public keys representing the executors for the governance.
+    /// * `chain_id` - The chain ID of Solana within the Photon cross-chain messaging layer, operations
+    ///   are only loaded if they are destined to it.
     ///
     /// # Returns
     ///
@@ -320,21 +333,26 @@ pub mod photon {
         consensus_target_rate: u64,
         gov_transmitters: Vec<EthAddress>,
         gov_executors: Vec<Pubkey>,
+        chain_id: u128,
     ) -> Result<()> {
+        require!(chain_id != 0, CustomError::ChainIdNotSet);
+        ctx.accounts.config.version = Config::VERSION;
         ctx.accounts.config.admin = ctx.accounts.admin.key();
         ctx.accounts.config.eob_chain_id = eob_chain_id;
+        ctx.accounts.config.chain_id = chain_id;
         require_eq!(eob_master_smart_contract.len(), 32);
         ctx.accounts.config.eob_master_smart_contract.copy_from_slice(&eob_master_smart_contract);
-        ctx.accounts.protocol_info.is_init = true;
-        ctx.accounts.protocol_info.protocol_address = photon::ID;
-        ctx.accounts.protocol_info.consensus_target_rate = consensus_target_rate;
-        ctx.accounts.protocol_info.transmitters = Default::default();
+        let mut protocol_info = ProtocolInfo::load_or_init(&ctx.accounts.protocol_info)?;
+        protocol_info.is_init = 1;
+        protocol_info.protocol_address = photon::ID;
+        protocol_info.consensus_target_rate = consensus_target_rate;
+        protocol_info.transmitters = Default::default();
         for (i, k) in gov_transmitters.into_iter().enumerate() {
-            ctx.accounts.protocol_info.transmitters[i] = k;
+            protocol_info.transmitters[i] = k;
         }
-        ctx.accounts.protocol_info.executors = Default::default();
+        protocol_info.executors = Default::default();
         for (i, e) in gov_executors.into_iter().enumerate() {
-            ctx.accounts.protocol_info.executors[i] = e;
+            protocol_info.executors[i] = e;
         }
         Ok(())
     }
@@ -358,20 +376,26 @@ pub mod photon {
         op_data: OperationData,
         op_hash_cached: Vec<u8>,
     ) -> Result<()> {
-        let op_hash = op_data.op_hash_with_message();
+        let op_hash = op_data.op_hash_with_message()?;
         require!(op_hash == op_hash_cached, CustomError::CachedOpHashMismatch);
-        require_eq!(op_data.dest_chain_id, SOLANA_CHAIN_ID, CustomError::OpIsNotForThisChain);
+        // The config migrated from the legacy layout has no chain ID until it is set
+        require!(ctx.accounts.config.chain_id != 0, CustomError::ChainIdNotSet);
         require_eq!(
-            ctx.accounts.protocol_info.protocol_address,
-            op_data.protocol_addr,
+            op_data.dest_chain_id,
+            ctx.accounts.config.chain_id,
+            CustomError::OpIsNotForThisChain
+        );
+        let protocol_info = ProtocolInfo::load_checked(&ctx.accounts.protocol_info)?;
+        require!(
+            protocol_info.protocol_addresses().contains(&op_data.protocol_addr),
             CustomError::ProtocolAddressMismatch
         );
         require!(
             op_data.protocol_id != [0; 32] && op_data.protocol_id.len() == 32,
             CustomError::InvalidOpData
         );
-        ctx.accounts.op_info.op_data = op_data;
-        ctx.accounts.op_info.status = OpStatus::Init;
+        protocol_info.check_executor(&op_hash, &ctx.accounts.executor.key())?;
+        OpInfo::init(&ctx.accounts.op_info, &op_data, ctx.accounts.executor.key())?;
         emit!(ProposalLoaded {
             op_hash,
             executor: ctx.accounts.executor.key()
@@ -398,18 +422,16 @@ pub mod photon {
         op_hash: Vec<u8>,
         signatures: Vec<TransmitterSignature>,
     ) -> Result<bool> {
-        let allowed_transmitters = &ctx.accounts.protocol_info.transmitters();
+        let protocol_info = ProtocolInfo::load_checked(&ctx.accounts.protocol_info)?;
+        protocol_info.check_executor(&op_hash, &ctx.accounts.executor.key())?;
+        let mut op_info = OpInfo::load_checked_mut(&ctx.accounts.op_info)?;
+        let allowed_transmitters = &protocol_info.transmitters();
         require_gt!(allowed_transmitters.len(), 0, CustomError::NoTransmittersAllowed);
-        let mut unique_signers: Vec<EthAddress> = ctx
-            .accounts
-            .op_info
-            .unique_signers
-            .into_iter()
-            .filter(|x| x != &EthAddress::default())
-            .collect();
+        let mut unique_signers: Vec<EthAddress> =
+            op_info.unique_signers.into_iter().filter(|x| x != &EthAddress::default()).collect();
         let consensus =
             ((unique_signers.len() as u64) * RATE_DECIMALS) / (allowed_transmitters.len() as u64);
-        let mut consensus_reached = consensus >= ctx.accounts.protocol_info.consensus_target_rate;
+        let mut consensus_reached = consensus >= protocol_info.consensus_target_rate;
         if consensus_reached {
             return Ok(true);
         }
@@ -420,9 +442,9 @@ pub mod photon {
                 unique_signers.push(transmitter);
                 let consensus_rate = ((unique_signers.len() as u64) * RATE_DECIMALS)
                     / (allowed_transmitters.len() as u64);
-                if consensus_rate >= ctx.accounts.protocol_info.consensus_target_rate {
+                if consensus_rate >= protocol_info.consensus_target_rate {
                     consensus_reached = true;
-                    ctx.accounts.op_info.status = OpStatus::Signed;
+                    op_info.set_status(OpStatus::Signed);
                     emit!(ProposalApproved {
                         op_hash,
                         executor: ctx.accounts.executor.key()
@@ -431,9 +453,9 @@ pub mod photon {
                 }
             }
         }
-        ctx.accounts.op_info.unique_signers = Default::default();
+        op_info.unique_signers = Default::default();
         for (i, s) in unique_signers.into_iter().enumerate() {
-            ctx.accounts.op_info.unique_signers[i] = s;
+            op_info.unique_signers[i] = s;
         }
         Ok(consensus_reached)
     }
@@ -442,8 +464,30 @@ pub mod photon {
         ctx: Context<'_, '_, '_, 'info, ExecuteOperation<'info>>,
         op_hash: Vec<u8>,
     ) -> Result<()> {
-        let op_data = &ctx.accounts.op_info.op_data;
+        let op_data = OpInfo::op_data(&ctx.accounts.op_info)?;
         msg!("op_hash: {}", hex::encode(&op_hash));
+        let (hold, executor_reward) = {
+            let mut protocol_info = ProtocolInfo::load_checked_mut(&ctx.accounts.protocol_info)?;
+            protocol_info.check_executor(&op_hash, &ctx.accounts.executor.key())?;
+            let mut op_info = OpInfo::load_checked_mut(&ctx.accounts.op_info)?;
+            let hold = protocol_info.rate_limit.consume(
+                Clock::get()?.slot,
+                &op_data.params,
+                op_info.held_until,
+            )?;
+            if let Some(hold) = hold.filter(|x| x.recorded) {
+                op_info.held_until = hold.until;
+            }
+            (hold, protocol_info.executor_reward)
+        };
+        if let Some(hold) = hold {
+            emit!(ProposalHeld {
+                op_hash,
+                executor: ctx.accounts.executor.key(),
+                retry_slot: hold.until
+            });
+            return Ok(());
+        }
         // The first account in remaining_accounts should be protocol address, which is added first in account list
         let mut accounts: Vec<_> = ctx.remaining_accounts.first().into_iter().cloned().collect();
         require!(
@@ -486,7 +530,7 @@ pub mod photon {
                 };
                 (name.clone(), payload.try_to_vec().expect("fixed struct serialization"))
             }
-            FunctionSelector::Dummy => panic!("Uninitialized function_selector"),
+            FunctionSelector::Dummy => return err!(CustomError::InvalidFunctionSelector),
         };
 
         let data = [&sighash("global", &method)[..], &payload[..]].concat();
@@ -502,12 +546,33 @@ pub mod photon {
             ]],
         )?;
 
-        ctx.accounts.op_info.status = OpStatus::Executed;
+        OpInfo::load_checked_mut(&ctx.accounts.op_info)?.set_status(OpStatus::Executed);
 
         emit!(ProposalExecuted {
-            op_hash,
+            op_hash: op_hash.clone(),
             executor: ctx.accounts.executor.key()
         });
+
+        let op_info_rent = Rent::get()?.minimum_balance(ctx.accounts.op_info.as_ref().data_len());
+        let payout = gas_vault::reimburse_executor(
+            &ctx.accounts.gas_vault,
+            &ctx.accounts.executor,
+            &ctx.accounts.loader,
+            &ctx.accounts.system_program,
+            &op_data.protocol_id,
+            ctx.bumps.gas_vault,
+            op_info_rent,
+            executor_reward,
+        )?;
+        if payout.total() > 0 {
+            emit!(ExecutorReimbursed {
+                op_hash,
+                executor: ctx.accounts.executor.key(),
+                loader: ctx.accounts.loader.key(),
+                rent: payout.rent,
+                reward: payout.reward
+            });
+        }
         Ok(())
     }
 
@@ -527,6 +592,11 @@ pub mod photon {
     /// * `function_selector` - The function selector for the proposal.
     /// * `params` - The parameters for the proposed function, represented as a vector of bytes.
     ///
+    /// # Outbox mode
+    ///
+    /// If the `outbox` account is passed along with the `payer`, the proposal is also stored in it,
+    /// see [outbox].
+    ///
     /// # Returns
     ///
     /// Returns a result indicating the success or failure of the proposal creation.
@@ -541,13 +611,36 @@ pub mod photon {
         // TODO: check if all requirements are satisfied
         let nonce = ctx.accounts.config.nonce;
         ctx.accounts.config.nonce += 1;
+        let function_selector = function_selector.to_bytes()?;
+        let proposer = ctx.accounts.proposer.key();
+        let caller_program = match ctx.accounts.instructions.as_ref() {
+            Some(instructions) => util::cpi_caller_program(instructions)?,
+            None => None,
+        };
+        if let Some(outbox) = ctx.accounts.outbox.as_mut() {
+            let payer = ctx.accounts.payer.as_ref().ok_or(ErrorCode::ConstraintAccountIsNone)?;
+            outbox.set_inner(outbox::OutboundProposal {
+                payer: payer.key(),
+                slot: Clock::get()?.slot,
+                proposer,
+                caller_program,
+                nonce,
+                dst_chain_id,
+                protocol_id: protocol_id.clone(),
+                protocol_address: protocol_address.clone(),
+                function_selector: function_selector.clone(),
+                params: params.clone(),
+            });
+        }
         emit!(ProposeEvent {
             protocol_id,
             nonce,
             dst_chain_id,
             protocol_address,
-            function_selector: function_selector.to_bytes()?,
-            params
+            function_selector,
+            params,
+            proposer,
+            caller_program
         });
         Ok(())
     }
@@ -572,24 +665,139 @@ pub mod photon {
     ///
     pub fn receive_photon_msg(
         ctx: Context<ReceivePhotonMsg>,
-        _op_hash: Vec<u8>,
+        op_hash: Vec<u8>,
         code: Vec<u8>,
-        _params: Vec<u8>,
+        params: Vec<u8>,
     ) -> Result<()> {
-        let op_data = &ctx.accounts.op_info.op_data;
+        let op_data = OpInfo::op_data(&ctx.accounts.op_info)?;
         require!(
             op_data.protocol_id == gov_protocol_id() && op_data.protocol_addr == ID,
             CustomError::InvalidEndpoint
         );
+        // The target protocol info is derived from the passed code and params, so they must be
+        // the same as the stored ones
+        require!(
+            matches!(&op_data.function_selector, FunctionSelector::ByCode(c) if c == &code),
+            CustomError::InvalidMethodSelector
+        );
+        require!(op_data.params == params, CustomError::InvalidGovMsg);
+        let gov_operation = gov::gov_operation_by_code(&code)?;
+        let mut target_protocol_info =
+            ProtocolInfo::load_or_init(&ctx.accounts.target_protocol_info)?;
+
+        let slot = Clock::get()?.slot;
+        let timelock = GovTimelock::load(&ctx.accounts.gov_timelock)?;
+        if let Some(executable_slot) = timelock.and_then(|x| x.executable_slot(gov_operation, slot))
+        {
+            let target_protocol_id = gov::target_protocol(&code, &params);
+            timelock::queue_gov_change(
+                PendingGovChange {
+                    payer: ctx.accounts.executor.key(),
+                    code: gov_operation as u32,
+                    target_protocol_id,
+                    executable_slot,
+                    op_hash: op_hash.clone(),
+                },
+                &ctx.accounts.pending_change,
+                &ctx.accounts.executor,
+                &ctx.accounts.system_program,
+                ctx.bumps.pending_change,
+            )?;
+            emit!(GovChangeQueued {
+                op_hash,
+                protocol_id: target_protocol_id.to_vec(),
+                code: gov_operation as u32,
+                executable_slot
+            });
+            return Ok(());
+        }
+
+        handle_gov_operation(
+            &mut ctx.accounts.config,
+            &mut target_protocol_info,
+            op_hash,
+            gov_operation,
+            &op_data,
+        )
+    }
+
+    /// Applies the timelocked gov operation once its delay is over, see [timelock]. Can be called
+    /// by a gov executor.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `op_hash` - The hash of the queued gov operation.
+    ///
+    pub fn apply_gov_change(ctx: Context<ApplyGovChange>, op_hash: Vec<u8>) -> Result<()> {
+        require!(
+            Clock::get()?.slot >= ctx.accounts.pending_change.executable_slot,
+            CustomError::GovChangeIsLocked
+        );
+        let op_data = OpInfo::op_data(&ctx.accounts.op_info)?;
+        let gov_operation = gov::GovOperation::try_from(ctx.accounts.pending_change.code)
+            .map_err(|_| CustomError::InvalidMethodSelector)?;
         handle_gov_operation(
             &mut ctx.accounts.config,
-            &mut ctx.accounts.target_protocol_info,
-            code,
-            op_data,
+            &mut *ProtocolInfo::load_checked_mut(&ctx.accounts.target_protocol_info)?,
+            op_hash,
+            gov_operation,
+            &op_data,
         )
     }
 
-    /// Updates global admin. Can only be called by deployer address.
+    /// Drops the timelocked gov operation before it is applied, see [timelock]. Can be called by the
+    /// guardian only.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `op_hash` - The hash of the queued gov operation.
+    ///
+    pub fn veto_gov_change(ctx: Context<VetoGovChange>, op_hash: Vec<u8>) -> Result<()> {
+        emit!(GovChangeVetoed {
+            op_hash,
+            protocol_id: ctx.accounts.pending_change.target_protocol_id.to_vec(),
+            guardian: ctx.accounts.guardian.key()
+        });
+        Ok(())
+    }
+
+    /// Sets the gov operations to be timelocked along with the delay and the guardian, see [timelock].
+    /// Can only be called by the admin.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `guardian` - The account allowed to veto pending changes.
+    /// * `delay_slots` - The number of slots a pending change is held for, zero disables the timelock.
+    /// * `operations` - The codes of the timelocked gov operations.
+    ///
+    pub fn set_gov_timelock(
+        ctx: Context<SetGovTimelock>,
+        guardian: Pubkey,
+        delay_slots: u64,
+        operations: Vec<u32>,
+    ) -> Result<()> {
+        require!(
+            operations.len() <= timelock::MAX_TIMELOCKED_OPERATIONS,
+            CustomError::TooManyTimelockedOperations
+        );
+        for code in &operations {
+            require!(
+                gov::GovOperation::try_from(*code).is_ok(),
+                CustomError::InvalidMethodSelector
+            );
+        }
+        ctx.accounts.gov_timelock.set_inner(GovTimelock {
+            guardian,
+            delay_slots,
+            operations,
+        });
+        Ok(())
+    }
+
+    /// Updates global admin. Can only be called by the upgrade authority of the program.
     ///
     /// # Arguments
     ///
@@ -604,6 +812,150 @@ pub mod photon {
         ctx.accounts.config.admin = admin;
         Ok(())
     }
+
+    /// Updates the chain ID of Solana stored in the config. Can only be called by the upgrade
+    /// authority of the program. The config migrated from the legacy layout has no chain ID, so
+    /// operations are rejected with `ChainIdNotSet` until it is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `chain_id` - The chain ID of Solana.
+    ///
+    pub fn set_chain_id(ctx: Context<SetAdmin>, chain_id: u128) -> Result<()> {
+        require!(chain_id != 0, CustomError::ChainIdNotSet);
+        ctx.accounts.config.chain_id = chain_id;
+        Ok(())
+    }
+
+    /// Migrates the config, protocol info or operation info account stored in an outdated layout to the
+    /// current version. The kind of the account is detected by its discriminator, accounts stored in
+    /// the current layout are left untouched. Can be called by the admin or a gov executor, who pays
+    /// the rent for the increased account size.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    ///
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        require!(
+            migration::is_migration_authority(
+                &ctx.accounts.authority.key(),
+                &ctx.accounts.config,
+                &ctx.accounts.gov_info
+            )?,
+            CustomError::InvalidMigrationAuthority
+        );
+        migration::migrate_account(
+            &ctx.accounts.account,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )
+    }
+
+    /// Adds a proposer to the protocol by its owner, see [owner].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `protocol_id` - The identifier of the protocol.
+    /// * `proposer` - The proposer to be added.
+    ///
+    pub fn owner_add_proposer(
+        ctx: Context<ManageProtocol>,
+        protocol_id: Vec<u8>,
+        proposer: Pubkey,
+    ) -> Result<()> {
+        manage_protocol(ctx, protocol_id, owner::OwnerAction::AddProposer(proposer))
+    }
+
+    /// Removes a proposer from the protocol by its owner, see [owner].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `protocol_id` - The identifier of the protocol.
+    /// * `proposer` - The proposer to be removed.
+    ///
+    pub fn owner_remove_proposer(
+        ctx: Context<ManageProtocol>,
+        protocol_id: Vec<u8>,
+        proposer: Pubkey,
+    ) -> Result<()> {
+        manage_protocol(ctx, protocol_id, owner::OwnerAction::RemoveProposer(proposer))
+    }
+
+    /// Acknowledges the delivery of an outbound proposal stored in the outbox mode. The outbox account
+    /// is closed and its rent is returned to the payer. Can be called by a gov executor or an executor
+    /// of the protocol.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `protocol_id` - The identifier of the protocol.
+    /// * `nonce` - The nonce of the proposal.
+    ///
+    pub fn ack_outbound(ctx: Context<AckOutbound>, protocol_id: Vec<u8>, nonce: u64) -> Result<()> {
+        emit!(OutboundAcked {
+            protocol_id,
+            nonce,
+            authority: ctx.accounts.authority.key()
+        });
+        Ok(())
+    }
+
+    /// Returns the status of the operation through the return data, see [view]. `OpStatus::None`
+    /// is returned if the operation hasn't been loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `_op_hash` - The hash of the operation.
+    ///
+    pub fn get_op_status(ctx: Context<GetOpStatus>, _op_hash: Vec<u8>) -> Result<OpStatus> {
+        view::op_status(&ctx.accounts.op_info)
+    }
+
+    /// Returns the settings of the protocol through the return data, see [view].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    /// * `_protocol_id` - The identifier of the protocol.
+    ///
+    pub fn get_protocol_info(
+        ctx: Context<GetProtocolInfo>,
+        _protocol_id: Vec<u8>,
+    ) -> Result<view::ProtocolInfoView> {
+        Ok(view::ProtocolInfoView::from(&*ProtocolInfo::load_checked(&ctx.accounts.protocol_info)?))
+    }
+
+    /// Returns the nonce the next proposal is going to get through the return data, see [view].
+    ///
+    /// # Arguments
+    ///
+    /// * `ctx` - The context containing the necessary accounts.
+    ///
+    pub fn get_next_nonce(ctx: Context<GetNextNonce>) -> Result<u64> {
+        Ok(ctx.accounts.config.nonce)
+    }
+}
+
+fn manage_protocol(
+    ctx: Context<ManageProtocol>,
+    protocol_id: Vec<u8>,
+    action: owner::OwnerAction,
+) -> Result<()> {
+    let change = owner::apply_owner_action(
+        action,
+        &mut *ProtocolInfo::load_checked_mut(&ctx.accounts.protocol_info)?,
+    )?;
+    emit!(OwnerActionApplied {
+        protocol_id,
+        owner: ctx.accounts.owner.key(),
+        change
+    });
+    Ok(())
 }
 
 /// Represents the accounts required for initializing the Solana program.
@@ -614,12 +966,13 @@ pub mod photon {
 /// # Fields
 ///
 /// * `admin` - The admin account, which must be a signer and mutable. Additionally, it must either
-/// match the `admin` key in the configuration or be a default public key.
+/// match the `admin` key in the configuration or be the upgrade authority of the program.
 /// * `protocol_info` - The protocol information account. It is initialized if needed, with space allocated
 /// based on `ProtocolInfo::LEN`, and it utilizes seeds and a bump for addressing.
 /// * `config` - The system configuration account. It is initialized if needed, with space allocated
 /// based on `Config::LEN`, and it utilizes seeds and a bump for addressing.
 /// * `system_program` - The system program.
+/// * `program_data` - The program data account holding the upgrade authority of the program.
 ///
 #[derive(Accounts)]
 pub struct Initialize<'info> {
@@ -627,7 +980,7 @@ pub struct Initialize<'info> {
     #[account(
         signer,
         mut,
-        constraint = (admin.key() == config.admin || admin.key() == DEPLOYER.parse().expect("Deployer key not set"))
+        constraint = (admin.key() == config.admin || program_data.upgrade_authority_address == Some(admin.key()))
             @ CustomError::IsNotAdmin
     )]
     admin: Signer<'info>,
@@ -640,7 +993,7 @@ pub struct Initialize<'info> {
         seeds = [ROOT, b"PROTOCOL", gov_protocol_id()],
         bump
     )]
-    protocol_info: Box<Account<'info, ProtocolInfo>>,
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
 
     /// System config
     #[account(init_if_needed, payer = admin, space = Config::LEN, seeds = [ROOT, b"CONFIG"], bump)]
@@ -648,6 +1001,10 @@ pub struct Initialize<'info> {
 
     /// System program
     system_program: Program<'info, System>,
+
+    /// Program data
+    #[account(seeds = [crate::ID.as_ref()], bump, seeds::program = bpf_loader_upgradeable::ID)]
+    program_data: Box<Account<'info, ProgramData>>,
 }
 
 /// Represents the context for loading an operation within the Photon cross-chain messaging layer.
@@ -677,7 +1034,7 @@ pub struct LoadOperation<'info> {
     #[account(
         signer,
         mut,
-        constraint = protocol_info.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
+        constraint = ProtocolInfo::load_checked(&protocol_info)?.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
     )]
     executor: Signer<'info>,
 
@@ -686,7 +1043,7 @@ pub struct LoadOperation<'info> {
         seeds = [ROOT, b"PROTOCOL", &op_data.protocol_id],
         bump
     )]
-    protocol_info: Box<Account<'info, ProtocolInfo>>,
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
 
     /// Operation info
     #[account(
@@ -695,9 +1052,8 @@ pub struct LoadOperation<'info> {
         space = OpInfo::len(&op_data),
         seeds = [ROOT, b"OP", &op_hash_cached],
         bump,
-        constraint = op_info.status == OpStatus::None @ CustomError::OpStateInvalid,
     )]
-    op_info: Box<Account<'info, OpInfo>>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// System config
     #[account(mut, seeds = [ROOT, b"CONFIG"], bump)]
@@ -730,7 +1086,7 @@ pub struct SignOperation<'info> {
     #[account(
         signer,
         mut,
-        constraint = protocol_info.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
+        constraint = ProtocolInfo::load_checked(&protocol_info)?.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
     )]
     executor: Signer<'info>,
 
@@ -739,16 +1095,16 @@ pub struct SignOperation<'info> {
         mut,
         seeds = [ROOT, b"OP", &op_hash],
         bump,
-        constraint = (op_info.status == OpStatus::Init || op_info.status == OpStatus::Signed) @ CustomError::OpStateInvalid
+        constraint = matches!(OpInfo::load_checked(&op_info)?.status(), OpStatus::Init | OpStatus::Signed) @ CustomError::OpStateInvalid
     )]
-    op_info: Box<Account<'info, OpInfo>>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// Protocol info
     #[account(
-        seeds = [ROOT, b"PROTOCOL", &op_info.op_data.protocol_id],
+        seeds = [ROOT, b"PROTOCOL", &OpInfo::load_checked(&op_info)?.protocol_id],
         bump
     )]
-    protocol_info: Box<Account<'info, ProtocolInfo>>,
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
 }
 
 /// Represents the context for executing an operation in the Photon cross-chain messaging layer.
@@ -762,9 +1118,14 @@ pub struct SignOperation<'info> {
 /// * `executor` - The executor account, which must be a signer and mutable, and should be an authorized executor.
 /// * `op_info` - The operation information account, which is mutable and identified using seeds and a bump.
 ///               It should be in the `Signed` state.
-/// * `protocol_info` - The protocol information account, identified using seeds and a bump.
+/// * `protocol_info` - The protocol information account, which is mutable to track the rate limit and
+///   identified using seeds and a bump.
 /// * `call_authority` - is a Program Derived Address (PDA) verified at the photon layer to ensure
 /// it is invoked via cross-program invocation—handled by the photon layer
+/// * `gas_vault` - The gas vault of the protocol the executor is reimbursed from, see [gas_vault].
+/// * `loader` - The executor that loaded the operation, it gets the rent of the operation account
+///   back. Operations loaded before the loader was recorded pass the executor itself.
+/// * `system_program` - The system program used to transfer the reimbursement.
 ///
 /// # Arguments
 ///
@@ -776,7 +1137,7 @@ pub struct ExecuteOperation<'info> {
     #[account(
         signer,
         mut,
-        constraint = protocol_info.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
+        constraint = ProtocolInfo::load_checked(&protocol_info)?.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
     )]
     executor: Signer<'info>,
 
@@ -785,24 +1146,44 @@ pub struct ExecuteOperation<'info> {
         mut,
         seeds = [ROOT, b"OP", &op_hash],
         bump,
-        constraint = op_info.status == OpStatus::Signed @ CustomError::OpStateInvalid
+        constraint = OpInfo::load_checked(&op_info)?.status() == OpStatus::Signed @ CustomError::OpStateInvalid
     )]
-    op_info: Box<Account<'info, OpInfo>>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// Protocol info
     #[account(
-        seeds = [ROOT, b"PROTOCOL", &op_info.op_data.protocol_id],
+        mut,
+        seeds = [ROOT, b"PROTOCOL", &OpInfo::load_checked(&op_info)?.protocol_id],
         bump
     )]
-    protocol_info: Box<Account<'info, ProtocolInfo>>,
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
 
     /// Per-protocol call authority
     /// CHECK: only used as authority account
     #[account(
-        seeds = [ROOT, b"CALL_AUTHORITY", &op_info.op_data.protocol_id],
+        seeds = [ROOT, b"CALL_AUTHORITY", &OpInfo::load_checked(&op_info)?.protocol_id],
         bump
     )]
     call_authority: AccountInfo<'info>,
+
+    /// Per-protocol gas vault the executor is reimbursed from
+    #[account(
+        mut,
+        seeds = [ROOT, b"GAS_VAULT", &OpInfo::load_checked(&op_info)?.protocol_id],
+        bump
+    )]
+    gas_vault: SystemAccount<'info>,
+
+    /// Executor that loaded the operation
+    /// CHECK: checked against the loader recorded in the operation info
+    #[account(
+        mut,
+        constraint = loader.key() == OpInfo::load_checked(&op_info)?.loader().unwrap_or(executor.key()) @ CustomError::InvalidLoader
+    )]
+    loader: UncheckedAccount<'info>,
+
+    /// System program
+    system_program: Program<'info, System>,
 }
 
 /// Represents the accounts context necessary for proposing an operation in the Photon cross-chain messaging layer.
@@ -816,17 +1197,28 @@ pub struct ExecuteOperation<'info> {
 /// * `proposer` - The proposer account, which must be a signer and must be listed as an authorized proposer in the protocol info.
 /// * `config` - The system configuration account, which is mutable and identified using seeds and a bump.
 /// * `protocol_info` - The target protocol information account, identified using seeds and a bump based on the provided `protocol_id`.
+/// * `payer` - The optional account paying the rent of the outbox account, required in the outbox mode.
+/// * `outbox` - The optional outbox account the proposal is stored in, derived from `protocol_id` and the config nonce.
+/// * `system_program` - The system program, required in the outbox mode.
+/// * `instructions` - The optional instructions sysvar, the CPI caller program is reported in the event if passed.
 ///
 /// # Arguments
 ///
 /// * `protocol_id` - The identifier for the protocol, used for deriving the `protocol_info` account.
+/// * `protocol_address`, `function_selector`, `params` - The proposal fields, used for sizing the `outbox` account.
 #[derive(Accounts)]
-#[instruction(protocol_id: Vec<u8>)]
+#[instruction(
+    protocol_id: Vec<u8>,
+    dst_chain_id: u128,
+    protocol_address: Vec<u8>,
+    function_selector: FunctionSelector,
+    params: Vec<u8>
+)]
 pub struct Propose<'info> {
     /// Proposer account
     #[account(
         signer,
-        constraint = protocol_info.proposers.contains(&proposer.key()) @ CustomError::ProposerIsNotAllowed
+        constraint = ProtocolInfo::load_checked(&protocol_info)?.proposers.contains(&proposer.key()) @ CustomError::ProposerIsNotAllowed
     )]
     proposer: Signer<'info>,
 
@@ -839,7 +1231,34 @@ pub struct Propose<'info> {
         seeds = [ROOT, b"PROTOCOL", &protocol_id],
         bump
     )]
-    protocol_info: Box<Account<'info, ProtocolInfo>>,
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// Outbox rent payer
+    #[account(signer, mut)]
+    payer: Option<Signer<'info>>,
+
+    /// Outbox account
+    #[account(
+        init,
+        payer = payer,
+        space = outbox::OutboundProposal::len(
+            &protocol_id,
+            &protocol_address,
+            &function_selector.to_bytes()?,
+            &params
+        ),
+        seeds = [ROOT, b"OUTBOX", &protocol_id, &config.nonce.to_be_bytes()],
+        bump
+    )]
+    outbox: Option<Box<Account<'info, outbox::OutboundProposal>>>,
+
+    /// System program
+    system_program: Option<Program<'info, System>>,
+
+    /// Instructions sysvar
+    /// CHECK: the address is checked, read to detect the CPI caller program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    instructions: Option<UncheckedAccount<'info>>,
 }
 
 /// Represents the account context necessary for receiving and processing a photon message within
@@ -860,6 +1279,8 @@ pub struct Propose<'info> {
 /// * `gov_info` - The governance protocol information account, which governs the operation.
 /// * `target_protocol_info` - The target protocol information, potentially initialized and set up for the specific operation being handled.
 /// * `system_program` - The system program.
+/// * `gov_timelock` - The timelock settings account, it may not be created yet.
+/// * `pending_change` - The pending change account created if the operation is timelocked, see [timelock].
 ///
 /// # Arguments
 ///
@@ -874,7 +1295,7 @@ pub struct ReceivePhotonMsg<'info> {
     #[account(
         signer,
         mut,
-        constraint = gov_info.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
+        constraint = ProtocolInfo::load_checked(&gov_info)?.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
     )]
     executor: Signer<'info>,
 
@@ -886,9 +1307,9 @@ pub struct ReceivePhotonMsg<'info> {
     #[account(
         seeds = [ROOT, b"OP", &op_hash],
         bump,
-        constraint = op_info.status == OpStatus::Signed @ CustomError::OpStateInvalid
+        constraint = OpInfo::load_checked(&op_info)?.status() == OpStatus::Signed @ CustomError::OpStateInvalid
     )]
-    op_info: Box<Account<'info, OpInfo>>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// System config
     #[account(init_if_needed, space = Config::LEN, payer = executor, seeds = [ROOT, b"CONFIG"], bump)]
@@ -899,31 +1320,338 @@ pub struct ReceivePhotonMsg<'info> {
         seeds = [ROOT, b"PROTOCOL", gov_protocol_id()],
         bump
     )]
-    gov_info: Box<Account<'info, ProtocolInfo>>,
+    gov_info: AccountLoader<'info, ProtocolInfo>,
 
     /// Target protocol info
     #[account(
         init_if_needed,
         space = ProtocolInfo::LEN,
         payer = executor,
-        seeds = [ROOT, b"PROTOCOL", &gov::target_protocol(&op_info.op_data.function_selector, &op_info.op_data.params)],
+        seeds = [ROOT, b"PROTOCOL", &gov::target_protocol(&code, &params)],
+        bump
+    )]
+    target_protocol_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// System program
+    system_program: Program<'info, System>,
+
+    /// Gov timelock settings
+    /// CHECK: the address is checked, the account is read if it is created
+    #[account(seeds = [ROOT, b"GOV_TIMELOCK"], bump)]
+    gov_timelock: UncheckedAccount<'info>,
+
+    /// Pending gov change
+    /// CHECK: the address is checked, the account is created only if the operation is timelocked
+    #[account(mut, seeds = [ROOT, b"PENDING_GOV", &op_hash], bump)]
+    pending_change: UncheckedAccount<'info>,
+}
+
+/// Represents the accounts context for applying a timelocked gov operation.
+///
+/// # Fields
+///
+/// * `executor` - The gov executor, which must be a signer.
+/// * `op_info` - The executed gov operation, its data is applied.
+/// * `config` - The system configuration account.
+/// * `gov_info` - The governance protocol information account.
+/// * `pending_change` - The pending change account, closed once the change is applied.
+/// * `target_protocol_info` - The information of the protocol whose settings are changed.
+/// * `payer` - The payer of the pending change account, receives its rent back.
+///
+/// # Arguments
+///
+/// * `op_hash` - The hash of the queued gov operation.
+///
+#[derive(Accounts)]
+#[instruction(op_hash: Vec<u8>)]
+pub struct ApplyGovChange<'info> {
+    /// Executor account
+    #[account(
+        signer,
+        constraint = ProtocolInfo::load_checked(&gov_info)?.executors.contains(&executor.key()) @ CustomError::ExecutorIsNotAllowed
+    )]
+    executor: Signer<'info>,
+
+    /// Operation info
+    #[account(
+        seeds = [ROOT, b"OP", &op_hash],
+        bump,
+        constraint = OpInfo::load_checked(&op_info)?.status() == OpStatus::Executed @ CustomError::OpStateInvalid
+    )]
+    op_info: AccountLoader<'info, OpInfo>,
+
+    /// System config
+    #[account(mut, seeds = [ROOT, b"CONFIG"], bump)]
+    config: Box<Account<'info, Config>>,
+
+    /// Gov protocol info
+    #[account(seeds = [ROOT, b"PROTOCOL", gov_protocol_id()], bump)]
+    gov_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// Pending gov change
+    #[account(
+        mut,
+        seeds = [ROOT, b"PENDING_GOV", &op_hash],
+        bump,
+        has_one = payer,
+        close = payer
+    )]
+    pending_change: Box<Account<'info, PendingGovChange>>,
+
+    /// Target protocol info
+    #[account(
+        mut,
+        seeds = [ROOT, b"PROTOCOL", &pending_change.target_protocol_id],
+        bump
+    )]
+    target_protocol_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// Rent receiver
+    /// CHECK: checked against the payer stored in the pending change account
+    #[account(mut)]
+    payer: AccountInfo<'info>,
+}
+
+/// Represents the accounts context for vetoing a timelocked gov operation.
+///
+/// # Fields
+///
+/// * `guardian` - The guardian set in the timelock settings, which must be a signer.
+/// * `gov_timelock` - The timelock settings account.
+/// * `pending_change` - The pending change account, closed without applying the change.
+/// * `payer` - The payer of the pending change account, receives its rent back.
+///
+/// # Arguments
+///
+/// * `op_hash` - The hash of the queued gov operation.
+///
+#[derive(Accounts)]
+#[instruction(op_hash: Vec<u8>)]
+pub struct VetoGovChange<'info> {
+    /// Guardian account
+    #[account(signer, address = gov_timelock.guardian @ CustomError::InvalidGuardian)]
+    guardian: Signer<'info>,
+
+    /// Gov timelock settings
+    #[account(seeds = [ROOT, b"GOV_TIMELOCK"], bump)]
+    gov_timelock: Box<Account<'info, GovTimelock>>,
+
+    /// Pending gov change
+    #[account(
+        mut,
+        seeds = [ROOT, b"PENDING_GOV", &op_hash],
+        bump,
+        has_one = payer,
+        close = payer
+    )]
+    pending_change: Box<Account<'info, PendingGovChange>>,
+
+    /// Rent receiver
+    /// CHECK: checked against the payer stored in the pending change account
+    #[account(mut)]
+    payer: AccountInfo<'info>,
+}
+
+/// Represents the accounts context for setting the gov timelock.
+///
+/// # Fields
+///
+/// * `admin` - The admin account, which must be a signer and pays for the timelock account.
+/// * `config` - The system configuration account.
+/// * `gov_timelock` - The timelock settings account, initialized if needed.
+/// * `system_program` - The system program.
+///
+#[derive(Accounts)]
+pub struct SetGovTimelock<'info> {
+    /// Admin account
+    #[account(signer, mut, address = config.admin @ CustomError::IsNotAdmin)]
+    admin: Signer<'info>,
+
+    /// System config
+    #[account(seeds = [ROOT, b"CONFIG"], bump)]
+    config: Box<Account<'info, Config>>,
+
+    /// Gov timelock settings
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = GovTimelock::LEN,
+        seeds = [ROOT, b"GOV_TIMELOCK"],
         bump
     )]
-    target_protocol_info: Box<Account<'info, ProtocolInfo>>,
+    gov_timelock: Box<Account<'info, GovTimelock>>,
 
     /// System program
     system_program: Program<'info, System>,
 }
 
+/// Represents the accounts context for updating the config by the upgrade authority of the program.
+///
+/// # Fields
+///
+/// * `deployer` - The upgrade authority of the program, which must be a signer.
+/// * `config` - The system configuration account.
+/// * `program_data` - The program data account holding the upgrade authority of the program.
+///
 #[derive(Accounts)]
 pub struct SetAdmin<'info> {
     /// Deployer address
-    #[account(signer, address = DEPLOYER.parse().expect("Deployer key not set"))]
+    #[account(
+        signer,
+        constraint = program_data.upgrade_authority_address == Some(deployer.key()) @ CustomError::IsNotUpgradeAuthority
+    )]
     pub deployer: Signer<'info>,
 
     /// Config address
     #[account(mut, seeds = [ROOT, b"CONFIG"], bump)]
     pub config: Box<Account<'info, Config>>,
+
+    /// Program data
+    #[account(seeds = [crate::ID.as_ref()], bump, seeds::program = bpf_loader_upgradeable::ID)]
+    pub program_data: Box<Account<'info, ProgramData>>,
+}
+
+/// Represents the accounts context necessary for migrating an account stored in an outdated layout.
+///
+/// # Fields
+///
+/// * `authority` - The admin or a gov executor, which must be a signer and pays for the increased account size.
+/// * `config` - The system configuration account, identified using seeds and a bump.
+/// * `gov_info` - The governance protocol information account, identified using seeds and a bump.
+/// * `account` - The config, protocol info or operation info account to be migrated.
+/// * `system_program` - The system program.
+///
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// Admin or gov executor account
+    #[account(signer, mut)]
+    authority: Signer<'info>,
+
+    /// System config
+    /// CHECK: read in any supported layout during the migration
+    #[account(seeds = [ROOT, b"CONFIG"], bump)]
+    config: AccountInfo<'info>,
+
+    /// Gov protocol info
+    /// CHECK: read in any supported layout during the migration
+    #[account(seeds = [ROOT, b"PROTOCOL", gov_protocol_id()], bump)]
+    gov_info: AccountInfo<'info>,
+
+    /// Account to be migrated
+    /// CHECK: the layout is checked during the migration
+    #[account(mut, owner = ID)]
+    account: AccountInfo<'info>,
+
+    /// System program
+    system_program: Program<'info, System>,
+}
+
+/// Represents the accounts context necessary for the protocol owner to manage its proposers.
+///
+/// # Fields
+///
+/// * `owner` - The protocol owner set by gov, which must be a signer.
+/// * `protocol_info` - The protocol information account, which is mutable and identified using seeds and a bump based on the provided `protocol_id`.
+///
+#[derive(Accounts)]
+#[instruction(protocol_id: Vec<u8>)]
+pub struct ManageProtocol<'info> {
+    /// Protocol owner account
+    #[account(
+        signer,
+        constraint = ProtocolInfo::load_checked(&protocol_info)?.owner() == Some(owner.key()) @ CustomError::InvalidProtocolOwner
+    )]
+    owner: Signer<'info>,
+
+    /// Protocol info
+    #[account(mut, seeds = [ROOT, b"PROTOCOL", &protocol_id], bump)]
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
+}
+
+/// Represents the accounts context necessary for acknowledging the delivery of an outbound proposal.
+///
+/// # Fields
+///
+/// * `authority` - A gov executor or an executor of the protocol, which must be a signer.
+/// * `gov_info` - The governance protocol information account, identified using seeds and a bump.
+/// * `protocol_info` - The protocol information account, identified using seeds and a bump based on the provided `protocol_id`.
+/// * `outbox` - The outbox account to be closed, identified using seeds and a bump based on the provided `protocol_id` and `nonce`.
+/// * `payer` - The account receiving the rent back, which must match the payer stored in the outbox account.
+///
+#[derive(Accounts)]
+#[instruction(protocol_id: Vec<u8>, nonce: u64)]
+pub struct AckOutbound<'info> {
+    /// Gov or protocol executor account
+    #[account(
+        signer,
+        constraint = outbox::is_ack_authority(&authority.key(), &gov_info, &protocol_info)? @ CustomError::ExecutorIsNotAllowed
+    )]
+    authority: Signer<'info>,
+
+    /// Gov protocol info
+    #[account(seeds = [ROOT, b"PROTOCOL", gov_protocol_id()], bump)]
+    gov_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// Protocol info
+    #[account(seeds = [ROOT, b"PROTOCOL", &protocol_id], bump)]
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
+
+    /// Outbox account
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [ROOT, b"OUTBOX", &protocol_id, &nonce.to_be_bytes()],
+        bump
+    )]
+    outbox: Box<Account<'info, outbox::OutboundProposal>>,
+
+    /// Rent receiver
+    /// CHECK: checked against the payer stored in the outbox account
+    #[account(mut)]
+    payer: AccountInfo<'info>,
+}
+
+/// Represents the accounts context for reading the operation status.
+///
+/// # Fields
+///
+/// * `op_info` - The operation information account, identified using seeds and a bump based on the provided `op_hash`, it may not exist yet.
+///
+#[derive(Accounts)]
+#[instruction(op_hash: Vec<u8>)]
+pub struct GetOpStatus<'info> {
+    /// Operation info
+    /// CHECK: empty if the operation hasn't been loaded, the owner is checked otherwise
+    #[account(seeds = [ROOT, b"OP", &op_hash], bump)]
+    op_info: AccountInfo<'info>,
+}
+
+/// Represents the accounts context for reading the protocol settings.
+///
+/// # Fields
+///
+/// * `protocol_info` - The protocol information account, identified using seeds and a bump based on the provided `protocol_id`.
+///
+#[derive(Accounts)]
+#[instruction(protocol_id: Vec<u8>)]
+pub struct GetProtocolInfo<'info> {
+    /// Protocol info
+    #[account(seeds = [ROOT, b"PROTOCOL", &protocol_id], bump)]
+    protocol_info: AccountLoader<'info, ProtocolInfo>,
+}
+
+/// Represents the accounts context for reading the next proposal nonce.
+///
+/// # Fields
+///
+/// * `config` - The system configuration account, identified using seeds and a bump.
+///
+#[derive(Accounts)]
+pub struct GetNextNonce<'info> {
+    /// System config
+    #[account(seeds = [ROOT, b"CONFIG"], bump)]
+    config: Box<Account<'info, Config>>,
 }
 
 /// Represents the photon cross-chain messaging configuration stored in a Solana account.
@@ -934,25 +1662,32 @@ pub struct SetAdmin<'info> {
 ///
 /// # Fields
 ///
+/// * `version` - The version of the account layout.
 /// * `admin` - The public key of the administrator.
 /// * `eob_chain_id` - The chain ID for the Entangle Oracle Blockchain.
 /// * `eob_master_smart_contract` - The address of the master smart contract.
 /// * `nonce` - A unique identifier.
+/// * `chain_id` - The chain ID of Solana within the Photon cross-chain messaging layer.
 ///
 /// # Usage
 ///
-/// The `Config` struct is used as part of the photon cross-chain messaging layer.
+/// The `Config` struct is used as part of the photon cross-chain messaging layer. Accounts created
+/// without the version byte have to be migrated with `migrate_account` first.
 #[account]
 #[derive(Default)]
 pub struct Config {
+    version: u8,
     admin: Pubkey,
     eob_chain_id: u64,
     eob_master_smart_contract: [u8; 32],
     nonce: u64,
+    chain_id: u128,
 }
 
 impl Config {
-    pub const LEN: usize = 8 + 32 * 2 + 8 * 2;
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 8 + 1 + 32 * 2 + 8 * 2 + 16;
+    pub const LEGACY_LEN: usize = 8 + 32 * 2 + 8 * 2;
 }
 
 /// Represents the information for a protocol within the Photon cross-chain messaging layer.
@@ -962,29 +1697,48 @@ impl Config {
 ///
 /// # Fields
 ///
+/// * `version` - The version of the account layout.
 /// * `is_init` - Indicates whether the protocol is initialized.
+/// * `exclusive_window_slots` - The number of slots of an executor turn, the executor in charge alone may load, sign and execute an operation during its turn, zero disables the assignment.
 /// * `consensus_target_rate` - The rate of signing operations to be executed.
-/// * `protocol_address` - The public key of the protocol.
+/// * `protocol_address` - The first receiver program of the protocol.
 /// * `transmitters` - The Ethereum addresses of entities that sign operations for execution.
 /// * `executors` - The Solana addresses authorized to execute operations in the Photon Endpoint Solana program.
 /// * `proposers` - The accounts permitted to call the Photon Endpoint for emitting a `Propose` event, which is meant for execution in a destination chain that is not Solana.
+/// * `rate_limit` - The optional limits of the operations executed for the protocol, see [RateLimit].
+/// * `executor_reward` - The lamports paid to the executor out of the protocol gas vault for every executed operation.
+/// * `owner` - The optional protocol owner managing proposers without gov, the default key disables it.
+/// * `owner_max_proposers` - The number of proposers the owner may bring the protocol up to.
+/// * `extra_protocol_addresses` - The other receiver programs of the protocol, see [ProtocolInfo::protocol_addresses].
 ///
 /// # Usage
 ///
-/// The `ProtocolInfo` struct is utilized in the Photon cross-chain messaging layer.
-#[account]
-#[derive(Default)]
+/// The `ProtocolInfo` struct is utilized in the Photon cross-chain messaging layer. It is a zero-copy
+/// account, so instructions read only the fields they need. Accounts created in the legacy borsh layout
+/// have to be migrated with `migrate_account` first.
+#[account(zero_copy)]
 pub struct ProtocolInfo {
-    is_init: bool,
+    version: u8,
+    is_init: u8,
+    _padding: [u8; 2],
+    exclusive_window_slots: u32,
     consensus_target_rate: u64,
     protocol_address: Pubkey,
-    transmitters: Box<[EthAddress; 20]>, // cannot use const with anchor
-    executors: Box<[Pubkey; 20]>,
-    proposers: Box<[Pubkey; 20]>,
+    transmitters: [EthAddress; 20], // cannot use const with anchor
+    executors: [Pubkey; 20],
+    proposers: [Pubkey; 20],
+    rate_limit: RateLimit,
+    executor_reward: u64,
+    owner: Pubkey,
+    owner_max_proposers: u32,
+    _owner_padding: [u8; 4],
+    extra_protocol_addresses: [Pubkey; MAX_PROTOCOL_ADDRESSES - 1],
 }
 
 impl ProtocolInfo {
-    pub const LEN: usize =
+    pub const VERSION: u8 = 1;
+    pub const LEN: usize = 8 + std::mem::size_of::<ProtocolInfo>();
+    pub const LEGACY_LEN: usize =
         8 + 1 + 8 + 32 + (20 * MAX_TRANSMITTERS) + (32 * MAX_EXECUTORS) + (32 * MAX_PROPOSERS);
 
     pub fn transmitters(&self) -> Vec<EthAddress> {
@@ -998,6 +1752,107 @@ impl ProtocolInfo {
     pub fn proposers(&self) -> Vec<Pubkey> {
         self.proposers.into_iter().take_while(|k| k != &Pubkey::default()).collect()
     }
+
+    /// Returns the programs allowed to receive the operations of the protocol, `protocol_address`
+    /// goes first followed by `extra_protocol_addresses`
+    pub fn protocol_addresses(&self) -> Vec<Pubkey> {
+        std::iter::once(self.protocol_address)
+            .chain(self.extra_protocol_addresses)
+            .take_while(|k| k != &Pubkey::default())
+            .collect()
+    }
+
+    pub fn rate_limit(&self) -> &RateLimit {
+        &self.rate_limit
+    }
+
+    pub fn exclusive_window_slots(&self) -> u32 {
+        self.exclusive_window_slots
+    }
+
+    pub fn executor_reward(&self) -> u64 {
+        self.executor_reward
+    }
+
+    pub fn owner(&self) -> Option<Pubkey> {
+        (self.owner != Pubkey::default()).then_some(self.owner)
+    }
+
+    /// Returns the executor in charge of the operation at `slot` if the assignment is enabled for the
+    /// protocol. Executors take turns of `exclusive_window_slots`, the operation is assigned by the
+    /// first eight bytes of its hash modulo the number of executors and handed over to the next
+    /// executor once the turn expires, so exactly one executor may process it at any slot.
+    pub fn assigned_executor(&self, op_hash: &[u8], slot: u64) -> Option<Pubkey> {
+        let executors = self.executors();
+        if self.exclusive_window_slots == 0 || executors.is_empty() || op_hash.len() < 8 {
+            return None;
+        }
+        let len = executors.len() as u64;
+        let hash = u64::from_be_bytes(op_hash[..8].try_into().expect("8 bytes slice"));
+        let turn = slot / self.exclusive_window_slots as u64;
+        Some(executors[((hash % len + turn % len) % len) as usize])
+    }
+
+    /// Returns the slot the turn containing `slot` expires at, zero if the assignment is disabled
+    pub fn turn_expires_at(&self, slot: u64) -> u64 {
+        let window = self.exclusive_window_slots as u64;
+        match window {
+            0 => 0,
+            _ => (slot / window + 1) * window,
+        }
+    }
+
+    /// Returns whether the assignment is disabled or the `executor` is in charge at `slot`
+    pub fn is_executor_allowed(&self, op_hash: &[u8], executor: &Pubkey, slot: u64) -> bool {
+        match self.assigned_executor(op_hash, slot) {
+            Some(assigned) => assigned == *executor,
+            None => true,
+        }
+    }
+
+    /// Checks that the `executor` may process the operation at the current slot
+    fn check_executor(&self, op_hash: &[u8], executor: &Pubkey) -> Result<()> {
+        let slot = Clock::get()?.slot;
+        require!(
+            self.is_executor_allowed(op_hash, executor, slot),
+            CustomError::ExecutorIsNotAssigned
+        );
+        Ok(())
+    }
+
+    /// Loads the protocol info checking that the account is stored in the current layout
+    pub fn load_checked<'a>(
+        loader: &'a AccountLoader<'_, ProtocolInfo>,
+    ) -> Result<Ref<'a, ProtocolInfo>> {
+        require_eq!(loader.as_ref().data_len(), Self::LEN, CustomError::AccountNotMigrated);
+        let protocol_info = loader.load()?;
+        require_eq!(protocol_info.version, Self::VERSION, CustomError::AccountNotMigrated);
+        Ok(protocol_info)
+    }
+
+    /// Mutable counterpart of [ProtocolInfo::load_checked]
+    pub fn load_checked_mut<'a>(
+        loader: &'a AccountLoader<'_, ProtocolInfo>,
+    ) -> Result<RefMut<'a, ProtocolInfo>> {
+        require_eq!(loader.as_ref().data_len(), Self::LEN, CustomError::AccountNotMigrated);
+        let protocol_info = loader.load_mut()?;
+        require_eq!(protocol_info.version, Self::VERSION, CustomError::AccountNotMigrated);
+        Ok(protocol_info)
+    }
+
+    /// Loads the protocol info created with `init_if_needed`. The discriminator of a just created
+    /// account is only written on exit, so such an account is initialized with the current version.
+    pub fn load_or_init<'a>(
+        loader: &'a AccountLoader<'_, ProtocolInfo>,
+    ) -> Result<RefMut<'a, ProtocolInfo>> {
+        let is_created = loader.as_ref().try_borrow_data()?[..8] == [0; 8];
+        if !is_created {
+            return Self::load_checked_mut(loader);
+        }
+        let mut protocol_info = loader.load_init()?;
+        protocol_info.version = Self::VERSION;
+        Ok(protocol_info)
+    }
 }
 
 /// Represents information about an operation in the Photon cross-chain messaging layer.
@@ -1007,20 +1862,118 @@ impl ProtocolInfo {
 ///
 /// # Fields
 ///
+/// * `version` - The version of the account layout.
 /// * `status` - The current status of the operation.
 /// * `unique_signers` - An array of unique Ethereum addresses that have signed the operation.
-/// * `op_data` - The data related to the operation.
-#[account]
-#[derive(Default)]
+/// * `protocol_id` - The protocol id of the operation, used to derive the protocol accounts.
+/// * `op_data_len` - The length of the serialized operation data.
+/// * `loader` - The executor that loaded the operation and paid the rent of the account, it is the
+///   one reimbursed for the rent once the operation is executed.
+/// * `held_until` - The slot the operation held by the rate limit is released at, zero if it is not
+///   recorded as held, see [RateLimit].
+///
+/// # Layout
+///
+/// The struct is a zero-copy header that is followed by the borsh serialized [OperationData], so
+/// signing doesn't touch the operation data at all, while executing deserializes it only once.
+/// Accounts created in the legacy borsh layout have to be migrated with `migrate_account` first.
+#[account(zero_copy)]
 pub struct OpInfo {
-    pub status: OpStatus,
+    version: u8,
+    status: u8,
     unique_signers: [EthAddress; 16],
-    pub op_data: OperationData,
+    pub protocol_id: Bytes32,
+    _padding: [u8; 2],
+    op_data_len: u32,
+    loader: Pubkey,
+    held_until: u64,
 }
 
 impl OpInfo {
+    pub const VERSION: u8 = 1;
+    pub const HEADER_LEN: usize = std::mem::size_of::<OpInfo>();
+
     pub fn len(op_data: &OperationData) -> usize {
-        8 + 1 + 20 * 16 + borsh::to_vec(op_data).expect("fixed struct serialization").len()
+        8 + Self::HEADER_LEN + borsh::to_vec(op_data).expect("fixed struct serialization").len()
+    }
+
+    pub fn status(&self) -> OpStatus {
+        match self.status {
+            1 => OpStatus::Init,
+            2 => OpStatus::Signed,
+            3 => OpStatus::Executed,
+            _ => OpStatus::None,
+        }
+    }
+
+    fn set_status(&mut self, status: OpStatus) {
+        self.status = status as u8;
+    }
+
+    pub fn held_until(&self) -> u64 {
+        self.held_until
+    }
+
+    /// Returns the executor that loaded the operation, `None` for operations loaded before the
+    /// loader was recorded
+    pub fn loader(&self) -> Option<Pubkey> {
+        (self.loader != Pubkey::default()).then_some(self.loader)
+    }
+
+    fn is_current(&self, data_len: usize) -> bool {
+        self.version == Self::VERSION
+            && 8 + Self::HEADER_LEN + self.op_data_len as usize == data_len
+    }
+
+    /// Loads the operation info checking that the account is stored in the current layout
+    pub fn load_checked<'a>(loader: &'a AccountLoader<'_, OpInfo>) -> Result<Ref<'a, OpInfo>> {
+        let data_len = loader.as_ref().data_len();
+        require_gte!(data_len, 8 + Self::HEADER_LEN, CustomError::AccountNotMigrated);
+        let op_info = loader.load()?;
+        require!(op_info.is_current(data_len), CustomError::AccountNotMigrated);
+        Ok(op_info)
+    }
+
+    /// Mutable counterpart of [OpInfo::load_checked]
+    pub fn load_checked_mut<'a>(
+        loader: &'a AccountLoader<'_, OpInfo>,
+    ) -> Result<RefMut<'a, OpInfo>> {
+        let data_len = loader.as_ref().data_len();
+        require_gte!(data_len, 8 + Self::HEADER_LEN, CustomError::AccountNotMigrated);
+        let op_info = loader.load_mut()?;
+        require!(op_info.is_current(data_len), CustomError::AccountNotMigrated);
+        Ok(op_info)
+    }
+
+    /// Deserializes the operation data stored behind the header
+    pub fn op_data(loader: &AccountLoader<'_, OpInfo>) -> Result<OperationData> {
+        Self::load_checked(loader)?;
+        let data = loader.as_ref().try_borrow_data()?;
+        Ok(OperationData::try_from_slice(&data[8 + Self::HEADER_LEN..])?)
+    }
+
+    /// Reads the operation status from the raw account data, e.g. fetched by an off-chain client.
+    /// Accounts stored in the legacy layout are accepted as well.
+    pub fn status_from_account_data(data: &[u8]) -> Result<OpStatus> {
+        Ok(migration::op_info_from_account_data(data)?.0.status())
+    }
+
+    fn init(
+        loader: &AccountLoader<'_, OpInfo>,
+        op_data: &OperationData,
+        executor: Pubkey,
+    ) -> Result<()> {
+        let serialized = op_data.try_to_vec()?;
+        {
+            let mut op_info = loader.load_init()?;
+            op_info.version = Self::VERSION;
+            op_info.set_status(OpStatus::Init);
+            op_info.protocol_id.copy_from_slice(&op_data.protocol_id);
+            op_info.op_data_len = serialized.len() as u32;
+            op_info.loader = executor;
+        }
+        loader.as_ref().try_borrow_mut_data()?[8 + Self::HEADER_LEN..].copy_from_slice(&serialized);
+        Ok(())
     }
 }
 
@@ -1075,6 +2028,45 @@ pub struct ProposalExecuted {
     pub op_hash: Vec<u8>,
     pub executor: Pubkey,
 }
+
+/// Emitted when an operation is held instead of being executed because it exceeds the rate limit of
+/// the protocol. The operation stays `Signed` and can be executed again starting from `retry_slot`,
+/// it is released ahead of new operations if it is recorded as held in the operation info.
+///
+/// # Fields
+///
+/// * `op_hash` - The hash of the held operation.
+/// * `executor` - The public key of the account that tried to execute the operation.
+/// * `retry_slot` - The slot the current rate limit window ends at or the recorded release slot.
+///
+#[derive(Debug)]
+#[event]
+pub struct ProposalHeld {
+    pub op_hash: Vec<u8>,
+    pub executor: Pubkey,
+    pub retry_slot: u64,
+}
+
+/// Emitted when the executor of an operation is paid out of the gas vault of the protocol.
+///
+/// # Fields
+///
+/// * `op_hash` - The hash of the executed operation.
+/// * `executor` - The public key of the account that executed the operation.
+/// * `loader` - The public key of the account that loaded the operation and got the rent back.
+/// * `rent` - The lamports reimbursed for the rent of the operation account.
+/// * `reward` - The lamports paid as the executor reward.
+///
+#[derive(Debug)]
+#[event]
+pub struct ExecutorReimbursed {
+    pub op_hash: Vec<u8>,
+    pub executor: Pubkey,
+    pub loader: Pubkey,
+    pub rent: u64,
+    pub reward: u64,
+}
+
 /// Represents an event emitted when an associated program, registered in the protocol
 /// info as a proposer, proposes an operation.
 ///
@@ -1094,6 +2086,11 @@ pub struct ProposalExecuted {
 /// * `protocol_address` - The address of the protocol on the destination chain, represented as a vector of bytes.
 /// * `function_selector` - The function selector for the operation, formatted as a vector of bytes.
 /// * `params` - The parameters required for executing the proposed function, provided as a vector of bytes.
+/// * `proposer` - The whitelisted proposer that has sent the proposal.
+/// * `caller_program` - The program that has called `propose` through CPI, known if the instructions sysvar is passed.
+///
+/// The sender is passed to the destination side in the `reserved` field of the outbound operation,
+/// see [protocol_data::ProposerIdentity].
 ///
 /// # Usage
 ///
@@ -1110,4 +2107,142 @@ pub struct ProposeEvent {
     pub protocol_address: Vec<u8>,
     pub function_selector: Vec<u8>,
     pub params: Vec<u8>,
+    pub proposer: Pubkey,
+    pub caller_program: Option<Pubkey>,
+}
+
+/// Emitted when a gov operation is applied, so changes of the protocol settings can be audited
+/// without diffing account snapshots.
+///
+/// # Fields
+///
+/// * `op_hash` - The hash of the gov operation.
+/// * `protocol_id` - The identifier of the protocol whose settings are changed.
+/// * `change` - The old and the new values of the changed settings, see [gov::GovChange].
+///
+#[derive(Debug)]
+#[event]
+pub struct GovOperationApplied {
+    pub op_hash: Vec<u8>,
+    pub protocol_id: Vec<u8>,
+    pub change: gov::GovChange,
+}
+
+/// Emitted when a timelocked gov operation is queued instead of being applied.
+///
+/// # Fields
+///
+/// * `op_hash` - The hash of the gov operation.
+/// * `protocol_id` - The identifier of the protocol whose settings are to be changed.
+/// * `code` - The code of the gov operation.
+/// * `executable_slot` - The slot the change may be applied at.
+///
+#[derive(Debug)]
+#[event]
+pub struct GovChangeQueued {
+    pub op_hash: Vec<u8>,
+    pub protocol_id: Vec<u8>,
+    pub code: u32,
+    pub executable_slot: u64,
+}
+
+/// Emitted when the guardian vetoes a timelocked gov operation.
+///
+/// # Fields
+///
+/// * `op_hash` - The hash of the gov operation.
+/// * `protocol_id` - The identifier of the protocol whose settings were to be changed.
+/// * `guardian` - The guardian that vetoed the change.
+///
+#[derive(Debug)]
+#[event]
+pub struct GovChangeVetoed {
+    pub op_hash: Vec<u8>,
+    pub protocol_id: Vec<u8>,
+    pub guardian: Pubkey,
+}
+
+/// Emitted when the protocol owner changes the proposers of its protocol.
+///
+/// # Fields
+///
+/// * `protocol_id` - The identifier of the protocol.
+/// * `owner` - The owner of the protocol.
+/// * `change` - The old and the new values of the changed settings, see [gov::GovChange].
+///
+#[derive(Debug)]
+#[event]
+pub struct OwnerActionApplied {
+    pub protocol_id: Vec<u8>,
+    pub owner: Pubkey,
+    pub change: gov::GovChange,
+}
+
+/// Emitted when the delivery of an outbound proposal is acknowledged and its outbox account is closed.
+///
+/// # Fields
+///
+/// * `protocol_id` - The identifier of the protocol.
+/// * `nonce` - The nonce of the proposal.
+/// * `authority` - The executor that acknowledged the proposal.
+///
+#[derive(Debug)]
+#[event]
+pub struct OutboundAcked {
+    pub protocol_id: Vec<u8>,
+    pub nonce: u64,
+    pub authority: Pubkey,
+}
+
+/// Emitted when an account stored in an outdated layout is migrated to the current one.
+///
+/// # Fields
+///
+/// * `account` - The address of the migrated account.
+/// * `version` - The version of the layout the account is migrated to.
+///
+#[derive(Debug)]
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_executor_turns() {
+        let executors = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut protocol_info = ProtocolInfo::zeroed();
+        protocol_info.executors[..3].copy_from_slice(&executors);
+        protocol_info.exclusive_window_slots = 10;
+        let op_hash = [7; 32];
+        let assigned = protocol_info.assigned_executor(&op_hash, 0).unwrap();
+        let others: Vec<_> = executors.iter().filter(|k| **k != assigned).collect();
+
+        // Only the executor in charge may load, sign or execute until its turn expires
+        assert_eq!(protocol_info.turn_expires_at(5), 10);
+        assert!(protocol_info.is_executor_allowed(&op_hash, &assigned, 9));
+        assert!(others.iter().all(|k| !protocol_info.is_executor_allowed(&op_hash, k, 9)));
+
+        // Every executor is in charge once within a round of turns
+        let turns: Vec<_> = (0..3)
+            .map(|turn| protocol_info.assigned_executor(&op_hash, turn * 10).unwrap())
+            .collect();
+        assert!(executors.iter().all(|k| turns.contains(k)));
+        assert!(!protocol_info.is_executor_allowed(&op_hash, &assigned, 10));
+        assert_eq!(protocol_info.assigned_executor(&op_hash, 30), Some(assigned));
+
+        protocol_info.exclusive_window_slots = 0;
+        assert_eq!(protocol_info.assigned_executor(&op_hash, 100), None);
+        assert_eq!(protocol_info.turn_expires_at(100), 0);
+        assert!(others.iter().all(|k| protocol_info.is_executor_allowed(&op_hash, k, 9)));
+    }
 }
@@ -0,0 +1,363 @@
+//! The `migration` module upgrades accounts created by earlier versions of the Photon Endpoint program
+//! to the current layouts and reads accounts stored in any supported layout.
+//!
+//! ## Overview
+//! `Config`, `ProtocolInfo` and `OpInfo` start with a version byte. Accounts created before the version
+//! byte was introduced are treated as version 0: `Config` is the same borsh struct without the version,
+//! while `ProtocolInfo` and `OpInfo` used to be borsh serialized rather than zero-copy. Every layout
+//! shares the account discriminator with its legacy counterpart, therefore a legacy account is
+//! recognized by its exact borsh layout: the data has to be consumed completely and, for operations,
+//! the stored operation data has to hash to the `op_hash` the account is derived from.
+//!
+//! Fields missing from the legacy layouts are zeroed by the migration: a migrated `ProtocolInfo` has no
+//! rate limit, executor reward, owner or extra protocol addresses, a migrated `OpInfo` has no loader,
+//! so the rent of such an operation is reimbursed to its executor. A migrated
+//! `Config` has no chain ID until the upgrade authority sets it with `set_chain_id`.
+//!
+//! ## Migration
+//! The `migrate_account` instruction detects the account kind by its discriminator and upgrades it in
+//! place: the account is reallocated to the new size, the caller pays the rent difference and the data
+//! is rewritten in the current layout. Migrating an account that is already current is a no-op.
+//! Accounts that are not migrated are rejected by every instruction using them.
+//!
+//! ## Off-chain usage
+//! `protocol_info_from_account_data` and `op_info_from_account_data` convert raw account data in either
+//! layout into the current one, so clients keep working while the accounts are migrated.
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+use bytemuck::Zeroable;
+
+use crate::{
+    error::CustomError,
+    photon::ROOT,
+    protocol_data::{OpStatus, OperationData},
+    util::EthAddress,
+    AccountMigrated, Config, OpInfo, ProtocolInfo,
+};
+
+/// The layout of `Config` preceding the versioned one.
+#[derive(AnchorDeserialize)]
+struct LegacyConfig {
+    admin: Pubkey,
+    eob_chain_id: u64,
+    eob_master_smart_contract: [u8; 32],
+    nonce: u64,
+}
+
+impl From<LegacyConfig> for Config {
+    fn from(legacy: LegacyConfig) -> Self {
+        Config {
+            version: Config::VERSION,
+            admin: legacy.admin,
+            eob_chain_id: legacy.eob_chain_id,
+            eob_master_smart_contract: legacy.eob_master_smart_contract,
+            nonce: legacy.nonce,
+            chain_id: 0,
+        }
+    }
+}
+
+/// The borsh layout of `ProtocolInfo` preceding the zero-copy one.
+#[derive(AnchorDeserialize)]
+struct LegacyProtocolInfo {
+    is_init: bool,
+    consensus_target_rate: u64,
+    protocol_address: Pubkey,
+    transmitters: [EthAddress; 20],
+    executors: [Pubkey; 20],
+    proposers: [Pubkey; 20],
+}
+
+impl From<LegacyProtocolInfo> for ProtocolInfo {
+    fn from(legacy: LegacyProtocolInfo) -> Self {
+        let mut protocol_info = ProtocolInfo::zeroed();
+        protocol_info.version = ProtocolInfo::VERSION;
+        protocol_info.is_init = legacy.is_init as u8;
+        protocol_info.consensus_target_rate = legacy.consensus_target_rate;
+        protocol_info.protocol_address = legacy.protocol_address;
+        protocol_info.transmitters = legacy.transmitters;
+        protocol_info.executors = legacy.executors;
+        protocol_info.proposers = legacy.proposers;
+        protocol_info
+    }
+}
+
+/// The borsh layout of `OpInfo` preceding the zero-copy one.
+#[derive(AnchorDeserialize)]
+struct LegacyOpInfo {
+    status: OpStatus,
+    unique_signers: [EthAddress; 16],
+    op_data: OperationData,
+}
+
+impl LegacyOpInfo {
+    const HEADER_LEN: usize = 1 + 20 * 16;
+}
+
+/// Reads the config stored in any supported layout
+pub fn config_from_account_data(data: &[u8]) -> Result<Config> {
+    require!(
+        data.len() >= 8 && data[..8] == Config::discriminator(),
+        CustomError::InvalidAccountLayout
+    );
+    if data.len() == Config::LEGACY_LEN {
+        let legacy = LegacyConfig::try_from_slice(&data[8..])
+            .map_err(|_| CustomError::InvalidAccountLayout)?;
+        return Ok(legacy.into());
+    }
+    require_eq!(data.len(), Config::LEN, CustomError::InvalidAccountLayout);
+    let config = Config::try_deserialize(&mut &data[..])?;
+    require_eq!(config.version, Config::VERSION, CustomError::InvalidAccountLayout);
+    Ok(config)
+}
+
+/// Reads the protocol info stored in any supported layout
+pub fn protocol_info_from_account_data(data: &[u8]) -> Result<ProtocolInfo> {
+    require!(
+        data.len() >= 8 && data[..8] == ProtocolInfo::discriminator(),
+        CustomError::InvalidAccountLayout
+    );
+    if data.len() == ProtocolInfo::LEGACY_LEN {
+        let legacy = LegacyProtocolInfo::try_from_slice(&data[8..])
+            .map_err(|_| CustomError::InvalidAccountLayout)?;
+        return Ok(legacy.into());
+    }
+    require_eq!(data.len(), ProtocolInfo::LEN, CustomError::InvalidAccountLayout);
+    let protocol_info: ProtocolInfo = bytemuck::pod_read_unaligned(&data[8..]);
+    require_eq!(protocol_info.version, ProtocolInfo::VERSION, CustomError::InvalidAccountLayout);
+    Ok(protocol_info)
+}
+
+/// Reads the operation info stored in any supported layout, returns the header in the current
+/// layout along with the serialized operation data
+pub fn op_info_from_account_data(data: &[u8]) -> Result<(OpInfo, &[u8])> {
+    require!(
+        data.len() >= 8 + LegacyOpInfo::HEADER_LEN && data[..8] == OpInfo::discriminator(),
+        CustomError::InvalidAccountLayout
+    );
+    if let Some(op_info) = current_op_info(data) {
+        return Ok((op_info, &data[8 + OpInfo::HEADER_LEN..]));
+    }
+    let legacy =
+        LegacyOpInfo::try_from_slice(&data[8..]).map_err(|_| CustomError::InvalidAccountLayout)?;
+    require_eq!(legacy.op_data.protocol_id.len(), 32, CustomError::InvalidAccountLayout);
+    let op_data = &data[8 + LegacyOpInfo::HEADER_LEN..];
+
+    let mut op_info = OpInfo::zeroed();
+    op_info.version = OpInfo::VERSION;
+    op_info.status = legacy.status as u8;
+    op_info.unique_signers = legacy.unique_signers;
+    op_info.protocol_id.copy_from_slice(&legacy.op_data.protocol_id);
+    op_info.op_data_len = op_data.len() as u32;
+    Ok((op_info, op_data))
+}
+
+fn current_op_info(data: &[u8]) -> Option<OpInfo> {
+    let op_info: OpInfo = bytemuck::pod_read_unaligned(data.get(8..8 + OpInfo::HEADER_LEN)?);
+    op_info.is_current(data.len()).then_some(op_info)
+}
+
+/// Checks whether the `authority` is either the admin or one of the gov executors. Both accounts
+/// are read in any supported layout, so they may be migrated in any order.
+pub(crate) fn is_migration_authority(
+    authority: &Pubkey,
+    config: &AccountInfo,
+    gov_info: &AccountInfo,
+) -> Result<bool> {
+    if &config_from_account_data(&config.try_borrow_data()?)?.admin == authority {
+        return Ok(true);
+    }
+    let gov_info = protocol_info_from_account_data(&gov_info.try_borrow_data()?)?;
+    Ok(gov_info.executors().contains(authority))
+}
+
+/// Upgrades the account stored in an outdated layout to the current one
+pub(crate) fn migrate_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let Some((migrated, version)) = migrated_data(account)? else {
+        return Ok(());
+    };
+    realloc(account, payer, system_program, migrated.len())?;
+    account.try_borrow_mut_data()?.copy_from_slice(&migrated);
+    emit!(AccountMigrated {
+        account: account.key(),
+        version
+    });
+    Ok(())
+}
+
+/// Returns the account data in the current layout along with its version or `None` if the account
+/// is current already
+fn migrated_data(account: &AccountInfo) -> Result<Option<(Vec<u8>, u8)>> {
+    let data = account.try_borrow_data()?;
+    let discriminator = data.get(..8).ok_or(CustomError::InvalidAccountLayout)?;
+
+    if discriminator == Config::discriminator() {
+        let config = config_from_account_data(&data)?;
+        if data.len() == Config::LEN {
+            return Ok(None);
+        }
+        let mut migrated = Vec::with_capacity(Config::LEN);
+        config.try_serialize(&mut migrated)?;
+        return Ok(Some((migrated, Config::VERSION)));
+    }
+
+    if discriminator == ProtocolInfo::discriminator() {
+        let protocol_info = protocol_info_from_account_data(&data)?;
+        if data.len() == ProtocolInfo::LEN {
+            return Ok(None);
+        }
+        let migrated = [discriminator, bytemuck::bytes_of(&protocol_info)].concat();
+        return Ok(Some((migrated, ProtocolInfo::VERSION)));
+    }
+
+    if discriminator == OpInfo::discriminator() {
+        if current_op_info(&data).is_some() {
+            return Ok(None);
+        }
+        let (op_info, op_data) = op_info_from_account_data(&data)?;
+        // The legacy layout is only accepted for the account the stored operation is derived from
+        let op_hash = OperationData::try_from_slice(op_data)?.op_hash_with_message()?;
+        let (address, _) = Pubkey::find_program_address(&[ROOT, b"OP", &op_hash], &crate::ID);
+        require_keys_eq!(address, account.key(), CustomError::InvalidAccountLayout);
+        let migrated = [discriminator, bytemuck::bytes_of(&op_info), op_data].concat();
+        return Ok(Some((migrated, OpInfo::VERSION)));
+    }
+
+    err!(CustomError::InvalidAccountLayout)
+}
+
+fn realloc<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    len: usize,
+) -> Result<()> {
+    let lamports = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if lamports > 0 {
+        let accounts = Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        transfer(CpiContext::new(system_program.clone(), accounts), lamports)?;
+    }
+    account.realloc(len, false)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::protocol_data::FunctionSelector;
+
+    fn op_data() -> OperationData {
+        OperationData {
+            protocol_id: vec![1; 32],
+            src_chain_id: 33133,
+            nonce: 7,
+            protocol_addr: Pubkey::new_unique(),
+            function_selector: FunctionSelector::ByName("increment".to_owned()),
+            params: vec![1, 2, 3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_layout_sizes() {
+        assert_eq!(Config::LEN, 105);
+        assert_eq!(Config::LEGACY_LEN, 88);
+        assert_eq!(ProtocolInfo::LEN, 2128);
+        assert_eq!(ProtocolInfo::LEGACY_LEN, 1729);
+        assert_eq!(OpInfo::HEADER_LEN, 400);
+    }
+
+    #[test]
+    fn test_config_from_legacy() {
+        let admin = Pubkey::new_unique();
+        let mut data = Config::discriminator().to_vec();
+        data.extend_from_slice(admin.as_ref());
+        data.extend_from_slice(&33133_u64.to_le_bytes());
+        data.extend_from_slice(&[3; 32]);
+        data.extend_from_slice(&42_u64.to_le_bytes());
+
+        let config = config_from_account_data(&data).unwrap();
+        assert_eq!(config.version, Config::VERSION);
+        assert_eq!(config.admin, admin);
+        assert_eq!(config.eob_chain_id, 33133);
+        assert_eq!(config.eob_master_smart_contract, [3; 32]);
+        assert_eq!(config.nonce, 42);
+
+        let mut migrated = Vec::new();
+        config.try_serialize(&mut migrated).unwrap();
+        assert_eq!(migrated.len(), Config::LEN);
+        assert_eq!(config_from_account_data(&migrated).unwrap().admin, admin);
+
+        migrated[8] = Config::VERSION + 1;
+        assert!(config_from_account_data(&migrated).is_err());
+    }
+
+    #[test]
+    fn test_protocol_info_from_legacy() {
+        let protocol_address = Pubkey::new_unique();
+        let executor = Pubkey::new_unique();
+        let mut data = ProtocolInfo::discriminator().to_vec();
+        data.push(1);
+        data.extend_from_slice(&5000_u64.to_le_bytes());
+        data.extend_from_slice(protocol_address.as_ref());
+        data.extend_from_slice(&[[2; 20]; 20].concat());
+        data.extend_from_slice(executor.as_ref());
+        data.resize(ProtocolInfo::LEGACY_LEN, 0);
+
+        let migrated = protocol_info_from_account_data(&data).unwrap();
+        assert_eq!(migrated.version, ProtocolInfo::VERSION);
+        assert_eq!(migrated.is_init, 1);
+        assert_eq!(migrated.consensus_target_rate, 5000);
+        assert_eq!(migrated.protocol_address, protocol_address);
+        assert_eq!(migrated.transmitters(), vec![[2; 20]; 20]);
+        assert_eq!(migrated.executors(), vec![executor]);
+        assert!(migrated.proposers().is_empty());
+
+        let current = [&data[..8], bytemuck::bytes_of(&migrated)].concat();
+        assert_eq!(protocol_info_from_account_data(&current).unwrap().executors(), vec![executor]);
+
+        let mut outdated = current.clone();
+        outdated[8] = ProtocolInfo::VERSION + 1;
+        assert!(protocol_info_from_account_data(&outdated).is_err());
+
+        data.push(0);
+        assert!(protocol_info_from_account_data(&data).is_err());
+    }
+
+    #[test]
+    fn test_op_info_from_legacy() {
+        let op_data = borsh::to_vec(&op_data()).unwrap();
+
+        let mut legacy = OpInfo::discriminator().to_vec();
+        legacy.push(OpStatus::Signed as u8);
+        legacy.extend_from_slice(&[[5; 20]; 16].concat());
+        legacy.extend_from_slice(&op_data);
+        let (op_info, legacy_op_data) = op_info_from_account_data(&legacy).unwrap();
+        assert!(op_info.status() == OpStatus::Signed);
+        assert_eq!(op_info.protocol_id, [1; 32]);
+        assert_eq!(op_info.unique_signers, [[5; 20]; 16]);
+        assert_eq!(legacy_op_data, &op_data[..]);
+        assert!(OpInfo::status_from_account_data(&legacy).unwrap() == OpStatus::Signed);
+
+        assert_eq!(op_info.loader(), None);
+
+        let mut op_info = op_info;
+        op_info.loader = Pubkey::new_unique();
+        let mut current = [&legacy[..8], bytemuck::bytes_of(&op_info), &op_data].concat();
+        assert_eq!(current_op_info(&current).unwrap().loader(), Some(op_info.loader));
+        assert!(OpInfo::status_from_account_data(&current).unwrap() == OpStatus::Signed);
+
+        current.pop();
+        assert!(OpInfo::status_from_account_data(&current).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! The `outbox` module keeps outbound proposals in durable accounts.
+//!
+//! ## Overview
+//! `ProposeEvent` lives only in the transaction logs, which may be truncated or pruned by the RPC
+//! node before the listener reads them. In the outbox mode `propose` is called along with a payer and
+//! the outbox account derived from `[ROOT, b"OUTBOX", protocol_id, nonce]`, so the proposal is also
+//! stored in an [OutboundProposal] account and can be reconstructed from it later.
+//!
+//! ## Rent reclaim
+//! Once the proposal is delivered, a gov executor or an executor of the protocol calls `ack_outbound`
+//! to close the account, and the rent is returned to the payer stored in it.
+//!
+//! The outbox accounts are optional, callers that don't pass them keep proposing through logs only.
+use anchor_lang::prelude::*;
+
+use crate::{photon::ROOT, ProposeEvent, ProtocolInfo};
+
+/// A proposal stored in the outbox account.
+///
+/// # Fields
+///
+/// * `payer` - The account that paid the rent and receives it back on `ack_outbound`.
+/// * `slot` - The slot the proposal was made at.
+/// * `proposer` - The proposer that has sent the proposal.
+/// * `caller_program` - The program that has called `propose` through CPI if known.
+/// * `nonce` - The nonce of the proposal.
+/// * `dst_chain_id` - The identifier of the destination chain.
+/// * `protocol_id` - The identifier of the protocol.
+/// * `protocol_address` - The address of the protocol on the destination chain.
+/// * `function_selector` - The serialized function selector.
+/// * `params` - The parameters of the proposed function.
+///
+#[account]
+#[derive(Debug, Default)]
+pub struct OutboundProposal {
+    pub payer: Pubkey,
+    pub slot: u64,
+    pub proposer: Pubkey,
+    pub caller_program: Option<Pubkey>,
+    pub nonce: u64,
+    pub dst_chain_id: u128,
+    pub protocol_id: Vec<u8>,
+    pub protocol_address: Vec<u8>,
+    pub function_selector: Vec<u8>,
+    pub params: Vec<u8>,
+}
+
+impl OutboundProposal {
+    /// Returns the account size for the proposal with the given variable length fields
+    pub fn len(
+        protocol_id: &[u8],
+        protocol_address: &[u8],
+        function_selector: &[u8],
+        params: &[u8],
+    ) -> usize {
+        8 + 32 * 2
+            + (1 + 32)
+            + 8 * 2
+            + 16
+            + 4 * 4
+            + protocol_id.len()
+            + protocol_address.len()
+            + function_selector.len()
+            + params.len()
+    }
+}
+
+impl From<OutboundProposal> for ProposeEvent {
+    fn from(value: OutboundProposal) -> Self {
+        ProposeEvent {
+            protocol_id: value.protocol_id,
+            nonce: value.nonce,
+            dst_chain_id: value.dst_chain_id,
+            protocol_address: value.protocol_address,
+            function_selector: value.function_selector,
+            params: value.params,
+            proposer: value.proposer,
+            caller_program: value.caller_program,
+        }
+    }
+}
+
+/// Returns the address of the outbox account for the protocol proposal with the given nonce
+pub fn outbox_address(protocol_id: &[u8], nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(&[ROOT, b"OUTBOX", protocol_id, &nonce.to_be_bytes()], &crate::ID)
+        .0
+}
+
+/// Checks if the key is a gov executor or an executor of the protocol, they are allowed to acknowledge
+/// the delivery of outbound proposals.
+pub(crate) fn is_ack_authority(
+    key: &Pubkey,
+    gov_info: &AccountLoader<'_, ProtocolInfo>,
+    protocol_info: &AccountLoader<'_, ProtocolInfo>,
+) -> Result<bool> {
+    Ok(ProtocolInfo::load_checked(gov_info)?.executors.contains(key)
+        || ProtocolInfo::load_checked(protocol_info)?.executors.contains(key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_outbound_proposal_len() {
+        let proposal = OutboundProposal {
+            protocol_id: vec![1; 32],
+            protocol_address: vec![2; 20],
+            function_selector: vec![0, 4, 1, 2, 3, 4],
+            params: vec![3; 100],
+            caller_program: Some(Pubkey::new_unique()),
+            ..Default::default()
+        };
+        let len = OutboundProposal::len(
+            &proposal.protocol_id,
+            &proposal.protocol_address,
+            &proposal.function_selector,
+            &proposal.params,
+        );
+        let mut data = Vec::new();
+        proposal.try_serialize(&mut data).expect("serialization");
+        assert_eq!(len, data.len());
+    }
+}
@@ -0,0 +1,83 @@
+//! The `owner` module lets the protocol team manage its proposers without gov.
+//!
+//! ## Overview
+//! Gov may assign an owner key to a protocol with the `SetProtocolOwner` operation along with the
+//! number of proposers the owner may bring the protocol up to. The owner adds and removes them
+//! directly on Solana with the `owner_*` instructions, so a new proposer PDA doesn't take a full
+//! cross-chain round trip. Executors, transmitters and the consensus rate stay under the control of
+//! gov.
+//!
+//! Every owner action emits `OwnerActionApplied` carrying the same [GovChange] as the corresponding
+//! gov operation, so both kinds of changes are audited the same way.
+use anchor_lang::prelude::*;
+
+use crate::{error::CustomError, gov, gov::GovChange, ProtocolInfo};
+
+/// The change of the protocol made by its owner
+#[derive(Clone, Copy, Debug)]
+pub enum OwnerAction {
+    AddProposer(Pubkey),
+    RemoveProposer(Pubkey),
+}
+
+/// Applies the owner action to the protocol info within the owner limits and returns the change it
+/// has made.
+pub(crate) fn apply_owner_action(
+    action: OwnerAction,
+    info: &mut ProtocolInfo,
+) -> Result<GovChange> {
+    Ok(match action {
+        OwnerAction::AddProposer(proposer) => {
+            let old = info.proposers();
+            require!(
+                old.len() < info.owner_max_proposers as usize,
+                CustomError::OwnerLimitExceeded
+            );
+            gov::add_proposer(proposer, info)?;
+            GovChange::AddAllowedProposerAddress {
+                old,
+                new: info.proposers(),
+            }
+        }
+        OwnerAction::RemoveProposer(proposer) => {
+            let old = info.proposers();
+            gov::remove_proposer(proposer, info);
+            GovChange::RemoveAllowedProposerAddress {
+                old,
+                new: info.proposers(),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn test_owner_limits() {
+        let mut info = ProtocolInfo::zeroed();
+        info.owner_max_proposers = 2;
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        for proposer in [first, second] {
+            apply_owner_action(OwnerAction::AddProposer(proposer), &mut info).unwrap();
+        }
+        assert!(
+            apply_owner_action(OwnerAction::AddProposer(Pubkey::new_unique()), &mut info).is_err()
+        );
+
+        let change = apply_owner_action(OwnerAction::RemoveProposer(first), &mut info);
+        assert_eq!(
+            change.unwrap(),
+            GovChange::RemoveAllowedProposerAddress {
+                old: vec![first, second],
+                new: vec![second]
+            }
+        );
+        assert!(
+            apply_owner_action(OwnerAction::AddProposer(Pubkey::new_unique()), &mut info).is_ok()
+        );
+    }
+}
@@ -11,6 +11,8 @@
 //!   direct binary code selections and human-readable names.
 //! - `OperationData`: Central structure representing an operation's data throughout its lifecycle,
 //!   including initialization, signing, and execution phases.
+//! - `ProposerIdentity`: The sender of an outbound proposal, passed to the destination side in the `reserved`
+//!   field of the outbound operation.
 //!
 //! ## Overview
 //! This module encapsulates the core data structures that are used across various stages of operation
@@ -84,7 +86,7 @@ pub struct TransmitterSignature {
 ///
 /// Example:
 ///
-#[derive(Clone, AnchorSerialize, AnchorDeserialize, Debug, Default)]
+#[derive(Clone, AnchorSerialize, AnchorDeserialize, Debug, Default, PartialEq, Eq)]
 pub enum FunctionSelector {
     ByCode(Vec<u8>),
     ByName(String),
@@ -93,45 +95,54 @@ pub enum FunctionSelector {
 }
 
 impl FunctionSelector {
+    /// Encodes the selector for proposals, the selector is limited to 32 bytes as on the EVM side
     pub fn to_bytes(&self) -> std::result::Result<Vec<u8>, CustomError> {
+        if self.selector()?.len() > 32 {
+            return Err(CustomError::SelectorTooBig);
+        }
+        self.encode()
+    }
+
+    /// Returns the code or the name bytes of the selector
+    pub fn selector(&self) -> std::result::Result<&[u8], CustomError> {
         match self {
-            FunctionSelector::ByCode(code) => {
-                if code.len() > 32 {
-                    return Err(CustomError::SelectorTooBig);
-                }
-                Ok([&[0_u8, code.len() as u8][..], code].concat())
-            }
-            FunctionSelector::ByName(name) => {
-                if name.as_bytes().len() > 32 {
-                    return Err(CustomError::SelectorTooBig);
-                }
-                Ok([&[1_u8, name.as_bytes().len() as u8][..], name.as_bytes()].concat())
-            }
-            FunctionSelector::Dummy => Ok(vec![2_u8, 0]),
+            FunctionSelector::ByCode(code) => Ok(code),
+            FunctionSelector::ByName(name) => Ok(name.as_bytes()),
+            FunctionSelector::Dummy => Err(CustomError::InvalidFunctionSelector),
         }
     }
+
+    /// Encodes the selector as the type byte followed by the length byte and the selector itself
+    fn encode(&self) -> std::result::Result<Vec<u8>, CustomError> {
+        let selector_type = match self {
+            FunctionSelector::ByCode(_) => 0_u8,
+            FunctionSelector::ByName(_) => 1_u8,
+            FunctionSelector::Dummy => return Err(CustomError::InvalidFunctionSelector),
+        };
+        let selector = self.selector()?;
+        let len = u8::try_from(selector.len()).map_err(|_| CustomError::SelectorTooBig)?;
+        Ok([&[selector_type, len][..], selector].concat())
+    }
 }
 
+/// Decodes the selector encoded by [FunctionSelector::to_bytes], the length byte has to match the
+/// length of the rest of the data and names have to be valid UTF-8.
 impl TryFrom<&[u8]> for FunctionSelector {
-    type Error = Vec<u8>;
-    fn try_from(value: &[u8]) -> std::result::Result<Self, Vec<u8>> {
-        let mut iter = value.iter().copied();
-        Ok(match iter.next().ok_or(vec![])? {
-            0 => {
-                let _len = iter.next().ok_or(vec![])?;
-                let code: Vec<u8> = iter.collect();
-                FunctionSelector::ByCode(code)
-            }
-            1 => {
-                let _len = iter.next().ok_or(vec![])?;
-                let name: Vec<u8> = iter.collect();
-                FunctionSelector::ByName(String::from_utf8(name).map_err(|err| {
-                    log::error!("Failed to get function_selector by name from data: {}", err);
-                    vec![]
-                })?)
-            }
-            _ => panic!("Unexpected function_selector type byte"),
-        })
+    type Error = CustomError;
+    fn try_from(value: &[u8]) -> std::result::Result<Self, CustomError> {
+        let [selector_type, len, selector @ ..] = value else {
+            return Err(CustomError::InvalidFunctionSelector);
+        };
+        if *len as usize != selector.len() {
+            return Err(CustomError::InvalidFunctionSelector);
+        }
+        match selector_type {
+            0 => Ok(FunctionSelector::ByCode(selector.to_vec())),
+            1 => std::str::from_utf8(selector)
+                .map(|name| FunctionSelector::ByName(name.to_owned()))
+                .map_err(|_| CustomError::InvalidFunctionSelector),
+            _ => Err(CustomError::InvalidFunctionSelector),
+        }
     }
 }
 
@@ -153,7 +164,7 @@ pub struct OperationData {
 }
 
 impl OperationData {
-    fn op_data_evm(&self) -> Vec<u8> {
+    fn op_data_evm(&self) -> std::result::Result<Vec<u8>, CustomError> {
         let mut buf = Vec::new();
         buf.extend_from_slice(&self.protocol_id);
         buf.extend_from_slice(&self.meta);
@@ -163,38 +174,65 @@ impl OperationData {
         buf.extend_from_slice(&u64_to_bytes32(self.nonce));
         buf.extend_from_slice(&u128_to_bytes32(self.dest_chain_id));
         buf.extend_from_slice(self.protocol_addr.as_ref());
-        match &self.function_selector {
-            FunctionSelector::ByCode(code) => {
-                buf.extend_from_slice(&[0, code.len() as u8]);
-                buf.extend_from_slice(code.as_slice())
-            }
-            FunctionSelector::ByName(name) => {
-                buf.extend_from_slice(&[1, name.len() as u8]);
-                buf.extend_from_slice(name.as_bytes())
-            }
-            FunctionSelector::Dummy => panic!("function_selector is not initialized"),
-        }
+        buf.extend_from_slice(&self.function_selector.encode()?);
         buf.extend_from_slice(&self.params);
         buf.extend_from_slice(&self.reserved);
-        buf
+        Ok(buf)
     }
 
-    fn op_hash(&self) -> Vec<u8> {
-        let op_data_evm = self.op_data_evm();
-        Keccak256::digest(op_data_evm).to_vec()
+    fn op_hash(&self) -> std::result::Result<Vec<u8>, CustomError> {
+        let op_data_evm = self.op_data_evm()?;
+        Ok(Keccak256::digest(op_data_evm).to_vec())
     }
 
     /// Calculates an operation hash based on its data
-    /// Generates the 32-bytes hash
+    /// Generates the 32-bytes hash, fails if the function selector can't be encoded
     ///
     /// Example:
     ///
     /// ```rust
-    /// let op_hash = op_data.op_hash_with_message();
+    /// let op_hash = op_data.op_hash_with_message()?;
     /// require!(op_hash == op_hash_cached, CustomError::CachedOpHashMismatch);
     ///```
-    pub fn op_hash_with_message(&self) -> Vec<u8> {
-        hash_with_message(&self.op_hash())
+    pub fn op_hash_with_message(&self) -> std::result::Result<Vec<u8>, CustomError> {
+        Ok(hash_with_message(&self.op_hash()?))
+    }
+}
+
+/// The sender of an outbound proposal: the whitelisted proposer and the program that has called
+/// `propose` through CPI if it's known.
+///
+/// It's passed in the `reserved` field of the outbound [OperationData], so it's covered by the op hash
+/// and the destination side can enforce per-sender policies. The encoding is the proposer key followed
+/// by the caller program key if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProposerIdentity {
+    pub proposer: Pubkey,
+    pub caller_program: Option<Pubkey>,
+}
+
+impl ProposerIdentity {
+    pub fn to_reserved(&self) -> Vec<u8> {
+        let mut reserved = self.proposer.to_bytes().to_vec();
+        if let Some(caller_program) = self.caller_program {
+            reserved.extend_from_slice(caller_program.as_ref());
+        }
+        reserved
+    }
+
+    pub fn from_reserved(reserved: &[u8]) -> Option<Self> {
+        let key = |bytes: &[u8]| Pubkey::try_from(bytes).ok();
+        match reserved.len() {
+            32 => Some(Self {
+                proposer: key(reserved)?,
+                caller_program: None,
+            }),
+            64 => Some(Self {
+                proposer: key(&reserved[..32])?,
+                caller_program: Some(key(&reserved[32..])?),
+            }),
+            _ => None,
+        }
     }
 }
 
@@ -250,9 +288,9 @@ pub fn derive_eth_address(public_key: &[u8]) -> EthAddress {
 }
 
 pub(crate) fn ecrecover(op_hash: &[u8], sig: &TransmitterSignature) -> Result<EthAddress> {
+    require!(sig.r.len() == 32 && sig.s.len() == 32, CustomError::InvalidSignature);
     let signature = [&sig.r[..], &sig.s[..]].concat();
     let v = sig.v % 27;
-    require_eq!(signature.len(), 64);
     let pk =
         secp256k1_recover(op_hash, v, &signature).map_err(|_| CustomError::InvalidSignature)?;
     Ok(derive_eth_address(&[&[0x04], &pk.0[..]].concat()))
@@ -285,3 +323,150 @@ pub enum OpStatus {
     Signed,
     Executed,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::util::SplitMix64;
+
+    #[test]
+    fn test_proposer_identity() {
+        let identity = ProposerIdentity {
+            proposer: Pubkey::new_unique(),
+            caller_program: None,
+        };
+        assert_eq!(identity.to_reserved().len(), 32);
+        assert_eq!(ProposerIdentity::from_reserved(&identity.to_reserved()), Some(identity));
+
+        let identity = ProposerIdentity {
+            caller_program: Some(Pubkey::new_unique()),
+            ..identity
+        };
+        assert_eq!(identity.to_reserved().len(), 64);
+        assert_eq!(ProposerIdentity::from_reserved(&identity.to_reserved()), Some(identity));
+        assert_eq!(ProposerIdentity::from_reserved(&[]), None);
+        assert_eq!(ProposerIdentity::from_reserved(&[1; 48]), None);
+    }
+
+    #[test]
+    fn test_function_selector_decoding() {
+        for selector in [
+            FunctionSelector::ByCode(vec![]),
+            FunctionSelector::ByCode(vec![0xaa; 32]),
+            FunctionSelector::ByName("receive_photon_msg".to_owned()),
+        ] {
+            let data = selector.to_bytes().unwrap();
+            assert_eq!(FunctionSelector::try_from(data.as_slice()).unwrap(), selector);
+        }
+        for data in [
+            &[][..],
+            &[0],
+            &[0, 2, 1],
+            &[0, 0, 1],
+            &[1, 2, 0xff, 0xfe],
+            &[2, 0],
+            &[3, 0],
+        ] {
+            assert!(matches!(
+                FunctionSelector::try_from(data),
+                Err(CustomError::InvalidFunctionSelector)
+            ));
+        }
+        assert!(FunctionSelector::Dummy.to_bytes().is_err());
+        assert!(OperationData::default().op_hash_with_message().is_err());
+    }
+
+    fn mutate(rng: &mut SplitMix64, data: &[u8]) -> Vec<u8> {
+        let mut data = data.to_vec();
+        match rng.below(3) {
+            0 => data.truncate(rng.below(data.len() + 1)),
+            1 if !data.is_empty() => {
+                let pos = rng.below(data.len());
+                data[pos] = rng.next() as u8;
+            }
+            _ => {
+                let len = rng.below(16);
+                data.extend(rng.bytes(len))
+            }
+        }
+        data
+    }
+
+    fn random_bytes(rng: &mut SplitMix64, max_len: usize) -> Vec<u8> {
+        let len = rng.below(max_len);
+        rng.bytes(len)
+    }
+
+    fn random_selector(rng: &mut SplitMix64) -> FunctionSelector {
+        let len = rng.below(40);
+        match rng.below(3) {
+            0 => FunctionSelector::ByCode(rng.bytes(len)),
+            1 => FunctionSelector::ByName(
+                (0..len).map(|_| (b'a' + rng.below(26) as u8) as char).collect(),
+            ),
+            _ => FunctionSelector::Dummy,
+        }
+    }
+
+    fn random_op_data(rng: &mut SplitMix64) -> OperationData {
+        OperationData {
+            protocol_id: rng.bytes(32),
+            meta: rng.bytes(32).try_into().unwrap(),
+            src_chain_id: rng.next() as u128,
+            src_block_number: rng.next(),
+            src_op_tx_id: random_bytes(rng, 64),
+            nonce: rng.next(),
+            dest_chain_id: rng.next() as u128,
+            protocol_addr: Pubkey::new_from_array(rng.bytes(32).try_into().unwrap()),
+            function_selector: random_selector(rng),
+            params: random_bytes(rng, 128),
+            reserved: random_bytes(rng, 64),
+        }
+    }
+
+    #[test]
+    fn test_fuzz_operation_parsing() {
+        let mut rng = SplitMix64(0x5eed);
+        for _ in 0..2000 {
+            let selector = random_selector(&mut rng);
+            let encoded = selector.to_bytes();
+            if let Ok(data) = &encoded {
+                assert_eq!(FunctionSelector::try_from(data.as_slice()).unwrap(), selector);
+            }
+            let encoded = encoded.unwrap_or_else(|_| random_bytes(&mut rng, 40));
+            for _ in 0..16 {
+                let data = mutate(&mut rng, &encoded);
+                if let Ok(decoded) = FunctionSelector::try_from(data.as_slice()) {
+                    // Decoding accepts selectors longer than the 32 bytes allowed for proposals
+                    assert_eq!(decoded.encode().unwrap(), data);
+                }
+            }
+
+            let op_data = random_op_data(&mut rng);
+            let op_hash = op_data.op_hash_with_message();
+            assert_eq!(op_hash.is_ok(), op_data.function_selector != FunctionSelector::Dummy);
+            let encoded = op_data.try_to_vec().unwrap();
+            for _ in 0..16 {
+                let data = mutate(&mut rng, &encoded);
+                if let Ok(decoded) = OperationData::try_from_slice(&data) {
+                    let _ = decoded.op_hash_with_message();
+                }
+            }
+
+            let sig = TransmitterSignature {
+                v: rng.next() as u8,
+                r: rng.bytes(32),
+                s: rng.bytes(32),
+            };
+            let encoded = sig.try_to_vec().unwrap();
+            for _ in 0..16 {
+                let data = mutate(&mut rng, &encoded);
+                if let Ok(sig) = TransmitterSignature::try_from_slice(&data) {
+                    let valid_len = sig.r.len() == 32 && sig.s.len() == 32;
+                    let recovered = ecrecover(&rng.bytes(32), &sig);
+                    assert!(valid_len || recovered.is_err());
+                }
+            }
+        }
+    }
+}
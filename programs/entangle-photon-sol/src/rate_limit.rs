@@ -0,0 +1,339 @@
+//! The `rate_limit` module throttles the execution of inbound operations per protocol.
+//!
+//! ## Overview
+//! A protocol may opt into a limit on the number of operations executed within a rolling window of
+//! slots and, additionally, on the cumulative value they carry. The value of an operation is read as a
+//! `uint256` at the declared byte offset of its ABI encoded `params`, e.g. the `amount` of the bridge
+//! `redeem`. The limits are set by the `SetRateLimit` gov operation and are disabled by default.
+//!
+//! ## Rolling window
+//! The window is approximated with two buckets: the counters of the current window and of the preceding
+//! one, which is weighted by the part of it still covered by the rolling window. This keeps the state
+//! fixed-size while not allowing a burst of twice the limit around a window boundary.
+//!
+//! ## Holding
+//! An operation exceeding the limits is not failed: `execute_operation` leaves it `Signed` and emits
+//! `ProposalHeld` with the slot the operation may be retried at. If the next window has room, the held
+//! operation is recorded: its share is reserved in the next window and the slot the window starts at is
+//! stored in the operation info. Once the window rolls over, the reserved shares are accounted ahead of
+//! new operations and the recorded operations are released, so held operations are not starved by the
+//! ones arriving later. An operation whose value alone exceeds the max value, or doesn't fit into
+//! `u128`, is counted at the max value, so it is released in a window of its own.
+//! `RateLimit::held_until` performs the same check off-chain, so executors can wait instead of sending
+//! transactions that will be held.
+use anchor_lang::prelude::*;
+
+use crate::{abi::AbiReader, error::CustomError};
+
+/// Represents the inbound limits of a protocol along with the state of the current window.
+///
+/// # Fields
+///
+/// * `window_slots` - The length of the rolling window in slots, zero disables the limits.
+/// * `max_ops` - The max number of operations executed within the window, zero means unlimited.
+/// * `max_value` - The max cumulative value of operations executed within the window, zero means unlimited.
+/// * `value_offset` - The byte offset of the `uint256` value within the operation params.
+/// * `window_start`, `window_ops`, `window_value` - The slot the current window started at and its counters.
+/// * `prev_window_ops`, `prev_window_value` - The counters of the preceding window.
+/// * `held_ops`, `held_value` - The shares of the held operations reserved in the next window.
+///
+/// Values are stored as little-endian bytes since `u128` alignment differs between targets.
+#[zero_copy]
+#[derive(Debug, Default)]
+pub struct RateLimit {
+    window_slots: u64,
+    max_ops: u64,
+    max_value: [u8; 16],
+    value_offset: u32,
+    _padding: [u8; 4],
+    window_start: u64,
+    window_ops: u64,
+    window_value: [u8; 16],
+    prev_window_ops: u64,
+    prev_window_value: [u8; 16],
+    held_ops: u64,
+    held_value: [u8; 16],
+}
+
+/// The outcome of an operation exceeding the limits.
+///
+/// # Fields
+///
+/// * `until` - The slot the operation may be retried at.
+/// * `recorded` - Whether the operation share is reserved in the window starting at `until`, so the
+///   slot has to be stored in the operation info to release it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hold {
+    pub until: u64,
+    pub recorded: bool,
+}
+
+impl RateLimit {
+    pub fn new(window_slots: u64, max_ops: u64, max_value: u128, value_offset: u32) -> Self {
+        Self {
+            window_slots,
+            max_ops,
+            max_value: max_value.to_le_bytes(),
+            value_offset,
+            ..Default::default()
+        }
+    }
+
+    pub fn window_slots(&self) -> u64 {
+        self.window_slots
+    }
+
+    pub fn max_ops(&self) -> u64 {
+        self.max_ops
+    }
+
+    pub fn max_value(&self) -> u128 {
+        u128::from_le_bytes(self.max_value)
+    }
+
+    pub fn value_offset(&self) -> u32 {
+        self.value_offset
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.window_slots > 0 && (self.max_ops > 0 || self.max_value() > 0)
+    }
+
+    /// Reads the value of the operation, it is zero for protocols not limiting the value. A value
+    /// not fitting into `u128` is saturated, it is counted at the max value anyway.
+    pub fn value(&self, params: &[u8]) -> std::result::Result<u128, CustomError> {
+        if self.max_value() == 0 {
+            return Ok(0);
+        }
+        let data = params.get(self.value_offset as usize..).ok_or(CustomError::InvalidProtoMsg)?;
+        let (high, low) = AbiReader::new(data).bytes32(0)?.split_at(16);
+        if high.iter().any(|x| *x != 0) {
+            return Ok(u128::MAX);
+        }
+        Ok(u128::from_be_bytes(low.try_into().expect("16 bytes slice")))
+    }
+
+    /// Returns the slot the operation can be retried at if executing it at `slot` exceeds the limits.
+    /// `held_until` is the release slot recorded in the operation info, zero if it is not recorded.
+    pub fn held_until(
+        &self,
+        slot: u64,
+        params: &[u8],
+        held_until: u64,
+    ) -> std::result::Result<Option<u64>, CustomError> {
+        Ok(self.admit(slot, params, held_until)?.1.map(|x| x.until))
+    }
+
+    /// Accounts the operation executed at `slot`. If the operation exceeds the limits, it is held
+    /// and its share is reserved in the next window if there is room for it.
+    pub(crate) fn consume(
+        &mut self,
+        slot: u64,
+        params: &[u8],
+        held_until: u64,
+    ) -> std::result::Result<Option<Hold>, CustomError> {
+        let (next, hold) = self.admit(slot, params, held_until)?;
+        *self = next;
+        Ok(hold)
+    }
+
+    fn admit(
+        &self,
+        slot: u64,
+        params: &[u8],
+        held_until: u64,
+    ) -> std::result::Result<(Self, Option<Hold>), CustomError> {
+        if !self.is_enabled() {
+            return Ok((*self, None));
+        }
+        let mut next = *self;
+        next.roll(slot);
+        // The share of a recorded operation is accounted once its window starts
+        if held_until != 0 {
+            if slot >= held_until {
+                return Ok((next, None));
+            }
+            return Ok((
+                *self,
+                Some(Hold {
+                    until: held_until,
+                    recorded: true,
+                }),
+            ));
+        }
+
+        let value = match self.max_value() {
+            0 => 0,
+            max_value => self.value(params)?.min(max_value),
+        };
+        let ops = next.weighted(next.prev_window_ops as u128, slot) + next.window_ops as u128 + 1;
+        let window_value = u128::from_le_bytes(next.window_value).saturating_add(value);
+        let total_value = next
+            .weighted(u128::from_le_bytes(next.prev_window_value), slot)
+            .saturating_add(window_value);
+        if !self.exceeds(ops, total_value) {
+            next.window_ops += 1;
+            next.window_value = window_value.to_le_bytes();
+            return Ok((next, None));
+        }
+
+        let until = next.window_start + next.window_slots;
+        let held_value = u128::from_le_bytes(next.held_value).saturating_add(value);
+        if self.exceeds(next.held_ops as u128 + 1, held_value) {
+            return Ok((
+                *self,
+                Some(Hold {
+                    until,
+                    recorded: false,
+                }),
+            ));
+        }
+        next.held_ops += 1;
+        next.held_value = held_value.to_le_bytes();
+        Ok((
+            next,
+            Some(Hold {
+                until,
+                recorded: true,
+            }),
+        ))
+    }
+
+    fn exceeds(&self, ops: u128, value: u128) -> bool {
+        (self.max_ops > 0 && ops > self.max_ops as u128)
+            || (self.max_value() > 0 && value > self.max_value())
+    }
+
+    /// Moves the window to the one containing `slot`, the held operations are accounted in it
+    fn roll(&mut self, slot: u64) {
+        let elapsed = slot.saturating_sub(self.window_start) / self.window_slots;
+        if elapsed == 0 {
+            return;
+        }
+        if elapsed == 1 {
+            self.prev_window_ops = self.window_ops;
+            self.prev_window_value = self.window_value;
+        } else {
+            self.prev_window_ops = 0;
+            self.prev_window_value = Default::default();
+        }
+        self.window_ops = self.held_ops;
+        self.window_value = self.held_value;
+        self.held_ops = 0;
+        self.held_value = Default::default();
+        self.window_start += elapsed * self.window_slots;
+    }
+
+    /// Returns the part of the preceding window counter still covered by the rolling window
+    fn weighted(&self, prev: u128, slot: u64) -> u128 {
+        let window = self.window_slots as u128;
+        let remaining = window - (slot - self.window_start) as u128;
+        // Split to avoid overflowing on large values
+        prev / window * remaining + prev % window * remaining / window
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethabi::{Token, Uint};
+
+    fn redeem_params(amount: u128) -> Vec<u8> {
+        ethabi::encode(&[
+            Token::Bytes(vec![1; 32]),
+            Token::Uint(Uint::from(amount)),
+            Token::Uint(Uint::from(10)),
+            Token::FixedBytes(vec![2; 32]),
+            Token::Uint(Uint::from(1)),
+            Token::FixedBytes(vec![3; 32]),
+        ])
+    }
+
+    fn hold(until: u64, recorded: bool) -> Option<Hold> {
+        Some(Hold { until, recorded })
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut rate_limit = RateLimit::default();
+        for slot in 0..100 {
+            assert_eq!(rate_limit.consume(slot, &[], 0).unwrap(), None);
+        }
+        let mut rate_limit = RateLimit::new(0, 1, 0, 0);
+        assert_eq!(rate_limit.consume(1, &[], 0).unwrap(), None);
+        assert_eq!(rate_limit.consume(1, &[], 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_max_ops_rolling_window() {
+        let mut rate_limit = RateLimit::new(100, 2, 0, 0);
+        assert_eq!(rate_limit.consume(1000, &[], 0).unwrap(), None);
+        assert_eq!(rate_limit.consume(1050, &[], 0).unwrap(), None);
+        assert_eq!(rate_limit.held_until(1099, &[], 0).unwrap(), Some(1100));
+
+        // Two operations are recorded for the next window, the third one has to retry then
+        assert_eq!(rate_limit.consume(1099, &[], 0).unwrap(), hold(1100, true));
+        assert_eq!(rate_limit.consume(1099, &[], 0).unwrap(), hold(1100, true));
+        assert_eq!(rate_limit.consume(1099, &[], 0).unwrap(), hold(1100, false));
+        assert_eq!(rate_limit.consume(1099, &[], 1100).unwrap(), hold(1100, true));
+        assert_eq!(rate_limit.held_until(1099, &[], 1100).unwrap(), Some(1100));
+
+        // The recorded operations are released ahead of the new ones
+        assert_eq!(rate_limit.consume(1100, &[], 1100).unwrap(), None);
+        assert_eq!(rate_limit.consume(1100, &[], 1100).unwrap(), None);
+        assert_eq!(rate_limit.window_ops, 2);
+        assert_eq!(rate_limit.consume(1100, &[], 0).unwrap(), hold(1200, true));
+        // Half of the preceding window is covered, but the released operations fill the window
+        assert_eq!(rate_limit.held_until(1150, &[], 0).unwrap(), Some(1200));
+
+        // The preceding window is dropped completely after an idle window
+        assert_eq!(rate_limit.consume(1300, &[], 1200).unwrap(), None);
+        assert_eq!(rate_limit.consume(1300, &[], 0).unwrap(), None);
+        assert_eq!(rate_limit.consume(1300, &[], 0).unwrap(), hold(1400, true));
+    }
+
+    #[test]
+    fn test_max_value() {
+        let max_value = 10_u128.pow(24);
+        let mut rate_limit = RateLimit::new(100, 0, max_value, 32);
+        assert_eq!(rate_limit.value(&redeem_params(max_value / 2)).unwrap(), max_value / 2);
+
+        assert_eq!(rate_limit.consume(0, &redeem_params(max_value / 2), 0).unwrap(), None);
+        assert_eq!(rate_limit.consume(1, &redeem_params(max_value / 2), 0).unwrap(), None);
+        let held = rate_limit;
+        assert_eq!(rate_limit.consume(2, &redeem_params(1), 0).unwrap(), hold(100, true));
+        assert_eq!(rate_limit.window_value, held.window_value);
+        assert_eq!(rate_limit.window_ops, held.window_ops);
+        assert_eq!(u128::from_le_bytes(rate_limit.held_value), 1);
+
+        // The recorded value is accounted in the next window, so the max value doesn't fit anymore
+        let params = redeem_params(max_value);
+        assert_eq!(rate_limit.consume(200, &params, 0).unwrap(), hold(300, true));
+        assert_eq!(rate_limit.consume(300, &params, 300).unwrap(), None);
+        assert_eq!(u128::from_le_bytes(rate_limit.window_value), max_value);
+
+        assert!(matches!(rate_limit.consume(400, &[0; 40], 0), Err(CustomError::InvalidProtoMsg)));
+    }
+
+    #[test]
+    fn test_oversized_values() {
+        let max_value = 10_u128.pow(24);
+        let mut rate_limit = RateLimit::new(100, 0, max_value, 32);
+        let oversized = redeem_params(max_value + 1);
+        let mut overflowing = redeem_params(0);
+        overflowing[32] = 1;
+        assert_eq!(rate_limit.value(&overflowing).unwrap(), u128::MAX);
+
+        // A value exceeding the max value takes a window of its own
+        assert_eq!(rate_limit.consume(0, &oversized, 0).unwrap(), None);
+        assert_eq!(u128::from_le_bytes(rate_limit.window_value), max_value);
+        assert_eq!(rate_limit.consume(1, &redeem_params(1), 0).unwrap(), hold(100, true));
+        assert_eq!(rate_limit.consume(2, &overflowing, 0).unwrap(), hold(100, false));
+
+        // The next window is partly reserved, so it is recorded for the one after
+        assert_eq!(rate_limit.consume(100, &overflowing, 0).unwrap(), hold(200, true));
+        assert_eq!(rate_limit.held_until(150, &overflowing, 200).unwrap(), Some(200));
+        assert_eq!(rate_limit.consume(200, &overflowing, 200).unwrap(), None);
+        assert_eq!(u128::from_le_bytes(rate_limit.window_value), max_value);
+    }
+}
@@ -0,0 +1,189 @@
+//! The `timelock` module delays the sensitive gov operations.
+//!
+//! ## Overview
+//! The admin selects the gov operation codes to be timelocked along with the delay and the guardian by
+//! `set_gov_timelock`, the settings are stored in the [GovTimelock] account derived from
+//! `[ROOT, b"GOV_TIMELOCK"]`. Once such an operation is executed, `receive_photon_msg` doesn't apply it
+//! but queues it in the [PendingGovChange] account derived from `[ROOT, b"PENDING_GOV", op_hash]` and
+//! emits `GovChangeQueued`.
+//!
+//! ## Two-phase apply
+//! After the delay a gov executor calls `apply_gov_change`, the operation data is read from the executed
+//! operation account, the change is applied and the pending account is closed. Until then the guardian
+//! may call `veto_gov_change` to drop the change.
+//!
+//! Without the timelock account or with the zero delay every gov operation is applied immediately.
+use anchor_lang::{
+    prelude::*,
+    system_program::{
+        allocate, assign, create_account, transfer, Allocate, Assign, CreateAccount, Transfer,
+    },
+};
+
+use crate::{gov::GovOperation, photon::ROOT, util::Bytes32};
+
+/// The maximum number of timelocked gov operations, there are fewer gov operations than that.
+pub const MAX_TIMELOCKED_OPERATIONS: usize = 32;
+
+/// The timelock settings of the gov operations.
+///
+/// # Fields
+///
+/// * `guardian` - The account allowed to veto pending changes.
+/// * `delay_slots` - The number of slots a pending change is held for, zero disables the timelock.
+/// * `operations` - The codes of the timelocked gov operations.
+///
+#[account]
+#[derive(Debug, Default)]
+pub struct GovTimelock {
+    pub guardian: Pubkey,
+    pub delay_slots: u64,
+    pub operations: Vec<u32>,
+}
+
+impl GovTimelock {
+    pub const LEN: usize = 8 + 32 + 8 + 4 + 4 * MAX_TIMELOCKED_OPERATIONS;
+
+    /// Returns the slot the gov operation may be applied at if it is timelocked
+    pub fn executable_slot(&self, gov_operation: GovOperation, slot: u64) -> Option<u64> {
+        (self.delay_slots > 0 && self.operations.contains(&(gov_operation as u32)))
+            .then(|| slot.saturating_add(self.delay_slots))
+    }
+
+    /// Loads the timelock settings, the account isn't created until the admin sets them
+    pub(crate) fn load(account: &AccountInfo) -> Result<Option<Self>> {
+        if account.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*account.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+        Ok(Some(Self::try_deserialize(&mut &account.try_borrow_data()?[..])?))
+    }
+}
+
+/// A gov operation waiting for the timelock to expire.
+///
+/// # Fields
+///
+/// * `payer` - The executor that paid the rent and receives it back once the change is applied or vetoed.
+/// * `code` - The code of the gov operation.
+/// * `target_protocol_id` - The identifier of the protocol whose settings are changed.
+/// * `executable_slot` - The slot the change may be applied at.
+/// * `op_hash` - The hash of the gov operation.
+///
+#[account]
+#[derive(Debug, Default)]
+pub struct PendingGovChange {
+    pub payer: Pubkey,
+    pub code: u32,
+    pub target_protocol_id: Bytes32,
+    pub executable_slot: u64,
+    pub op_hash: Vec<u8>,
+}
+
+impl PendingGovChange {
+    /// Returns the account size for the change of the operation with the given hash
+    pub fn len(op_hash: &[u8]) -> usize {
+        8 + 32 + 4 + 32 + 8 + 4 + op_hash.len()
+    }
+}
+
+/// Returns the address of the timelock settings account
+pub fn gov_timelock_address() -> Pubkey {
+    Pubkey::find_program_address(&[ROOT, b"GOV_TIMELOCK"], &crate::ID).0
+}
+
+/// Returns the address of the pending change account of the gov operation with the given hash
+pub fn pending_gov_change_address(op_hash: &[u8]) -> Pubkey {
+    Pubkey::find_program_address(&[ROOT, b"PENDING_GOV", op_hash], &crate::ID).0
+}
+
+/// Creates the pending change account and stores the change in it. Anyone may transfer lamports to
+/// the account address in advance, so the account is allocated in place in that case.
+pub(crate) fn queue_gov_change<'info>(
+    change: PendingGovChange,
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    bump: u8,
+) -> Result<()> {
+    let space = PendingGovChange::len(&change.op_hash);
+    let rent = Rent::get()?.minimum_balance(space);
+    let bump = [bump];
+    let seeds = [ROOT, b"PENDING_GOV", &change.op_hash, &bump];
+    let signer = &[&seeds[..]];
+    let program = system_program.to_account_info();
+
+    if account.lamports() == 0 {
+        let accounts = CreateAccount {
+            from: payer.to_account_info(),
+            to: account.clone(),
+        };
+        create_account(
+            CpiContext::new_with_signer(program, accounts, signer),
+            rent,
+            space as u64,
+            &crate::ID,
+        )?;
+    } else {
+        let missing = rent.saturating_sub(account.lamports());
+        if missing > 0 {
+            let accounts = Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            };
+            transfer(CpiContext::new(program.clone(), accounts), missing)?;
+        }
+        let accounts = Allocate {
+            account_to_allocate: account.clone(),
+        };
+        allocate(CpiContext::new_with_signer(program.clone(), accounts, signer), space as u64)?;
+        let accounts = Assign {
+            account_to_assign: account.clone(),
+        };
+        assign(CpiContext::new_with_signer(program, accounts, signer), &crate::ID)?;
+    }
+
+    change.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timelock_len() {
+        let timelock = GovTimelock {
+            operations: vec![GovOperation::RemoveTransmitters as u32; MAX_TIMELOCKED_OPERATIONS],
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        timelock.try_serialize(&mut data).expect("serialization");
+        assert_eq!(GovTimelock::LEN, data.len());
+
+        let change = PendingGovChange {
+            op_hash: vec![1; 32],
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        change.try_serialize(&mut data).expect("serialization");
+        assert_eq!(PendingGovChange::len(&change.op_hash), data.len());
+    }
+
+    #[test]
+    fn test_executable_slot() {
+        let mut timelock = GovTimelock {
+            guardian: Pubkey::new_unique(),
+            delay_slots: 0,
+            operations: vec![GovOperation::SetConsensusTargetRate as u32],
+        };
+        assert_eq!(timelock.executable_slot(GovOperation::SetConsensusTargetRate, 100), None);
+
+        timelock.delay_slots = 50;
+        assert_eq!(timelock.executable_slot(GovOperation::SetConsensusTargetRate, 100), Some(150));
+        assert_eq!(timelock.executable_slot(GovOperation::AddExecutor, 100), None);
+        assert_eq!(
+            timelock.executable_slot(GovOperation::SetConsensusTargetRate, u64::MAX),
+            Some(u64::MAX)
+        );
+    }
+}
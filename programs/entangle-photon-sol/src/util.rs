@@ -20,6 +20,8 @@
 //! ## Internal Utilities
 //! - `sighash`: Generates a signature hash for a given namespace and function name, commonly used for creating a
 //!   consistent identifier for function calls within smart contracts or other programmatic contexts.
+//! - `cpi_caller_program`: Detects the program that has invoked the current instruction through CPI by the
+//!   instructions sysvar.
 //!
 //! ## Usage
 //! The utilities provided by this module are integral to the operation of the Photon messaging layer, ensuring data
@@ -38,6 +40,14 @@
 //! This function is critical for situations where uniform byte arrays are necessary, such as cryptographic operations
 //! or when interfacing with systems that require fixed-length byte arrays.
 
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+        sysvar::instructions::get_instruction_relative,
+    },
+};
+
 /// Aliasing type for 32 raw bytes sequence
 pub type Bytes32 = [u8; 32];
 /// Aliasing type for evm compatible shortened address
@@ -66,6 +76,17 @@ pub(super) fn sighash(namespace: &str, name: &str) -> [u8; 8] {
     sighash
 }
 
+/// Returns the program of the top level instruction if the current instruction is invoked through CPI
+/// by another program. The instructions sysvar holds only the top level instructions, so in case of
+/// nested CPI it's the outermost program rather than the direct caller.
+pub(super) fn cpi_caller_program(instructions: &AccountInfo) -> Result<Option<Pubkey>> {
+    if get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Ok(None);
+    }
+    let instruction = get_instruction_relative(0, instructions)?;
+    Ok((instruction.program_id != crate::ID).then_some(instruction.program_id))
+}
+
 /// Checks whether a result is `Ok` and returns the value if it is.
 /// If the result is an `Err`, it triggers an error check using `require_eq!`
 /// and then panics with an `unreachable!` call.
@@ -98,3 +119,26 @@ macro_rules! require_ok {
         }
     };
 }
+
+/// Deterministic generator so failures are reproducible without extra dependencies.
+#[cfg(test)]
+pub(crate) struct SplitMix64(pub u64);
+
+#[cfg(test)]
+impl SplitMix64 {
+    pub fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
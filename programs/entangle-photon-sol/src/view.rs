@@ -0,0 +1,82 @@
+//! The `view` module serves the read-only instructions.
+//!
+//! ## Overview
+//! `get_op_status`, `get_protocol_info` and `get_next_nonce` don't modify any account, they return the
+//! result through `set_return_data` instead. Off-chain tools read it from `simulateTransaction`, so no
+//! account layout has to be deserialized on their side. Protocols call them by CPI and read the result
+//! with `get_return_data`, e.g. to proceed only once an operation has been executed.
+//!
+//! ## Return data limit
+//! The return data is limited to `MAX_RETURN_DATA` bytes, so [ProtocolInfoView] omits the executors and
+//! the proposers, they don't fit along with the transmitters and are read from the account.
+use anchor_lang::prelude::*;
+
+use crate::{protocol_data::OpStatus, util::EthAddress, OpInfo, ProtocolInfo};
+
+/// The protocol settings returned by `get_protocol_info`.
+///
+/// # Fields
+///
+/// * `is_init` - Indicates whether the protocol is initialized.
+/// * `consensus_target_rate` - The rate of signing operations to be executed.
+/// * `transmitters` - The Ethereum addresses of entities that sign operations for execution.
+/// * `protocol_addresses` - The programs allowed to receive the operations of the protocol.
+/// * `exclusive_window_slots` - The number of slots of an executor turn, see [ProtocolInfo::assigned_executor].
+/// * `executor_reward` - The lamports paid to the executor for every executed operation.
+/// * `owner` - The protocol owner managing proposers without gov.
+///
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolInfoView {
+    pub is_init: bool,
+    pub consensus_target_rate: u64,
+    pub transmitters: Vec<EthAddress>,
+    pub protocol_addresses: Vec<Pubkey>,
+    pub exclusive_window_slots: u32,
+    pub executor_reward: u64,
+    pub owner: Option<Pubkey>,
+}
+
+impl From<&ProtocolInfo> for ProtocolInfoView {
+    fn from(info: &ProtocolInfo) -> Self {
+        Self {
+            is_init: info.is_init != 0,
+            consensus_target_rate: info.consensus_target_rate,
+            transmitters: info.transmitters(),
+            protocol_addresses: info.protocol_addresses(),
+            exclusive_window_slots: info.exclusive_window_slots(),
+            executor_reward: info.executor_reward(),
+            owner: info.owner(),
+        }
+    }
+}
+
+/// Reads the status of the operation, an account that hasn't been created yet means the operation
+/// hasn't been loaded
+pub(crate) fn op_status(op_info: &AccountInfo) -> Result<OpStatus> {
+    if op_info.data_is_empty() {
+        return Ok(OpStatus::None);
+    }
+    require_keys_eq!(*op_info.owner, crate::ID, ErrorCode::AccountOwnedByWrongProgram);
+    OpInfo::status_from_account_data(&op_info.try_borrow_data()?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::photon::{MAX_PROTOCOL_ADDRESSES, MAX_TRANSMITTERS};
+    use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+
+    #[test]
+    fn test_protocol_info_view_fits_return_data() {
+        let view = ProtocolInfoView {
+            is_init: true,
+            consensus_target_rate: u64::MAX,
+            transmitters: vec![[1; 20]; MAX_TRANSMITTERS],
+            protocol_addresses: vec![Pubkey::new_unique(); MAX_PROTOCOL_ADDRESSES],
+            exclusive_window_slots: u32::MAX,
+            executor_reward: u64::MAX,
+            owner: Some(Pubkey::new_unique()),
+        };
+        assert!(view.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+    }
+}
@@ -74,6 +74,10 @@ pub mod onefunc {
             proposer: ctx.accounts.proposer.to_account_info(),
             config: ctx.accounts.config.to_account_info(),
             protocol_info: ctx.accounts.protocol_info.to_account_info(),
+            payer: None,
+            outbox: None,
+            system_program: None,
+            instructions: None,
         };
         let bump = [ctx.bumps.proposer];
         let proposer_seeds = [ROOT, b"PROPOSER", &bump[..]];
@@ -104,6 +108,10 @@ pub mod onefunc {
             proposer: ctx.accounts.proposer.to_account_info(),
             config: ctx.accounts.config.to_account_info(),
             protocol_info: ctx.accounts.protocol_info.to_account_info(),
+            payer: None,
+            outbox: None,
+            system_program: None,
+            instructions: None,
         };
         let bump = [ctx.bumps.proposer];
         let proposer_seeds = [ROOT, b"PROPOSER", &bump[..]];
@@ -169,7 +177,7 @@ pub struct InitOwnedCounter<'info> {
 
     /// Operation info
     #[account()]
-    op_info: Account<'info, OpInfo>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// Account that owns and determines which counter to be incremented
     #[account(signer)]
@@ -201,7 +209,7 @@ pub struct Increment<'info> {
 
     /// Operation info
     #[account()]
-    op_info: Account<'info, OpInfo>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// Counter
     #[account(
@@ -224,7 +232,7 @@ pub struct ToBeFailed<'info> {
 
     /// Operation info
     #[account()]
-    op_info: Account<'info, OpInfo>,
+    op_info: AccountLoader<'info, OpInfo>,
 }
 
 #[derive(Accounts)]
@@ -239,7 +247,7 @@ pub struct IncrementOwnedCounter<'info> {
 
     /// Operation info
     #[account()]
-    op_info: Account<'info, OpInfo>,
+    op_info: AccountLoader<'info, OpInfo>,
 
     /// account that owns and determines which counter to be incremented
     #[account(signer)]
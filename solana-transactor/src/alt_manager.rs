@@ -1,153 +1,482 @@
-use log::debug;
-use std::{collections::HashSet, time::Duration};
+#![allow(clippy::result_large_err)]
+
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::ErrorKind,
+    path::Path,
+    sync::{Arc, Mutex as SyncMutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::Mutex;
 
 use solana_address_lookup_table_program::{
     instruction::{
         close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table,
     },
-    state::AddressLookupTable,
+    state::{AddressLookupTable, LookupTableMeta, LOOKUP_TABLE_MAX_ADDRESSES},
 };
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
-    address_lookup_table_account::AddressLookupTableAccount, commitment_config::CommitmentConfig,
-    instruction::AccountMeta, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program,
+    address_lookup_table_account::AddressLookupTableAccount, clock::Slot,
+    commitment_config::CommitmentConfig, instruction::Instruction, pubkey::Pubkey,
+    signature::Keypair, signer::Signer, slot_hashes::MAX_ENTRIES,
+};
+
+use crate::{
+    config::AltRegistryConfig,
+    ix_compiler::InstructionBundle,
+    send_result::{all_landed, BundleResult, LandedTransaction},
+    SolanaTransactor, TransactorError,
 };
 
-use crate::{ix_compiler::InstructionBundle, SolanaTransactor, TransactorError};
+/// Compute units of the lookup table instructions
+const ALT_COMPUTE_UNITS: u32 = 200000;
+/// Addresses added by one extend instruction, so its transaction fits the packet
+const EXTEND_CHUNK: usize = 20;
+/// Period of polling the slot while waiting for the extended addresses to be activated
+const SLOT_POLL_PERIOD: Duration = Duration::from_millis(400);
+/// Attempts to load the extended table, the read rpcs may lag behind the confirmation
+const LOAD_ATTEMPTS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct TableRecord {
+    key: String,
+    address: Pubkey,
+    /// Addresses of the table, cached from the chain
+    addresses: Vec<Pubkey>,
+    /// Unix timestamp in seconds
+    last_used: u64,
+    /// Set once the table is deactivated, it's closed after the slot leaves the slot hashes
+    deactivation_slot: Option<Slot>,
+}
+
+impl TableRecord {
+    fn is_active_for(&self, key: &str) -> bool {
+        self.key == key && self.deactivation_slot.is_none()
+    }
+}
+
+/// Keeps the lookup tables of the authority by keys of account sets, e.g. per protocol. The tables
+/// are extended by the missing addresses on demand and reused until they're idle, then
+/// deactivated and closed by [AltRegistry::run_cleanup]. The tables are recorded to a file, so
+/// they're reused and closed after a restart.
+pub struct AltRegistry {
+    transactor: SolanaTransactor,
+    authority: Keypair,
+    config: AltRegistryConfig,
+    tables: Mutex<Vec<TableRecord>>,
+    /// Locks serializing the extensions of the tables of a key, the tables aren't locked while
+    /// sending
+    key_locks: SyncMutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl AltRegistry {
+    /// Opens the registry file and refreshes the recorded tables from the chain, the closed ones
+    /// are dropped. The authority pays for the tables and signs their instructions.
+    pub async fn open(
+        transactor: SolanaTransactor,
+        authority: Keypair,
+        config: AltRegistryConfig,
+    ) -> Result<Self, TransactorError> {
+        let records = read_records(Path::new(&config.path))?;
+        let registry = Self {
+            transactor,
+            authority,
+            config,
+            tables: Mutex::default(),
+            key_locks: SyncMutex::default(),
+        };
+        let mut tables = registry.tables.lock().await;
+        for mut record in records {
+            let Some((meta, addresses)) = registry.fetch_table(record.address).await? else {
+                info!("Lookup table {} of {} is closed", record.address, record.key);
+                continue;
+            };
+            record.addresses = addresses;
+            if meta.deactivation_slot != Slot::MAX {
+                record.deactivation_slot = Some(meta.deactivation_slot);
+            }
+            tables.push(record);
+        }
+        registry.save(&tables)?;
+        drop(tables);
+        Ok(registry)
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        self.authority.pubkey()
+    }
+
+    /// Returns the active tables of the key extended by the missing addresses. The extended
+    /// addresses can be looked up once this returns.
+    pub async fn tables(
+        &self,
+        key: &str,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>, TransactorError> {
+        let key_lock = self.key_lock(key);
+        let _key_guard = key_lock.lock().await;
+        let missing = {
+            let mut tables = self.tables.lock().await;
+            // Marked as used first, so the cleanup doesn't deactivate the tables being extended
+            let now = now();
+            for record in tables.iter_mut().filter(|record| record.is_active_for(key)) {
+                record.last_used = now;
+            }
+            missing_addresses(&tables, key, addresses)
+        };
+        if !missing.is_empty() {
+            debug!("Extending lookup tables of {} by {} addresses", key, missing.len());
+            self.extend(key, missing).await?;
+        }
+        let mut tables = self.tables.lock().await;
+        let now = now();
+        let mut accounts = Vec::new();
+        for record in tables.iter_mut().filter(|record| record.is_active_for(key)) {
+            record.last_used = now;
+            accounts.push(AddressLookupTableAccount {
+                key: record.address,
+                addresses: record.addresses.clone(),
+            });
+        }
+        self.save(&tables)?;
+        Ok(accounts)
+    }
+
+    fn key_lock(&self, key: &str) -> Arc<Mutex<()>> {
+        let mut key_locks = self.key_locks.lock().expect("Lock poisoned");
+        key_locks.entry(key.to_owned()).or_default().clone()
+    }
+
+    /// Extends the tables of the key without locking them while sending. If an extension fails,
+    /// the tables sent to are re-read, so the cache holds the addresses that actually landed.
+    async fn extend(&self, key: &str, missing: Vec<Pubkey>) -> Result<(), TransactorError> {
+        let plan = plan_extension(&self.tables.lock().await, key, missing);
+        let mut extended = Vec::new();
+        if let Err(e) = self.send_extension(key, plan, &mut extended).await {
+            for (address, _) in &extended {
+                self.refresh(*address).await?;
+            }
+            return Err(e);
+        }
+        let mut last_extended_slot = 0;
+        for (address, addresses) in extended {
+            let (meta, table) = self.load_extended(address, &addresses).await?;
+            last_extended_slot = last_extended_slot.max(meta.last_extended_slot);
+            self.update(address, table).await?;
+        }
+        // The addresses can be looked up after the slot they're added in
+        while self.get_slot().await <= last_extended_slot {
+            tokio::time::sleep(SLOT_POLL_PERIOD).await;
+        }
+        Ok(())
+    }
+
+    /// Sends the planned extensions, the tables are pushed to `extended` before they're sent to
+    async fn send_extension(
+        &self,
+        key: &str,
+        plan: Vec<(Option<Pubkey>, Vec<Pubkey>)>,
+        extended: &mut Vec<(Pubkey, Vec<Pubkey>)>,
+    ) -> Result<(), TransactorError> {
+        let authority = self.authority.pubkey();
+        for (address, addresses) in plan {
+            let address = match address {
+                Some(address) => address,
+                None => {
+                    let address = self.create().await?;
+                    info!("Lookup table {} of {} created", address, key);
+                    let mut tables = self.tables.lock().await;
+                    tables.push(TableRecord {
+                        key: key.to_owned(),
+                        address,
+                        addresses: Vec::new(),
+                        last_used: now(),
+                        deactivation_slot: None,
+                    });
+                    self.save(&tables)?;
+                    address
+                }
+            };
+            extended.push((address, addresses.clone()));
+            for chunk in addresses.chunks(EXTEND_CHUNK) {
+                let ix = extend_lookup_table(address, authority, Some(authority), chunk.to_vec());
+                self.send(ix).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Re-reads the addresses of the table from the chain
+    async fn refresh(&self, address: Pubkey) -> Result<(), TransactorError> {
+        if let Some((_, addresses)) = self.fetch_table(address).await? {
+            self.update(address, addresses).await?;
+        }
+        Ok(())
+    }
+
+    /// Records the addresses of the table
+    async fn update(&self, address: Pubkey, addresses: Vec<Pubkey>) -> Result<(), TransactorError> {
+        let mut tables = self.tables.lock().await;
+        if let Some(record) = tables.iter_mut().find(|record| record.address == address) {
+            record.addresses = addresses;
+        }
+        self.save(&tables)
+    }
+
+    async fn create(&self) -> Result<Pubkey, TransactorError> {
+        let slot = self.get_slot().await;
+        let authority = self.authority.pubkey();
+        let (ix, address) = create_lookup_table(authority, authority, slot);
+        self.send(ix).await?;
+        Ok(address)
+    }
+
+    /// Loads the table until it contains the extended addresses
+    async fn load_extended(
+        &self,
+        address: Pubkey,
+        extended: &[Pubkey],
+    ) -> Result<(LookupTableMeta, Vec<Pubkey>), TransactorError> {
+        for _ in 0..LOAD_ATTEMPTS {
+            if let Some((meta, addresses)) = self.fetch_table(address).await? {
+                if extended.iter().all(|address| addresses.contains(address)) {
+                    return Ok((meta, addresses));
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        Err(TransactorError::AltNotExtended(address))
+    }
+
+    /// Deactivates the tables idle for the configured period and closes the deactivated ones once
+    /// they can't be used anymore. Runs until the task is aborted.
+    pub async fn run_cleanup(&self) {
+        let period = Duration::from_secs(self.config.cleanup_period_secs);
+        loop {
+            if let Err(e) = self.cleanup().await {
+                warn!("Failed to clean up lookup tables: {}", e);
+            }
+            tokio::time::sleep(period).await;
+        }
+    }
+
+    /// The tables are only locked while the candidates are picked and the results are recorded,
+    /// so the sends don't block the users of the registry
+    async fn cleanup(&self) -> Result<(), TransactorError> {
+        let idle_since = now().saturating_sub(self.config.idle_secs);
+        let slot = self.get_slot().await;
+        let authority = self.authority.pubkey();
+        let (to_deactivate, to_close) = {
+            let mut tables = self.tables.lock().await;
+            let mut to_deactivate = Vec::new();
+            let mut to_close = Vec::new();
+            for record in tables.iter_mut() {
+                match record.deactivation_slot {
+                    None if record.last_used < idle_since => {
+                        // Withdrawn from use until the deactivation result is known
+                        record.deactivation_slot = Some(slot);
+                        to_deactivate.push((record.address, record.key.clone()));
+                    }
+                    Some(deactivation_slot) if slot > deactivation_slot + MAX_ENTRIES as Slot => {
+                        to_close.push((record.address, record.key.clone()));
+                    }
+                    _ => {}
+                }
+            }
+            (to_deactivate, to_close)
+        };
 
+        let mut deactivated = HashMap::new();
+        for (address, key) in to_deactivate {
+            let ix = deactivate_lookup_table(address, authority);
+            let deactivation_slot = match self.send(ix).await {
+                Ok(landed) => {
+                    info!("Lookup table {} of {} deactivated", address, key);
+                    Some(landed.slot.unwrap_or(slot))
+                }
+                Err(e) => {
+                    warn!("Failed to deactivate lookup table {}: {}", address, e);
+                    None
+                }
+            };
+            deactivated.insert(address, deactivation_slot);
+        }
+        let mut closed = HashSet::new();
+        for (address, key) in to_close {
+            let ix = close_lookup_table(address, authority, authority);
+            match self.send(ix).await {
+                Ok(_) => {
+                    info!("Lookup table {} of {} closed", address, key);
+                    closed.insert(address);
+                }
+                Err(e) => warn!("Failed to close lookup table {}: {}", address, e),
+            }
+        }
+
+        let mut tables = self.tables.lock().await;
+        for record in tables.iter_mut() {
+            if let Some(deactivation_slot) = deactivated.get(&record.address) {
+                record.deactivation_slot = *deactivation_slot;
+            }
+        }
+        tables.retain(|record| !closed.contains(&record.address));
+        self.save(&tables)
+    }
+
+    async fn send(&self, ix: Instruction) -> Result<LandedTransaction, TransactorError> {
+        let results = self
+            .transactor
+            .send_all_instructions::<&str>(
+                None,
+                &[InstructionBundle::new(ix, ALT_COMPUTE_UNITS)],
+                &[&self.authority],
+                self.authority.pubkey(),
+                1,
+                &[],
+                None,
+                false,
+            )
+            .await?;
+        all_landed(results)?.pop().ok_or(TransactorError::EmptyBundle)
+    }
+
+    async fn fetch_table(
+        &self,
+        address: Pubkey,
+    ) -> Result<Option<(LookupTableMeta, Vec<Pubkey>)>, TransactorError> {
+        let account = self
+            .transactor
+            .rpc_pool()
+            .with_read_rpc_loop(
+                |rpc| async move {
+                    rpc.get_account_with_commitment(&address, CommitmentConfig::confirmed()).await
+                },
+                CommitmentConfig::confirmed(),
+            )
+            .await;
+        let Some(account) = account.value else {
+            return Ok(None);
+        };
+        let table = AddressLookupTable::deserialize(&account.data)?;
+        Ok(Some((table.meta, table.addresses.to_vec())))
+    }
+
+    async fn get_slot(&self) -> Slot {
+        self.transactor
+            .rpc_pool()
+            .with_read_rpc_loop(
+                |rpc| async move { rpc.get_slot_with_commitment(CommitmentConfig::confirmed()).await },
+                CommitmentConfig::confirmed(),
+            )
+            .await
+    }
+
+    fn save(&self, tables: &[TableRecord]) -> Result<(), TransactorError> {
+        write_records(Path::new(&self.config.path), tables)
+    }
+}
+
+/// Sends the instructions paid and signed by the authority of the registry with the lookup tables
+/// of the key, they're extended by the addresses missing in the given tables beforehand
 pub async fn send_with_alt(
-    transactor: &SolanaTransactor,
+    registry: &AltRegistry,
+    key: &str,
     instructions: &[InstructionBundle],
-    signer: &Keypair,
     additional_signers: &[&Keypair],
     parallel_limit: usize,
     alt: &[AddressLookupTableAccount],
     compute_unit_price: Option<u64>,
-) {
-    let alt_addresses: Vec<_> = alt.iter().flat_map(|x| x.addresses.clone().into_iter()).collect();
-    let total_addresses: Vec<_> = instructions
-        .iter()
-        .flat_map(|x| {
-            x.instruction
-                .accounts
-                .clone()
-                .into_iter()
-                .map(|x| x.pubkey)
-                .chain(std::iter::once(x.instruction.program_id))
-        })
+) -> Result<Vec<BundleResult>, TransactorError> {
+    let addresses: Vec<_> = lookup_addresses(instructions)
+        .into_iter()
+        .filter(|address| !alt.iter().any(|table| table.addresses.contains(address)))
         .collect();
-    let to_add: HashSet<_> =
-        total_addresses.iter().cloned().filter(|x| !alt_addresses.contains(x)).collect();
-    debug!("Total addresses: {}", total_addresses.len());
-    debug!("To add: {}", to_add.len());
-    let slot = transactor
-        .rpc_pool()
-        .with_read_rpc(|rpc| async move { rpc.get_slot().await }, CommitmentConfig::confirmed())
-        .await
-        .expect("Failed to get slot");
-    let (ix, alt_address) = create_lookup_table(signer.pubkey(), signer.pubkey(), slot);
-    debug!("New ALT address {}", alt_address);
-    let ix = InstructionBundle::new(ix, 200000);
-    transactor
-        .send_all_instructions::<&str>(
-            None,
-            &[ix],
-            &[signer],
-            signer.pubkey(),
-            parallel_limit,
-            &[],
-            compute_unit_price,
-            true,
-        )
-        .await
-        .expect("Failed to send create ALT instruction");
-    debug!("ALT created");
-    let to_add: Vec<_> = to_add.into_iter().collect();
-    for chunk in to_add.chunks(20) {
-        let mut ix = extend_lookup_table(
-            alt_address,
-            signer.pubkey(),
-            Some(signer.pubkey()),
-            chunk.to_vec(),
-        );
-        ix.accounts.push(AccountMeta {
-            pubkey: system_program::ID,
-            is_signer: false,
-            is_writable: false,
-        });
-        let ix = InstructionBundle::new(ix, 200000);
-        transactor
-            .send_all_instructions::<&str>(
-                None,
-                &[ix],
-                &[signer],
-                signer.pubkey(),
-                parallel_limit,
-                &[],
-                compute_unit_price,
-                true,
-            )
-            .await
-            .expect("Failed to send create ALT instruction");
-        debug!("ALT extended by {}", chunk.len());
-    }
-    tokio::time::sleep(Duration::from_secs(20)).await;
-    let new_alt = transactor
-        .rpc_pool()
-        .with_read_rpc(
-            |rpc| async move { load_alt(rpc, alt_address).await },
-            CommitmentConfig::confirmed(),
-        )
-        .await
-        .expect("Failed to load new ALT");
-    if let Err(e) = transactor
+    let tables = registry.tables(key, &addresses).await?;
+    registry
+        .transactor
         .send_all_instructions::<&str>(
             None,
             instructions,
-            &[&[signer], additional_signers].concat(),
-            signer.pubkey(),
+            &[&[&registry.authority], additional_signers].concat(),
+            registry.authority.pubkey(),
             parallel_limit,
-            &[&[new_alt], alt].concat(),
+            &[tables, alt.to_vec()].concat(),
             compute_unit_price,
             true,
         )
         .await
-    {
-        log::error!("Failed to send tx: {}", e);
+}
+
+/// Returns the accounts of the instructions that can be looked up, the signers and the programs
+/// have to be the static keys of a message
+pub fn lookup_addresses(instructions: &[InstructionBundle]) -> Vec<Pubkey> {
+    let mut static_keys = HashSet::new();
+    for ix in instructions {
+        static_keys.insert(ix.instruction.program_id);
+        static_keys
+            .extend(ix.instruction.accounts.iter().filter(|a| a.is_signer).map(|a| a.pubkey));
     }
-    debug!("Deactivating ALT");
-    let ix = deactivate_lookup_table(alt_address, signer.pubkey());
-    let ix = InstructionBundle::new(ix, 200000);
-    transactor
-        .send_all_instructions::<&str>(
-            None,
-            &[ix],
-            &[signer],
-            signer.pubkey(),
-            parallel_limit,
-            &[],
-            compute_unit_price,
-            true,
-        )
-        .await
-        .expect("Failed to deactivate ALT");
-    tokio::time::sleep(Duration::from_secs(240)).await;
-    debug!("Clearing ALT");
-    let ix = close_lookup_table(alt_address, signer.pubkey(), signer.pubkey());
-    let ix = InstructionBundle::new(ix, 200000);
-    transactor
-        .send_all_instructions::<&str>(
-            None,
-            &[ix],
-            &[signer],
-            signer.pubkey(),
-            parallel_limit,
-            &[],
-            compute_unit_price,
-            true,
-        )
-        .await
-        .expect("Failed to close ALT");
+    let mut seen = HashSet::new();
+    instructions
+        .iter()
+        .flat_map(|ix| ix.instruction.accounts.iter().map(|account| account.pubkey))
+        .filter(|address| !static_keys.contains(address) && seen.insert(*address))
+        .collect()
+}
+
+/// Returns the addresses missing in the active tables of the key in the given order
+fn missing_addresses(tables: &[TableRecord], key: &str, addresses: &[Pubkey]) -> Vec<Pubkey> {
+    let mut present: HashSet<_> = tables
+        .iter()
+        .filter(|record| record.is_active_for(key))
+        .flat_map(|record| record.addresses.iter().copied())
+        .collect();
+    addresses.iter().copied().filter(|address| present.insert(*address)).collect()
+}
+
+/// Splits the addresses between the active tables of the key with free space left and the new
+/// tables, `None` stands for a new table
+fn plan_extension(
+    tables: &[TableRecord],
+    key: &str,
+    addresses: Vec<Pubkey>,
+) -> Vec<(Option<Pubkey>, Vec<Pubkey>)> {
+    let mut addresses = addresses.into_iter();
+    let mut plan = Vec::new();
+    for record in tables.iter().filter(|record| record.is_active_for(key)) {
+        let free = LOOKUP_TABLE_MAX_ADDRESSES.saturating_sub(record.addresses.len());
+        let chunk: Vec<_> = addresses.by_ref().take(free).collect();
+        if !chunk.is_empty() {
+            plan.push((Some(record.address), chunk));
+        }
+    }
+    let rest: Vec<_> = addresses.collect();
+    plan.extend(rest.chunks(LOOKUP_TABLE_MAX_ADDRESSES).map(|chunk| (None, chunk.to_vec())));
+    plan
+}
+
+fn read_records(path: &Path) -> Result<Vec<TableRecord>, TransactorError> {
+    match fs::read(path) {
+        Ok(data) => bincode::deserialize(&data).map_err(TransactorError::AltRegistryEncoding),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(TransactorError::AltRegistryIo(e)),
+    }
+}
+
+/// Replaces the registry file at once, so it's never left partially written
+fn write_records(path: &Path, tables: &[TableRecord]) -> Result<(), TransactorError> {
+    let data = bincode::serialize(tables).map_err(TransactorError::AltRegistryEncoding)?;
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, data).map_err(TransactorError::AltRegistryIo)?;
+    fs::rename(&tmp_path, path).map_err(TransactorError::AltRegistryIo)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_secs()
 }
 
 pub async fn load_alt(
@@ -161,3 +490,76 @@ pub async fn load_alt(
         addresses: address_lookup_table.addresses.to_vec(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
+
+    fn record(key: &str, addresses: usize, deactivation_slot: Option<Slot>) -> TableRecord {
+        TableRecord {
+            key: key.to_owned(),
+            address: Pubkey::new_unique(),
+            addresses: (0..addresses).map(|_| Pubkey::new_unique()).collect(),
+            last_used: 0,
+            deactivation_slot,
+        }
+    }
+
+    #[test]
+    fn test_lookup_addresses() {
+        let signer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let ix = |accounts| {
+            InstructionBundle::new(Instruction::new_with_bytes(program_id, &[], accounts), 0)
+        };
+        let instructions = [
+            ix(vec![
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(program_id, false),
+            ]),
+            ix(vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new(account, false),
+            ]),
+            ix(vec![AccountMeta::new_readonly(signer, false)]),
+        ];
+        assert_eq!(lookup_addresses(&instructions), vec![account]);
+    }
+
+    #[test]
+    fn test_plan_extension() {
+        let tables = vec![
+            record("a", LOOKUP_TABLE_MAX_ADDRESSES - 2, None),
+            record("a", 10, Some(1)),
+            record("b", 0, None),
+        ];
+        let present = tables[0].addresses[0];
+        let retired = tables[1].addresses[0];
+        let new: Vec<_> =
+            (0..LOOKUP_TABLE_MAX_ADDRESSES + 3).map(|_| Pubkey::new_unique()).collect();
+        let addresses = [&[present, retired][..], &new].concat();
+
+        let missing = missing_addresses(&tables, "a", &addresses);
+        assert_eq!(missing, [&[retired][..], &new].concat());
+        let plan = plan_extension(&tables, "a", missing);
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[0], (Some(tables[0].address), vec![retired, new[0]]));
+        assert_eq!(plan[1], (None, new[1..LOOKUP_TABLE_MAX_ADDRESSES + 1].to_vec()));
+        assert_eq!(plan[2], (None, new[LOOKUP_TABLE_MAX_ADDRESSES + 1..].to_vec()));
+
+        assert!(plan_extension(&tables, "b", Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_records() {
+        let path = std::env::temp_dir().join(format!("alt-{}.registry", Pubkey::new_unique()));
+        assert!(read_records(&path).unwrap().is_empty());
+        let tables = vec![record("a", 3, None), record("b", 0, Some(10))];
+        write_records(&path, &tables).unwrap();
+        assert_eq!(read_records(&path).unwrap(), tables);
+        fs::remove_file(&path).unwrap();
+    }
+}
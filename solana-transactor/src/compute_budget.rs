@@ -0,0 +1,87 @@
+use solana_sdk::{compute_budget, instruction::CompiledInstruction, message::VersionedMessage};
+
+/// `ComputeBudgetInstruction::SetComputeUnitLimit` discriminant
+pub(crate) const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+/// `ComputeBudgetInstruction::SetComputeUnitPrice` discriminant
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Returns the price set by the compute budget instruction of the message if any
+pub fn get_compute_unit_price(message: &VersionedMessage) -> Option<u64> {
+    let keys = message.static_account_keys();
+    message.instructions().iter().find_map(|ix| {
+        let is_budget = keys.get(ix.program_id_index as usize) == Some(&compute_budget::ID);
+        match ix.data.split_first() {
+            Some((&SET_COMPUTE_UNIT_PRICE, price)) if is_budget => {
+                Some(u64::from_le_bytes(price.try_into().ok()?))
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Updates the price of the compute budget instruction in place, so the message keeps its accounts.
+/// Returns false if the message has no such instruction.
+pub fn set_compute_unit_price(message: &mut VersionedMessage, price: u64) -> bool {
+    set_budget_value(message, SET_COMPUTE_UNIT_PRICE, &price.to_le_bytes())
+}
+
+/// Updates the limit of the compute budget instruction in place, so the message keeps its accounts.
+/// Returns false if the message has no such instruction.
+pub fn set_compute_unit_limit(message: &mut VersionedMessage, units: u32) -> bool {
+    set_budget_value(message, SET_COMPUTE_UNIT_LIMIT, &units.to_le_bytes())
+}
+
+/// Overwrites the value of the compute budget instruction with the given discriminant
+fn set_budget_value(message: &mut VersionedMessage, discriminant: u8, value: &[u8]) -> bool {
+    let budget_index = message.static_account_keys().iter().position(|k| k == &compute_budget::ID);
+    let instructions: &mut Vec<CompiledInstruction> = match message {
+        VersionedMessage::Legacy(message) => &mut message.instructions,
+        VersionedMessage::V0(message) => &mut message.instructions,
+    };
+    let Some(ix) = instructions.iter_mut().find(|ix| {
+        Some(ix.program_id_index as usize) == budget_index
+            && ix.data.len() == 1 + value.len()
+            && ix.data[0] == discriminant
+    }) else {
+        return false;
+    };
+    ix.data[1..].copy_from_slice(value);
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+
+    use super::*;
+    use crate::test_support::message;
+
+    #[test]
+    fn test_compute_budget() {
+        let payer = Pubkey::new_unique();
+        let budget_ixs = [
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            ComputeBudgetInstruction::set_compute_unit_price(1000),
+        ];
+        let mut message = message(&payer, &budget_ixs);
+        assert_eq!(get_compute_unit_price(&message), Some(1000));
+        assert!(set_compute_unit_price(&mut message, 5000));
+        assert_eq!(get_compute_unit_price(&message), Some(5000));
+        assert!(set_compute_unit_limit(&mut message, 300_000));
+        assert_eq!(message.instructions()[0].data[1..], 300_000_u32.to_le_bytes());
+
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[SET_COMPUTE_UNIT_LIMIT, 0, 0, 0, 0],
+            vec![AccountMeta::new(payer, true)],
+        );
+        let mut message = self::message(&payer, &[ix]);
+        assert_eq!(get_compute_unit_price(&message), None);
+        assert!(!set_compute_unit_price(&mut message, 5000));
+        assert!(!set_compute_unit_limit(&mut message, 300_000));
+    }
+}
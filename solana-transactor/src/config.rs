@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RpcEntry {
@@ -6,6 +7,12 @@ pub struct RpcEntry {
     pub url: String,
     /// Rate limit in requests per second
     pub ratelimit: u64,
+    /// Requests sent at once before the rate limit applies, 1 if omitted
+    #[serde(default)]
+    pub burst: Option<u64>,
+    /// Websocket address, signatures are subscribed to instead of polled if set
+    #[serde(default)]
+    pub ws_url: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,3 +20,147 @@ pub struct SolanaTransactorConfig {
     pub read_pool: Vec<RpcEntry>,
     pub write_pool: Vec<RpcEntry>,
 }
+
+/// Compute unit prices are in micro-lamports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PriorityFeeConfig {
+    /// Percentile of the recent prioritization fees taken as the price
+    pub percentile: u8,
+    /// Minimal compute unit price
+    pub floor: u64,
+    /// Maximal compute unit price
+    pub cap: u64,
+    /// Price increase in percent on every re-send of a transaction
+    pub escalation_percent: u64,
+}
+
+impl Default for PriorityFeeConfig {
+    fn default() -> Self {
+        Self {
+            percentile: 75,
+            floor: 1000,
+            cap: 1_000_000,
+            escalation_percent: 25,
+        }
+    }
+}
+
+/// Compute unit limits are sized by the units consumed in the simulation plus the margin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    /// Margin in percent of the consumed units
+    pub margin_percent: u8,
+    /// Minimal margin in compute units
+    pub min_margin: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            margin_percent: 10,
+            min_margin: 5000,
+        }
+    }
+}
+
+/// Optional features of the transactor, disabled by default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactorOptions {
+    /// Estimates compute unit prices by the recent prioritization fees
+    pub priority_fee: Option<PriorityFeeConfig>,
+    /// Sizes compute unit limits by simulating the messages
+    pub simulation: Option<SimulationConfig>,
+    /// Sends the messages with durable nonces
+    pub nonce: Option<NonceConfig>,
+    /// Records the sent bundles to a file
+    pub journal: Option<JournalConfig>,
+    /// Packs the instructions writing the same accounts apart from the others, so the messages
+    /// of the independent ones are sent in parallel and the dependent ones in order
+    #[serde(default)]
+    pub group_by_locks: bool,
+}
+
+/// Nonce accounts are derived from the authority by seeds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceConfig {
+    /// Nonce authority, it pays for the nonce accounts and signs the messages using them
+    pub authority: Pubkey,
+    /// Number of nonce accounts, it limits the number of messages awaiting confirmation
+    pub accounts: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+    /// Journal file path
+    pub path: String,
+    /// Seconds the history of a finished bundle is kept for
+    pub retention_secs: u64,
+    /// Bytes appended to the file before the finished bundles are evicted from it
+    pub compaction_bytes: u64,
+    /// Seconds between the evictions of the finished bundles
+    pub compaction_secs: u64,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            path: "transactor.journal".to_owned(),
+            retention_secs: 24 * 60 * 60,
+            compaction_bytes: 64 * 1024 * 1024,
+            compaction_secs: 60 * 60,
+        }
+    }
+}
+
+/// Endpoints are ejected by a circuit breaker and the ones lagging behind are avoided
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RpcHealthConfig {
+    /// Consecutive failures ejecting an endpoint
+    pub failure_threshold: u32,
+    /// Seconds an ejected endpoint is probed after, doubled on every failed probe
+    pub open_secs: u64,
+    /// Maximal seconds an ejected endpoint is probed after
+    pub max_open_secs: u64,
+    /// Slots an endpoint may lag behind the most up-to-date one
+    pub max_slot_lag: u64,
+    /// Seconds between the slot probes of the endpoints
+    pub probe_period_secs: u64,
+}
+
+impl Default for RpcHealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            open_secs: 5,
+            max_open_secs: 60,
+            max_slot_lag: 50,
+            probe_period_secs: 10,
+        }
+    }
+}
+
+/// Lookup tables are kept per key of the account set, extended on demand and closed once idle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AltRegistryConfig {
+    /// Registry file path
+    pub path: String,
+    /// Seconds a table is kept unused before it's deactivated
+    pub idle_secs: u64,
+    /// Seconds between the checks of the idle and the deactivated tables
+    pub cleanup_period_secs: u64,
+}
+
+impl Default for AltRegistryConfig {
+    fn default() -> Self {
+        Self {
+            path: "alt.registry".to_owned(),
+            idle_secs: 24 * 60 * 60,
+            cleanup_period_secs: 60,
+        }
+    }
+}
@@ -8,6 +8,8 @@ pub enum TransactorError {
     NoWriteRpcs,
     #[error("Invalid Solana RPC provided {0}")]
     InvalidRpc(String),
+    #[error("Failed to build http client {0}")]
+    HttpClient(reqwest::Error),
     #[error("Transaction bundle is empty")]
     EmptyBundle,
     #[error("Client error {0}")]
@@ -20,4 +22,28 @@ pub enum TransactorError {
     FailedToCompile(#[from] solana_sdk::message::CompileError),
     #[error("Instruction too big")]
     InstructionTooBig,
+    #[error("Simulation failed {0}")]
+    SimulationFailed(solana_sdk::transaction::TransactionError),
+    #[error("Transaction failed {0}")]
+    TransactionFailed(crate::send_result::TransactionFailure),
+    #[error("Invalid nonce account {0}")]
+    InvalidNonceAccount(solana_sdk::pubkey::Pubkey),
+    #[error("Nonce advanced before the transaction {0} landed")]
+    NonceAdvanced(solana_sdk::signature::Signature),
+    #[error("Transaction {0} expired before it landed")]
+    Expired(solana_sdk::signature::Signature),
+    #[error("Message doesn't use a durable nonce")]
+    NotDurable,
+    #[error("Nonce authority {0} is not the signer")]
+    NonceAuthority(solana_sdk::pubkey::Pubkey),
+    #[error("Lookup table {0} is missing the extended addresses")]
+    AltNotExtended(solana_sdk::pubkey::Pubkey),
+    #[error("Lookup table registry io error {0}")]
+    AltRegistryIo(std::io::Error),
+    #[error("Lookup table registry encoding error {0}")]
+    AltRegistryEncoding(bincode::Error),
+    #[error("Journal io error {0}")]
+    JournalIo(#[from] std::io::Error),
+    #[error("Journal encoding error {0}")]
+    JournalEncoding(#[from] bincode::Error),
 }
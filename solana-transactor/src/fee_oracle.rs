@@ -0,0 +1,135 @@
+use futures::future::BoxFuture;
+use solana_client::client_error::ClientError;
+use solana_sdk::{commitment_config::CommitmentConfig, message::VersionedMessage, pubkey::Pubkey};
+
+use crate::{config::PriorityFeeConfig, rpc_pool::RpcPool};
+
+/// The number of accounts `getRecentPrioritizationFees` accepts at most
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Provides the recent prioritization fees paid for locking the given accounts
+pub trait PriorityFeeSource: Send + Sync {
+    fn recent_prioritization_fees<'a>(
+        &'a self,
+        accounts: &'a [Pubkey],
+    ) -> BoxFuture<'a, Result<Vec<u64>, ClientError>>;
+}
+
+impl PriorityFeeSource for RpcPool {
+    fn recent_prioritization_fees<'a>(
+        &'a self,
+        accounts: &'a [Pubkey],
+    ) -> BoxFuture<'a, Result<Vec<u64>, ClientError>> {
+        Box::pin(self.with_read_rpc(
+            move |rpc| async move {
+                let fees = rpc.get_recent_prioritization_fees(accounts).await?;
+                Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+            },
+            CommitmentConfig::confirmed(),
+        ))
+    }
+}
+
+/// Estimates the compute unit price from the fees recently paid for the writable accounts of a
+/// message and raises it on every re-send of the message
+pub struct PriorityFeeOracle<S = RpcPool> {
+    source: S,
+    config: PriorityFeeConfig,
+}
+
+impl<S: PriorityFeeSource> PriorityFeeOracle<S> {
+    pub fn new(source: S, config: PriorityFeeConfig) -> Self {
+        Self { source, config }
+    }
+
+    /// Returns the configured percentile of the recent fees within the floor and the cap, the
+    /// `min_price` raises the floor. Falls back to the floor if the fees can't be fetched.
+    pub async fn estimate(&self, accounts: &[Pubkey], min_price: u64) -> u64 {
+        let accounts = &accounts[..accounts.len().min(MAX_FEE_ACCOUNTS)];
+        let price = match self.source.recent_prioritization_fees(accounts).await {
+            Ok(fees) => percentile(fees, self.config.percentile),
+            Err(err) => {
+                log::warn!("Failed to get recent prioritization fees: {}", err);
+                0
+            }
+        };
+        self.clamp(price, min_price)
+    }
+
+    /// Returns the price for the next attempt to send the message raised by the escalation step
+    pub fn escalate(&self, price: u64) -> u64 {
+        let step = (price.saturating_mul(self.config.escalation_percent) / 100).max(1);
+        self.clamp(price.saturating_add(step), 0)
+    }
+
+    fn clamp(&self, price: u64, min_price: u64) -> u64 {
+        let floor = self.config.floor.max(min_price);
+        price.max(floor).min(self.config.cap.max(floor))
+    }
+}
+
+fn percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let rank = (fees.len() - 1) * percentile.min(100) as usize / 100;
+    fees[rank]
+}
+
+/// Returns the static accounts the message may write to, accounts loaded from lookup tables are
+/// not taken into account
+pub fn writable_accounts(message: &VersionedMessage) -> Vec<Pubkey> {
+    message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| message.is_maybe_writable(*i))
+        .map(|(_, key)| *key)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::instruction::{AccountMeta, Instruction};
+
+    use super::*;
+    use crate::test_support::{message, oracle};
+
+    #[tokio::test]
+    async fn test_estimate() {
+        let accounts = [Pubkey::new_unique()];
+        let fees = (1..=100).map(|x| x * 100).collect();
+        let oracle = oracle(Some(fees));
+        assert_eq!(oracle.estimate(&accounts, 0).await, 7500);
+        assert_eq!(oracle.estimate(&accounts, 8000).await, 8000);
+        assert_eq!(*oracle.source.requested.lock().unwrap(), accounts);
+
+        assert_eq!(self::oracle(Some(vec![0; 150])).estimate(&accounts, 0).await, 1000);
+        assert_eq!(self::oracle(Some(vec![1_000_000])).estimate(&accounts, 0).await, 100_000);
+        assert_eq!(self::oracle(None).estimate(&accounts, 0).await, 1000);
+    }
+
+    #[test]
+    fn test_escalate() {
+        let oracle = oracle(None);
+        assert_eq!(oracle.escalate(1000), 1500);
+        assert_eq!(oracle.escalate(90_000), 100_000);
+        assert_eq!(oracle.escalate(100_000), 100_000);
+    }
+
+    #[test]
+    fn test_writable_accounts() {
+        let payer = Pubkey::new_unique();
+        let writable = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new(writable, false),
+                AccountMeta::new_readonly(payer, true),
+            ],
+        );
+        assert_eq!(writable_accounts(&message(&payer, &[ix])), [payer, writable]);
+    }
+}
@@ -4,14 +4,15 @@ use solana_sdk::{
     hash::Hash,
     instruction::Instruction,
     message::{v0::Message, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
+    signature::SIGNATURE_BYTES,
 };
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
-use crate::{log_with_ctx, TransactorError};
+use crate::{log_with_ctx, nonce_pool::advance_nonce_ix, TransactorError};
 
-const MAX_CU: u32 = 1_400_000;
-const MAX_MSG_LEN: usize = 1232 - 65; // assuming only one signature
+pub(crate) const MAX_CU: u32 = 1_400_000;
 
 #[derive(Debug)]
 pub struct InstructionBundle {
@@ -34,6 +35,7 @@ pub struct IxCompiler {
     payer: Pubkey,
     address_lookup_table_accounts: Vec<AddressLookupTableAccount>,
     compute_units_price: Option<u64>,
+    nonce: Option<(Pubkey, Pubkey)>,
 }
 
 impl IxCompiler {
@@ -44,6 +46,7 @@ impl IxCompiler {
             payer,
             address_lookup_table_accounts: Vec::new(),
             compute_units_price,
+            nonce: None,
         }
     }
 
@@ -51,6 +54,18 @@ impl IxCompiler {
         self.compute_units_price = compute_units_price;
     }
 
+    /// Sets the nonce account and its authority to compile durable messages
+    pub fn set_nonce(&mut self, nonce: Option<(Pubkey, Pubkey)>) {
+        self.nonce = nonce;
+    }
+
+    pub fn get_ix_nonce_if_any(&self) -> Vec<Instruction> {
+        self.nonce
+            .map(|(account, authority)| advance_nonce_ix(&account, &authority))
+            .into_iter()
+            .collect()
+    }
+
     pub fn get_ix_price_if_any(&self) -> Vec<Instruction> {
         self.compute_units_price
             .map(ComputeBudgetInstruction::set_compute_unit_price)
@@ -70,6 +85,7 @@ impl IxCompiler {
         let msg = Message::try_compile(
             &self.payer,
             &[
+                &self.get_ix_nonce_if_any()[..],
                 &[get_compute_units_ix(compute_units)],
                 &self.get_ix_price_if_any()[..],
                 &[ix.clone()],
@@ -79,13 +95,13 @@ impl IxCompiler {
             Hash::default(),
         )?;
         let msg = VersionedMessage::V0(msg);
-        let msg_len = msg.serialize().len();
-        if exceeds_limits(msg_len, compute_units) {
+        if exceeds_limits(transaction_len(&msg), compute_units) {
             return Err(TransactorError::InstructionTooBig);
         }
 
         let total_compute_units = self.total_compute_units + compute_units;
         let ix_buffer = [
+            &self.get_ix_nonce_if_any()[..],
             &[get_compute_units_ix(total_compute_units)],
             &self.get_ix_price_if_any()[..],
             &self.ix_buffer[..],
@@ -104,20 +120,21 @@ impl IxCompiler {
             Hash::default(),
         )?;
         let msg = VersionedMessage::V0(msg);
-        let msg_len = msg.serialize().len();
+        let tx_len = transaction_len(&msg);
         log_with_ctx!(
             trace,
             log_ctx,
             "Instructions: {} Tx len: {} CU: {}",
             self.ix_buffer.len(),
-            msg_len,
+            tx_len,
             total_compute_units
         );
-        if exceeds_limits(msg_len, total_compute_units) {
+        if exceeds_limits(tx_len, total_compute_units) {
             log_with_ctx!(debug, log_ctx, "Tx limit reached, sending previous instructions...");
             let msg = Message::try_compile(
                 &self.payer,
                 &[
+                    &self.get_ix_nonce_if_any()[..],
                     &[get_compute_units_ix(self.total_compute_units)],
                     &self.get_ix_price_if_any()[..],
                     &self.ix_buffer[..],
@@ -132,7 +149,7 @@ impl IxCompiler {
             self.address_lookup_table_accounts.extend_from_slice(address_lookup_table_accounts);
             self.total_compute_units = compute_units;
             return Ok(Some(VersionedMessage::V0(msg)));
-        } else if approaches_limits(msg_len, total_compute_units) {
+        } else if approaches_limits(tx_len, total_compute_units) {
             log_with_ctx!(debug, log_ctx, "Tx limit reached, sending current instructions...");
             self.ix_buffer.clear();
             self.address_lookup_table_accounts.clear();
@@ -141,7 +158,7 @@ impl IxCompiler {
         }
         self.ix_buffer.push(ix);
         self.address_lookup_table_accounts.extend_from_slice(address_lookup_table_accounts);
-        self.total_compute_units += total_compute_units;
+        self.total_compute_units = total_compute_units;
         Ok(None)
     }
 
@@ -152,6 +169,7 @@ impl IxCompiler {
         let msg = Message::try_compile(
             &self.payer,
             &[
+                &self.get_ix_nonce_if_any()[..],
                 &[get_compute_units_ix(self.total_compute_units)],
                 &self.get_ix_price_if_any()[..],
                 &self.ix_buffer[..],
@@ -167,14 +185,66 @@ impl IxCompiler {
     }
 }
 
+/// Returns the length of the transaction signed by every signer the message requires
+pub fn transaction_len(message: &VersionedMessage) -> usize {
+    let signatures = message.header().num_required_signatures as usize;
+    // The number of signatures is a compact-u16
+    let signatures_len = if signatures < 0x80 { 1 } else { 2 };
+    signatures_len + signatures * SIGNATURE_BYTES + message.serialize().len()
+}
+
 /// Returns true if tx exceeds limits
-fn exceeds_limits(msg_len: usize, compute_units: u32) -> bool {
-    msg_len > MAX_MSG_LEN || compute_units > MAX_CU
+fn exceeds_limits(tx_len: usize, compute_units: u32) -> bool {
+    tx_len > PACKET_DATA_SIZE || compute_units > MAX_CU
 }
 
 /// Returns true if tx approaches limits
-fn approaches_limits(msg_len: usize, compute_units: u32) -> bool {
-    msg_len >= MAX_MSG_LEN - 32 || compute_units >= MAX_CU - 200_000
+fn approaches_limits(tx_len: usize, compute_units: u32) -> bool {
+    tx_len >= PACKET_DATA_SIZE - 32 || compute_units >= MAX_CU - 200_000
+}
+
+/// Groups the instructions by the accounts they lock, the instructions of a group are connected by
+/// the accounts written by any of them. Returns the indices of the instructions of every group in
+/// the order they're given. The payer is locked by every message, so it doesn't connect them.
+pub fn lock_groups(instructions: &[InstructionBundle], payer: &Pubkey) -> Vec<Vec<usize>> {
+    let mut parents: Vec<_> = (0..instructions.len()).collect();
+    // The first instruction using the account and whether any of them writes it
+    let mut accounts: HashMap<Pubkey, (usize, bool)> = HashMap::new();
+    let mut users: Vec<(Pubkey, usize)> = Vec::new();
+    for (index, ix) in instructions.iter().enumerate() {
+        for account in ix.instruction.accounts.iter().filter(|a| &a.pubkey != payer) {
+            let (_, writable) = accounts.entry(account.pubkey).or_insert((index, false));
+            *writable |= account.is_writable;
+            users.push((account.pubkey, index));
+        }
+    }
+    for (account, index) in users {
+        let (first, writable) = accounts[&account];
+        if writable {
+            let (root, other) = (find_root(&mut parents, first), find_root(&mut parents, index));
+            // The root is the lowest index, so the groups keep the order of the instructions
+            parents[root.max(other)] = root.min(other);
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = HashMap::new();
+    for index in 0..instructions.len() {
+        let root = find_root(&mut parents, index);
+        let group = *group_of_root.entry(root).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[group].push(index);
+    }
+    groups
+}
+
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
 }
 
 fn get_compute_units_ix(compute_units: u32) -> Instruction {
@@ -226,4 +296,105 @@ mod test {
         let tx_raw: Vec<u8> = bincode::serialize(&tx).unwrap();
         assert!(tx_raw.len() <= 1232);
     }
+
+    #[test]
+    fn test_signers_packing() {
+        let program = Pubkey::new_unique();
+        for signers in 1..=5 {
+            let keypairs: Vec<_> = (0..signers).map(|_| Keypair::new()).collect();
+            let payer = keypairs[0].pubkey();
+            let mut ix_compiler = IxCompiler::new(payer, Some(1000));
+            ix_compiler.set_nonce(Some((Pubkey::new_unique(), payer)));
+            let mut messages = Vec::new();
+            for _ in 0..40 {
+                let accounts = [
+                    &keypairs[1..]
+                        .iter()
+                        .map(|k| AccountMeta::new(k.pubkey(), true))
+                        .collect::<Vec<_>>()[..],
+                    &vec![AccountMeta::new(Pubkey::new_unique(), false); 3][..],
+                ]
+                .concat();
+                let ix = Instruction::new_with_bytes(program, &[1; 64], accounts);
+                messages.extend(ix_compiler.compile::<&str>(None, ix, &[], 20000).unwrap());
+            }
+            messages.extend(ix_compiler.flush().unwrap());
+
+            let mut packed = 0;
+            for msg in messages {
+                assert_eq!(msg.header().num_required_signatures as usize, signers);
+                packed += msg
+                    .instructions()
+                    .iter()
+                    .filter(|ix| msg.static_account_keys()[ix.program_id_index as usize] == program)
+                    .count();
+                let expected_len = transaction_len(&msg);
+                let keypairs: Vec<_> = keypairs.iter().collect();
+                let tx = VersionedTransaction::try_new(msg, &keypairs).unwrap();
+                let tx_raw = bincode::serialize(&tx).unwrap();
+                assert_eq!(tx_raw.len(), expected_len);
+                assert!(tx_raw.len() <= PACKET_DATA_SIZE, "{} signers", signers);
+            }
+            assert_eq!(packed, 40);
+        }
+    }
+
+    #[test]
+    fn test_compute_units() {
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let mut ix_compiler = IxCompiler::new(payer, None);
+        for _ in 0..3 {
+            assert!(ix_compiler.compile::<&str>(None, ix.clone(), &[], 100_000).unwrap().is_none());
+        }
+        let msg = ix_compiler.flush().unwrap().unwrap();
+        assert_eq!(msg.instructions()[0].data, get_compute_units_ix(300_000).data);
+
+        // The limit approached by the next instruction sends the packed ones with it
+        let msg = ix_compiler.compile::<&str>(None, ix.clone(), &[], 1_000_000).unwrap();
+        assert!(msg.is_none());
+        let msg = ix_compiler.compile::<&str>(None, ix.clone(), &[], 300_000).unwrap().unwrap();
+        assert_eq!(msg.instructions()[0].data, get_compute_units_ix(1_300_000).data);
+        assert!(ix_compiler.flush().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_lock_groups() {
+        let payer = Pubkey::new_unique();
+        let [a, b, c] = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let program = Pubkey::new_unique();
+        let ix = |accounts| {
+            InstructionBundle::new(Instruction::new_with_bytes(program, &[], accounts), 0)
+        };
+        let instructions = [
+            ix(vec![AccountMeta::new(a, false), AccountMeta::new(payer, true)]),
+            ix(vec![AccountMeta::new_readonly(b, false)]),
+            ix(vec![AccountMeta::new_readonly(a, false)]),
+            ix(vec![
+                AccountMeta::new_readonly(b, false),
+                AccountMeta::new(payer, true),
+            ]),
+            ix(vec![AccountMeta::new(payer, true)]),
+            ix(vec![
+                AccountMeta::new(c, false),
+                AccountMeta::new_readonly(program, false),
+            ]),
+            ix(vec![AccountMeta::new(b, false)]),
+        ];
+        assert_eq!(
+            lock_groups(&instructions, &payer),
+            vec![vec![0, 2], vec![1, 3, 6], vec![4], vec![5]]
+        );
+
+        // Read only accounts don't connect the instructions
+        let instructions = [
+            ix(vec![AccountMeta::new_readonly(a, false)]),
+            ix(vec![AccountMeta::new_readonly(a, false)]),
+        ];
+        assert_eq!(lock_groups(&instructions, &payer), vec![vec![0], vec![1]]);
+    }
 }
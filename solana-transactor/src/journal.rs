@@ -0,0 +1,298 @@
+#![allow(clippy::result_large_err)]
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+use crate::{config::JournalConfig, TransactorError};
+
+/// Records the bundles sent by the transactor, so their confirmation and finalization are
+/// resumed after a restart
+pub trait Journal: Send + Sync {
+    fn append(&self, entry: JournalEntry) -> Result<(), TransactorError>;
+
+    /// Returns the entries of the bundle in the order they were recorded
+    fn history(&self, id: Uuid) -> Result<Vec<JournalEntry>, TransactorError>;
+
+    /// Returns the histories of the bundles that are neither finalized nor failed
+    fn unfinished(&self) -> Result<Vec<Vec<JournalEntry>>, TransactorError>;
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BundleEvent {
+    /// The transaction has been signed with the blockhash and sent
+    Sent {
+        transaction: VersionedTransaction,
+        payer: Pubkey,
+        log_ctx: Option<String>,
+        finalize: bool,
+    },
+    Confirmed(Signature),
+    Finalized(Signature),
+    Failed(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    id: u128,
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    pub event: BundleEvent,
+}
+
+impl JournalEntry {
+    pub fn new(id: Uuid, event: BundleEvent) -> Self {
+        Self {
+            id: id.as_u128(),
+            timestamp: now(),
+            event,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        Uuid::from_u128(self.id)
+    }
+}
+
+/// Returns true if nothing is left to do for the bundle with the history
+fn is_finished(history: &[JournalEntry]) -> bool {
+    let finalize =
+        history.iter().any(|entry| matches!(entry.event, BundleEvent::Sent { finalize: true, .. }));
+    match history.last().map(|entry| &entry.event) {
+        Some(BundleEvent::Sent { .. }) | None => false,
+        Some(BundleEvent::Confirmed(_)) => !finalize,
+        Some(BundleEvent::Finalized(_) | BundleEvent::Failed(_)) => true,
+    }
+}
+
+/// Appends the entries to a file as length prefixed bincode records. The entries are indexed in
+/// memory, the finished bundles are dropped after the retention period when the file is opened
+/// and compacted, see [JournalConfig].
+pub struct FileJournal {
+    path: PathBuf,
+    config: JournalConfig,
+    file: Mutex<JournalFile>,
+    bundles: Mutex<IndexMap<u128, Vec<JournalEntry>>>,
+}
+
+/// The file the entries are appended to, it's replaced on every compaction
+struct JournalFile {
+    file: File,
+    /// Bytes appended since the last compaction
+    appended: u64,
+    /// Unix timestamp of the last compaction in seconds
+    compacted_at: u64,
+}
+
+impl FileJournal {
+    pub fn open(config: &JournalConfig) -> Result<Self, TransactorError> {
+        let path = PathBuf::from(&config.path);
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut bundles = IndexMap::<_, Vec<_>>::new();
+        for entry in decode(&data) {
+            bundles.entry(entry.id).or_default().push(entry);
+        }
+        let file = compact(&path, &mut bundles, config.retention_secs)?;
+        Ok(Self {
+            path,
+            config: config.clone(),
+            file: Mutex::new(file),
+            bundles: Mutex::new(bundles),
+        })
+    }
+}
+
+impl Journal for FileJournal {
+    fn append(&self, entry: JournalEntry) -> Result<(), TransactorError> {
+        let record = encode(&entry)?;
+        let mut file = self.file.lock().expect("Lock poisoned");
+        file.file.write_all(&record)?;
+        file.appended += record.len() as u64;
+        let mut bundles = self.bundles.lock().expect("Lock poisoned");
+        bundles.entry(entry.id).or_default().push(entry);
+        if file.appended >= self.config.compaction_bytes
+            || now() >= file.compacted_at.saturating_add(self.config.compaction_secs)
+        {
+            *file = compact(&self.path, &mut bundles, self.config.retention_secs)?;
+        }
+        Ok(())
+    }
+
+    fn history(&self, id: Uuid) -> Result<Vec<JournalEntry>, TransactorError> {
+        let bundles = self.bundles.lock().expect("Lock poisoned");
+        Ok(bundles.get(&id.as_u128()).cloned().unwrap_or_default())
+    }
+
+    fn unfinished(&self) -> Result<Vec<Vec<JournalEntry>>, TransactorError> {
+        let bundles = self.bundles.lock().expect("Lock poisoned");
+        Ok(bundles.values().filter(|history| !is_finished(history)).cloned().collect())
+    }
+}
+
+/// Drops the bundles finished before the retention period and rewrites the file with the rest.
+/// The compacted journal replaces the file at once, a partially written record is dropped.
+fn compact(
+    path: &Path,
+    bundles: &mut IndexMap<u128, Vec<JournalEntry>>,
+    retention_secs: u64,
+) -> Result<JournalFile, TransactorError> {
+    let expired = now().saturating_sub(retention_secs);
+    bundles.retain(|_, history| {
+        !is_finished(history) || history.last().is_some_and(|e| e.timestamp >= expired)
+    });
+
+    let tmp_path = path.with_extension("tmp");
+    let mut tmp = File::create(&tmp_path)?;
+    for entry in bundles.values().flatten() {
+        tmp.write_all(&encode(entry)?)?;
+    }
+    tmp.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(JournalFile {
+        file: OpenOptions::new().append(true).open(path)?,
+        appended: 0,
+        compacted_at: now(),
+    })
+}
+
+fn encode(entry: &JournalEntry) -> Result<Vec<u8>, TransactorError> {
+    let data = bincode::serialize(entry)?;
+    Ok([&(data.len() as u32).to_le_bytes()[..], &data].concat())
+}
+
+/// Decodes the records until the first truncated or malformed one
+fn decode(mut data: &[u8]) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    while data.len() >= 4 {
+        let (len, rest) = data.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize;
+        let Some(entry) = rest.get(..len).and_then(|r| bincode::deserialize(r).ok()) else {
+            log::warn!("Journal is truncated, {} bytes dropped", data.len());
+            break;
+        };
+        entries.push(entry);
+        data = &rest[len..];
+    }
+    entries
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::ZERO).as_secs()
+}
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::{
+        hash::Hash,
+        message::{v0::Message, VersionedMessage},
+    };
+
+    use super::*;
+
+    fn sent(finalize: bool) -> BundleEvent {
+        let payer = Pubkey::new_unique();
+        let message = Message::try_compile(&payer, &[], &[], Hash::new_unique()).unwrap();
+        BundleEvent::Sent {
+            transaction: VersionedTransaction {
+                signatures: vec![Signature::new_unique()],
+                message: VersionedMessage::V0(message),
+            },
+            payer,
+            log_ctx: Some("ctx".to_owned()),
+            finalize,
+        }
+    }
+
+    #[test]
+    fn test_file_journal() {
+        let path = std::env::temp_dir().join(format!("journal-{}", Uuid::new_v4()));
+        let config = JournalConfig {
+            path: path.to_string_lossy().into_owned(),
+            retention_secs: 3600,
+            ..Default::default()
+        };
+        let confirmed = Uuid::new_v4();
+        let finalizing = Uuid::new_v4();
+        let failed = Uuid::new_v4();
+        let history = [
+            JournalEntry::new(confirmed, sent(false)),
+            JournalEntry::new(confirmed, BundleEvent::Confirmed(Signature::new_unique())),
+        ];
+        {
+            let journal = FileJournal::open(&config).unwrap();
+            for entry in history.iter().cloned() {
+                journal.append(entry).unwrap();
+            }
+            journal.append(JournalEntry::new(finalizing, sent(true))).unwrap();
+            let signature = Signature::new_unique();
+            journal
+                .append(JournalEntry::new(finalizing, BundleEvent::Confirmed(signature)))
+                .unwrap();
+            // Finished long ago
+            for event in [sent(true), BundleEvent::Failed("err".to_owned())] {
+                let entry = JournalEntry {
+                    timestamp: 0,
+                    ..JournalEntry::new(failed, event)
+                };
+                journal.append(entry).unwrap();
+            }
+            assert_eq!(journal.history(failed).unwrap().len(), 2);
+        }
+        // A record cut by a crash is dropped
+        let mut data = fs::read(&path).unwrap();
+        data.extend_from_slice(&100u32.to_le_bytes());
+        data.extend_from_slice(&[1, 2, 3]);
+        fs::write(&path, data).unwrap();
+
+        let journal = FileJournal::open(&config).unwrap();
+        assert_eq!(journal.history(confirmed).unwrap(), history);
+        assert_eq!(journal.history(Uuid::new_v4()).unwrap(), []);
+        let unfinished = journal.unfinished().unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0][0].id(), finalizing);
+        // Finished bundles are dropped after the retention period
+        assert_eq!(journal.history(failed).unwrap(), []);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_compaction() {
+        let path = std::env::temp_dir().join(format!("journal-{}", Uuid::new_v4()));
+        let config = JournalConfig {
+            path: path.to_string_lossy().into_owned(),
+            retention_secs: 3600,
+            compaction_bytes: 1,
+            ..Default::default()
+        };
+        let journal = FileJournal::open(&config).unwrap();
+        let failed = Uuid::new_v4();
+        let sending = Uuid::new_v4();
+        journal.append(JournalEntry::new(sending, sent(false))).unwrap();
+        // Finished long ago
+        for event in [sent(false), BundleEvent::Failed("err".to_owned())] {
+            let entry = JournalEntry {
+                timestamp: 0,
+                ..JournalEntry::new(failed, event)
+            };
+            journal.append(entry).unwrap();
+        }
+        // Evicted from the memory and the file without reopening the journal
+        assert_eq!(journal.history(failed).unwrap(), []);
+        assert_eq!(decode(&fs::read(&path).unwrap()), journal.history(sending).unwrap());
+        assert_eq!(journal.unfinished().unwrap().len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+}
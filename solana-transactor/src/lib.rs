@@ -1,16 +1,28 @@
 #[macro_use]
 
 pub mod alt_manager;
+pub mod compute_budget;
 mod config;
 mod error;
+pub mod fee_oracle;
 pub mod ix_compiler;
+pub mod journal;
+pub mod nonce_pool;
+pub mod pubsub;
+mod rate_limit;
 mod round_robin;
+mod rpc_health;
 mod rpc_pool;
+pub mod send_result;
+pub mod simulator;
+#[cfg(test)]
+mod test_support;
 mod transactor;
 
 pub use config::*;
 pub use error::TransactorError;
 pub use round_robin::RoundRobin;
+pub use rpc_health::{CircuitState, RpcPoolStats, RpcStats};
 pub use rpc_pool::RpcPool;
 pub use transactor::*;
 
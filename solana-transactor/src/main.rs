@@ -1,5 +1,5 @@
 #![allow(clippy::never_loop)]
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use clap::Parser;
 use solana_sdk::{
@@ -11,7 +11,10 @@ use solana_sdk::{
     transaction::{Transaction, VersionedTransaction},
 };
 use solana_transactor::{
-    alt_manager, ix_compiler::InstructionBundle, MessageBundle, RpcEntry, RpcPool, SolanaTransactor,
+    alt_manager::{self, AltRegistry},
+    ix_compiler::InstructionBundle,
+    send_result::all_landed,
+    AltRegistryConfig, MessageBundle, RpcEntry, RpcPool, SolanaTransactor,
 };
 
 #[derive(Parser, Debug)]
@@ -51,28 +54,40 @@ async fn main() {
         RpcEntry {
             url: "https://small-quaint-river.solana-mainnet.quiknode.pro/8dee9265e6e19f6bef9363466dd80c7ed971bfd8/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
         RpcEntry {
             url: "https://thrumming-cosmological-bridge.solana-mainnet.quiknode.pro/977a5d82f7a9684645762d4cf901752c447d1670/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
         RpcEntry {
             url: "https://wandering-distinguished-energy.solana-mainnet.quiknode.pro/72dff9a93a6f2b2c770719d26e67ba7cb320c16f/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
     ];
     let write_rpcs = [
         RpcEntry {
             url: "https://wandering-distinguished-energy.solana-mainnet.quiknode.pro/72dff9a93a6f2b2c770719d26e67ba7cb320c16f/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
         RpcEntry {
             url: "https://small-quaint-river.solana-mainnet.quiknode.pro/8dee9265e6e19f6bef9363466dd80c7ed971bfd8/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
         RpcEntry {
             url: "https://thrumming-cosmological-bridge.solana-mainnet.quiknode.pro/977a5d82f7a9684645762d4cf901752c447d1670/".to_owned(),
             ratelimit: 3,
+            burst: None,
+            ws_url: None,
         },
     ];
     let pool = RpcPool::new(&read_rpcs, &write_rpcs).unwrap();
@@ -97,10 +112,10 @@ async fn main() {
                     bincode::deserialize_from(&mut std::io::Cursor::new(raw)).expect("Invalid tx");
                 VersionedTransaction::from(tx)
             };
-            transactor
+            let results = transactor
                 .send::<&str>(None, &[MessageBundle::new(&tx.message, &[&k], k.pubkey())], true)
-                .await
-                .unwrap();
+                .await;
+            all_landed(results).unwrap();
             transactor.await_all_tx().await;
         }
         Subcommand::Batch { signers, data } => {
@@ -184,17 +199,32 @@ async fn main() {
             }
             let alts: Vec<_> = alts.values().cloned().collect();
             let signers: Vec<_> = signers.iter().collect();
-            alt_manager::send_with_alt(
-                &transactor,
+            let primary = Keypair::from_bytes(&k.to_bytes()).expect("Always 64 bytes");
+            let registry =
+                AltRegistry::open(transactor.clone(), primary, AltRegistryConfig::default())
+                    .await
+                    .expect("Failed to open ALT registry");
+            // Deactivates and closes the tables left idle by the previous runs
+            let registry = Arc::new(registry);
+            let cleanup = tokio::spawn({
+                let registry = registry.clone();
+                async move { registry.run_cleanup().await }
+            });
+            let results = alt_manager::send_with_alt(
+                &registry,
+                "batch",
                 &instructions,
-                &k,
                 &signers,
                 1,
                 &alts,
                 Some(10000),
             )
             .await;
+            if let Err(e) = results.and_then(all_landed) {
+                log::error!("Failed to send tx: {}", e);
+            }
             transactor.await_all_tx().await;
+            cleanup.abort();
         }
     }
 }
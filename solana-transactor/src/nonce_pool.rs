@@ -0,0 +1,257 @@
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::VersionedMessage,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    system_instruction::{self, SystemInstruction},
+    system_program,
+};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::config::NonceConfig;
+
+const NONCE_SEED_PREFIX: &str = "nonce-";
+
+/// Nonce accounts derived from the authority by seeds, every message sent with a nonce holds one
+/// of them until it's confirmed
+pub struct NoncePool {
+    authority: Pubkey,
+    accounts: Vec<Pubkey>,
+    free: Mutex<Vec<Pubkey>>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl NoncePool {
+    pub fn new(config: NonceConfig) -> Self {
+        let accounts: Vec<_> =
+            (0..config.accounts).map(|i| nonce_address(&config.authority, i)).collect();
+        Self {
+            authority: config.authority,
+            free: Mutex::new(accounts.iter().rev().copied().collect()),
+            semaphore: Arc::new(Semaphore::new(accounts.len())),
+            accounts,
+        }
+    }
+
+    pub fn authority(&self) -> &Pubkey {
+        &self.authority
+    }
+
+    pub fn accounts(&self) -> &[Pubkey] {
+        &self.accounts
+    }
+
+    /// Returns the account messages are compiled with before a nonce account is leased
+    pub fn placeholder(&self) -> Option<&Pubkey> {
+        self.accounts.first()
+    }
+
+    /// Waits for a free nonce account, it's returned to the pool when the lease is dropped
+    pub async fn lease(self: &Arc<Self>) -> NonceLease {
+        let permit = self.semaphore.clone().acquire_owned().await.expect("Never closed");
+        let account = self.free.lock().expect("Lock poisoned").pop().expect("Guarded by permit");
+        NonceLease {
+            pool: self.clone(),
+            account,
+            _permit: permit,
+        }
+    }
+
+    /// Leases the account if it's free without waiting, e.g. for a message sent before a restart
+    pub fn try_lease(self: &Arc<Self>, account: &Pubkey) -> Option<NonceLease> {
+        let permit = self.semaphore.clone().try_acquire_owned().ok()?;
+        let mut free = self.free.lock().expect("Lock poisoned");
+        let position = free.iter().position(|free| free == account)?;
+        free.remove(position);
+        Some(NonceLease {
+            pool: self.clone(),
+            account: *account,
+            _permit: permit,
+        })
+    }
+
+    /// Returns the instructions creating the nonce account at the index, the authority signs as the
+    /// base of the address
+    pub fn create_instructions(
+        &self,
+        payer: &Pubkey,
+        lamports: u64,
+        index: u16,
+    ) -> Vec<Instruction> {
+        system_instruction::create_nonce_account_with_seed(
+            payer,
+            &self.accounts[index as usize],
+            &self.authority,
+            &nonce_seed(index),
+            &self.authority,
+            lamports,
+        )
+    }
+}
+
+pub struct NonceLease {
+    pool: Arc<NoncePool>,
+    account: Pubkey,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl NonceLease {
+    pub fn account(&self) -> &Pubkey {
+        &self.account
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        // The permit is released after the account is back
+        self.pool.free.lock().expect("Lock poisoned").push(self.account);
+    }
+}
+
+fn nonce_seed(index: u16) -> String {
+    format!("{}{}", NONCE_SEED_PREFIX, index)
+}
+
+fn nonce_address(authority: &Pubkey, index: u16) -> Pubkey {
+    Pubkey::create_with_seed(authority, &nonce_seed(index), &system_program::ID)
+        .expect("Seed is short enough")
+}
+
+/// Returns the size of the nonce account data
+pub fn nonce_account_size() -> usize {
+    State::size()
+}
+
+/// Returns the durable nonce stored in the account data, `None` if the account is not initialized
+pub fn nonce_from_account_data(data: &[u8]) -> Option<Hash> {
+    match bincode::deserialize::<Versions>(data).ok()?.state() {
+        State::Initialized(data) => Some(data.blockhash()),
+        State::Uninitialized => None,
+    }
+}
+
+/// Returns the nonce account advanced by the first instruction of the message, such a message
+/// stays valid until the nonce is advanced
+pub fn durable_nonce(message: &VersionedMessage) -> Option<Pubkey> {
+    advance_nonce_account(message, 0)
+}
+
+/// Returns the authority signing the nonce advance of the durable message
+pub fn nonce_authority(message: &VersionedMessage) -> Option<Pubkey> {
+    advance_nonce_account(message, 2)
+}
+
+/// Returns the account at the position of the nonce advancing instruction of the message
+fn advance_nonce_account(message: &VersionedMessage, position: usize) -> Option<Pubkey> {
+    let keys = message.static_account_keys();
+    let ix = message.instructions().first()?;
+    if keys.get(ix.program_id_index as usize) != Some(&system_program::ID) {
+        return None;
+    }
+    match bincode::deserialize(&ix.data) {
+        Ok(SystemInstruction::AdvanceNonceAccount) => {
+            keys.get(*ix.accounts.get(position)? as usize).copied()
+        }
+        _ => None,
+    }
+}
+
+/// Replaces the placeholder nonce account of the message in place and sets the nonce as its
+/// blockhash. Returns false if the message doesn't advance the placeholder.
+pub fn set_nonce(
+    message: &mut VersionedMessage,
+    placeholder: &Pubkey,
+    account: &Pubkey,
+    nonce: Hash,
+) -> bool {
+    if durable_nonce(message).as_ref() != Some(placeholder) {
+        return false;
+    }
+    let keys = match message {
+        VersionedMessage::Legacy(message) => &mut message.account_keys,
+        VersionedMessage::V0(message) => &mut message.account_keys,
+    };
+    if let Some(key) = keys.iter_mut().find(|key| *key == placeholder) {
+        *key = *account;
+    }
+    message.set_recent_blockhash(nonce);
+    true
+}
+
+/// Returns the instruction advancing the nonce, it has to be the first one of the message
+pub fn advance_nonce_ix(account: &Pubkey, authority: &Pubkey) -> Instruction {
+    system_instruction::advance_nonce_account(account, authority)
+}
+
+#[cfg(test)]
+mod test {
+    use futures::FutureExt;
+    use solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        nonce::state::{Data, DurableNonce},
+    };
+
+    use super::*;
+    use crate::test_support::{message, nonce_pool as pool};
+
+    #[tokio::test]
+    async fn test_lease() {
+        let pool = pool(2);
+        assert_eq!(pool.accounts()[1], nonce_address(pool.authority(), 1));
+        assert_ne!(pool.accounts()[0], pool.accounts()[1]);
+
+        let first = pool.lease().await;
+        let second = pool.lease().await;
+        assert_eq!(first.account(), &pool.accounts()[0]);
+        assert_eq!(second.account(), &pool.accounts()[1]);
+        assert!(pool.lease().now_or_never().is_none());
+
+        drop(second);
+        let third = pool.lease().now_or_never().unwrap();
+        assert_eq!(third.account(), &pool.accounts()[1]);
+
+        drop(first);
+        assert!(pool.try_lease(&pool.accounts()[1]).is_none());
+        let first = pool.try_lease(&pool.accounts()[0]).unwrap();
+        assert_eq!(first.account(), &pool.accounts()[0]);
+        assert!(pool.lease().now_or_never().is_none());
+    }
+
+    #[test]
+    fn test_durable_message() {
+        let pool = pool(2);
+        let authority = *pool.authority();
+        let placeholder = *pool.placeholder().unwrap();
+        let ixs = [
+            advance_nonce_ix(&placeholder, &authority),
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+        ];
+        let mut message = message(&authority, &ixs);
+        assert_eq!(durable_nonce(&message), Some(placeholder));
+        assert_eq!(nonce_authority(&message), Some(authority));
+
+        let nonce = Hash::new_unique();
+        let account = pool.accounts()[1];
+        assert!(set_nonce(&mut message, &placeholder, &account, nonce));
+        assert_eq!(durable_nonce(&message), Some(account));
+        assert_eq!(*message.recent_blockhash(), nonce);
+        assert!(!set_nonce(&mut message, &placeholder, &account, nonce));
+
+        assert_eq!(durable_nonce(&self::message(&authority, &ixs[1..])), None);
+    }
+
+    #[test]
+    fn test_nonce_account_data() {
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let data = Data::new(Pubkey::new_unique(), durable_nonce, 5000);
+        let account_data = bincode::serialize(&Versions::new(State::Initialized(data))).unwrap();
+        assert_eq!(account_data.len(), nonce_account_size());
+        assert_eq!(nonce_from_account_data(&account_data), Some(*durable_nonce.as_hash()));
+
+        let account_data = bincode::serialize(&Versions::new(State::Uninitialized)).unwrap();
+        assert_eq!(nonce_from_account_data(&account_data), None);
+        assert_eq!(nonce_from_account_data(&[]), None);
+    }
+}
@@ -0,0 +1,207 @@
+use futures::{stream::FuturesUnordered, StreamExt};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient, rpc_config::RpcSignatureSubscribeConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
+
+use crate::rpc_health::RpcHealth;
+
+/// Delay before reconnecting to the websocket endpoint
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Time the endpoint has to acknowledge a subscription
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+struct Subscribe {
+    signature: Signature,
+    commitment: CommitmentConfig,
+    subscribed: oneshot::Sender<()>,
+    notified: oneshot::Sender<()>,
+}
+
+/// Notification of a transaction landing at the commitment. The subscription is closed if the
+/// websocket disconnects before, the status has to be polled then.
+pub struct SignatureSubscription {
+    notified: Option<oneshot::Receiver<()>>,
+}
+
+impl SignatureSubscription {
+    /// Waits for the notification until the timeout, returns true once notified
+    pub async fn wait(&mut self, timeout: Duration) -> bool {
+        let Some(notified) = self.notified.as_mut() else {
+            tokio::time::sleep(timeout).await;
+            return false;
+        };
+        match tokio::time::timeout(timeout, notified).await {
+            Ok(Ok(())) => true,
+            Ok(Err(_)) => {
+                self.notified = None;
+                false
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.notified.is_none()
+    }
+}
+
+/// Waits for a notification of any of the subscriptions until the timeout, returns the notified
+/// signature
+pub async fn wait_any<'a>(
+    subscriptions: impl Iterator<Item = (&'a Signature, &'a mut SignatureSubscription)>,
+    timeout: Duration,
+) -> Option<Signature> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut waits: FuturesUnordered<_> = subscriptions
+        .filter(|(_, subscription)| !subscription.is_closed())
+        .map(|(signature, subscription)| async move {
+            subscription.wait(timeout).await.then_some(*signature)
+        })
+        .collect();
+    while let Some(notified) = waits.next().await {
+        if notified.is_some() {
+            return notified;
+        }
+    }
+    // The subscriptions closed early, the caller polls the statuses after the timeout
+    tokio::time::sleep_until(deadline).await;
+    None
+}
+
+/// Subscribes to signatures and slots over the websocket of an endpoint, reconnecting on
+/// disconnect
+pub(crate) struct PubsubNotifier {
+    url: String,
+    connected: AtomicBool,
+    commands: UnboundedSender<Subscribe>,
+    receiver: Mutex<Option<UnboundedReceiver<Subscribe>>>,
+}
+
+impl PubsubNotifier {
+    pub(crate) fn new(url: String) -> Self {
+        let (commands, receiver) = unbounded_channel();
+        Self {
+            url,
+            connected: AtomicBool::new(false),
+            commands,
+            receiver: Mutex::new(Some(receiver)),
+        }
+    }
+
+    pub(crate) fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+
+    /// Returns the subscription once the endpoint acknowledged it, `None` if it's disconnected
+    pub(crate) async fn subscribe(
+        &self,
+        signature: Signature,
+        commitment: CommitmentConfig,
+    ) -> Option<SignatureSubscription> {
+        if !self.is_connected() {
+            return None;
+        }
+        let (subscribed, subscribed_receiver) = oneshot::channel();
+        let (notified, notified_receiver) = oneshot::channel();
+        let command = Subscribe {
+            signature,
+            commitment,
+            subscribed,
+            notified,
+        };
+        self.commands.send(command).ok()?;
+        tokio::time::timeout(SUBSCRIBE_TIMEOUT, subscribed_receiver).await.ok()?.ok()?;
+        Some(SignatureSubscription {
+            notified: Some(notified_receiver),
+        })
+    }
+
+    /// Keeps the websocket connected and serves the subscriptions, the observed slots are
+    /// recorded to the health of the endpoint. Returns at once if it's already running.
+    pub(crate) async fn run(&self, health: Arc<Mutex<RpcHealth>>) {
+        let Some(mut commands) = self.receiver.lock().expect("Lock poisoned").take() else {
+            return;
+        };
+        loop {
+            match PubsubClient::new(&self.url).await {
+                Ok(client) => {
+                    log::info!("Connected to pubsub: {}", self.url);
+                    self.connected.store(true, Ordering::Release);
+                    Self::serve(&client, &mut commands, &health).await;
+                    self.connected.store(false, Ordering::Release);
+                    log::warn!("Pubsub disconnected: {}", self.url);
+                }
+                Err(err) => log::warn!("Failed to connect to pubsub: {}, error: {}", self.url, err),
+            }
+            // The subscriptions requested meanwhile fall back to polling
+            while commands.try_recv().is_ok() {}
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn serve(
+        client: &PubsubClient,
+        commands: &mut UnboundedReceiver<Subscribe>,
+        health: &Mutex<RpcHealth>,
+    ) {
+        let mut slots = match client.slot_subscribe().await {
+            Ok((slots, _)) => slots,
+            Err(err) => {
+                log::warn!("Failed to subscribe to slots: {}", err);
+                return;
+            }
+        };
+        let mut notifications = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                slot = slots.next() => match slot {
+                    Some(slot) => health.lock().expect("Lock poisoned").record_slot(slot.slot),
+                    None => return,
+                },
+                command = commands.recv() => {
+                    let Some(command) = command else {
+                        return;
+                    };
+                    let config = RpcSignatureSubscribeConfig {
+                        commitment: Some(command.commitment),
+                        enable_received_notification: Some(false),
+                    };
+                    match client.signature_subscribe(&command.signature, Some(config)).await {
+                        Ok((mut notification, _)) => {
+                            let _ = command.subscribed.send(());
+                            let mut notified = command.notified;
+                            notifications.push(async move {
+                                // The stream is dropped once the subscription is
+                                let landed = tokio::select! {
+                                    notification = notification.next() => notification.is_some(),
+                                    _ = notified.closed() => false,
+                                };
+                                if landed {
+                                    let _ = notified.send(());
+                                }
+                            });
+                        }
+                        Err(err) => log::warn!(
+                            "Failed to subscribe to signature: {}, error: {}",
+                            command.signature,
+                            err
+                        ),
+                    }
+                },
+                Some(()) = notifications.next(), if !notifications.is_empty() => {}
+            }
+        }
+    }
+}
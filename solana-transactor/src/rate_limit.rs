@@ -0,0 +1,460 @@
+#![allow(clippy::result_large_err)]
+
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{
+    header::{CONTENT_TYPE, RETRY_AFTER},
+    Response, StatusCode,
+};
+use serde::Deserialize;
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_custom_error::{
+        NodeUnhealthyErrorData, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+    },
+    rpc_request::{RpcError, RpcRequest, RpcResponseErrorData},
+    rpc_response::RpcSimulateTransactionResult,
+    rpc_sender::{RpcSender, RpcTransportStats},
+};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Pause after a rate limited response without `Retry-After`
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_millis(500);
+/// Longest `Retry-After` honoured
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+/// The rate is halved on every rate limited response, down to 1/2^MAX_PENALTY of the configured one
+const MAX_PENALTY: u32 = 4;
+/// Period the rate is doubled back after, if no request is rate limited
+const RATE_RECOVERY: Duration = Duration::from_secs(30);
+/// Rate limited requests are sent again this many times before the error is returned
+const RATE_LIMITED_RETRIES: usize = 5;
+/// Longest time a rate limited request is retried for, then the error is returned, so the pool can
+/// fail over to another endpoint
+const MAX_RATE_LIMITED_WAIT: Duration = Duration::from_secs(3);
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    /// Tokens are added since this instant, it's in the future while the endpoint is paused
+    updated: Instant,
+    penalty: u32,
+    recover_at: Instant,
+}
+
+/// Token bucket limiting the requests sent to an endpoint. Requests may burst up to the capacity,
+/// the rate is lowered for a while after the endpoint responds with 429.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: u64, burst: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            burst: burst as f64,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                updated: now,
+                penalty: 0,
+                recover_at: now,
+            }),
+        }
+    }
+
+    fn bucket(&self, now: Instant) -> std::sync::MutexGuard<'_, Bucket> {
+        let mut bucket = self.bucket.lock().expect("Lock poisoned");
+        while bucket.penalty > 0 && now >= bucket.recover_at {
+            bucket.penalty -= 1;
+            bucket.recover_at += RATE_RECOVERY;
+        }
+        if now > bucket.updated {
+            let elapsed = (now - bucket.updated).as_secs_f64();
+            bucket.tokens = (bucket.tokens + elapsed * self.rate(&bucket)).min(self.burst);
+            bucket.updated = now;
+        }
+        bucket
+    }
+
+    fn rate(&self, bucket: &Bucket) -> f64 {
+        self.rate / (1u64 << bucket.penalty) as f64
+    }
+
+    /// Returns the time left until a token is available
+    pub(crate) fn wait_time(&self, now: Instant) -> Duration {
+        let bucket = self.bucket(now);
+        let deficit = (1.0 - bucket.tokens).max(0.0) / self.rate(&bucket);
+        bucket.updated.saturating_duration_since(now) + Duration::from_secs_f64(deficit)
+    }
+
+    /// Takes a token and returns the time to wait before the request is sent
+    pub(crate) fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket(now);
+        bucket.tokens -= 1.0;
+        let deficit = (-bucket.tokens).max(0.0) / self.rate(&bucket);
+        bucket.updated.saturating_duration_since(now) + Duration::from_secs_f64(deficit)
+    }
+
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Pauses the endpoint for the retry period and halves its rate
+    pub(crate) fn throttle(&self, retry_after: Option<Duration>, now: Instant) {
+        let mut bucket = self.bucket(now);
+        let retry_after = retry_after.unwrap_or(DEFAULT_RETRY_AFTER).min(MAX_RETRY_AFTER);
+        bucket.penalty = (bucket.penalty + 1).min(MAX_PENALTY);
+        bucket.recover_at = now + retry_after + RATE_RECOVERY;
+        bucket.updated = bucket.updated.max(now + retry_after);
+        bucket.tokens = bucket.tokens.min(1.0);
+    }
+
+    /// Returns the current rate in requests per second
+    pub(crate) fn current_rate(&self, now: Instant) -> f64 {
+        self.rate(&self.bucket(now))
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// Sends the requests of a client over http once the limiter lets them through. Rate limited
+/// requests wait for `Retry-After` and are sent again unless the wait exceeds
+/// `MAX_RATE_LIMITED_WAIT` in total.
+pub(crate) struct RateLimitedSender {
+    client: Arc<reqwest::Client>,
+    url: String,
+    limiter: Arc<RateLimiter>,
+    request_id: AtomicU64,
+    stats: Mutex<RpcTransportStats>,
+}
+
+impl RateLimitedSender {
+    pub(crate) fn new(
+        client: Arc<reqwest::Client>,
+        url: String,
+        limiter: Arc<RateLimiter>,
+    ) -> Self {
+        Self {
+            client,
+            url,
+            limiter,
+            request_id: AtomicU64::new(0),
+            stats: Mutex::default(),
+        }
+    }
+
+    async fn send_request(&self, request_json: String) -> ClientResult<serde_json::Value> {
+        let mut retries = RATE_LIMITED_RETRIES;
+        let first_sent = Instant::now();
+        loop {
+            let start = Instant::now();
+            self.limiter.acquire().await;
+            self.stats.lock().expect("Lock poisoned").rate_limited_time += start.elapsed();
+            let response = self
+                .client
+                .post(&self.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(request_json.clone())
+                .send()
+                .await?;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = retry_after(&response);
+                debug!("Rpc {} is rate limited, retry after: {:?}", self.url, retry_after);
+                let now = Instant::now();
+                self.limiter.throttle(retry_after, now);
+                let resumed = now + self.limiter.wait_time(now);
+                if retries > 0 && resumed <= first_sent + MAX_RATE_LIMITED_WAIT {
+                    retries -= 1;
+                    continue;
+                }
+            }
+            let response = response.error_for_status()?;
+            return parse_response(response.json().await?);
+        }
+    }
+}
+
+#[async_trait]
+impl RpcSender for RateLimitedSender {
+    async fn send(
+        &self,
+        request: RpcRequest,
+        params: serde_json::Value,
+    ) -> ClientResult<serde_json::Value> {
+        let start = Instant::now();
+        let request_id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let request_json = request.build_request_json(request_id, params).to_string();
+        let res = self.send_request(request_json).await;
+        let mut stats = self.stats.lock().expect("Lock poisoned");
+        stats.request_count += 1;
+        stats.elapsed_time += start.elapsed();
+        res
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.lock().expect("Lock poisoned").clone()
+    }
+
+    fn url(&self) -> String {
+        self.url.clone()
+    }
+}
+
+/// Returns the delay in seconds of the `Retry-After` header, the date form is not supported
+fn retry_after(response: &Response) -> Option<Duration> {
+    let secs = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+fn parse_response(mut json: serde_json::Value) -> ClientResult<serde_json::Value> {
+    if !json["error"].is_object() {
+        return Ok(json["result"].take());
+    }
+    let error = match serde_json::from_value::<RpcErrorObject>(json["error"].clone()) {
+        Ok(error) => error,
+        Err(err) => {
+            return Err(RpcError::RpcRequestError(format!(
+                "Failed to deserialize RPC error response: {} [{}]",
+                json["error"], err
+            ))
+            .into())
+        }
+    };
+    let data = json["error"]["data"].take();
+    let data = match error.code {
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+            serde_json::from_value::<RpcSimulateTransactionResult>(data)
+                .map(RpcResponseErrorData::SendTransactionPreflightFailure)
+                .unwrap_or(RpcResponseErrorData::Empty)
+        }
+        JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => serde_json::from_value(data)
+            .map(|NodeUnhealthyErrorData { num_slots_behind }| {
+                RpcResponseErrorData::NodeUnhealthy { num_slots_behind }
+            })
+            .unwrap_or(RpcResponseErrorData::Empty),
+        _ => RpcResponseErrorData::Empty,
+    };
+    Err(RpcError::RpcResponseError {
+        code: error.code,
+        message: error.message,
+        data,
+    }
+    .into())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use solana_client::{
+        client_error::ClientErrorKind, nonblocking::rpc_client::RpcClient,
+        rpc_client::RpcClientConfig,
+    };
+    use std::collections::VecDeque;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// The status, the headers and the body of a scripted response
+    pub(crate) type StubResponse<'a> = (u16, Vec<(&'a str, &'a str)>, &'a str);
+
+    /// Serves the scripted responses in order over http, the requests are counted
+    pub(crate) struct HttpStub {
+        pub(crate) url: String,
+        pub(crate) requests: Arc<AtomicU64>,
+    }
+
+    impl HttpStub {
+        pub(crate) async fn start(responses: Vec<StubResponse<'_>>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let responses: VecDeque<_> = responses
+                .into_iter()
+                .map(|(status, headers, body)| {
+                    let headers: String = headers
+                        .iter()
+                        .map(|(name, value)| format!("{name}: {value}\r\n"))
+                        .collect();
+                    format!(
+                        "HTTP/1.1 {status} STUB\r\n{headers}Content-Type: application/json\r\n\
+                         Content-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                })
+                .collect();
+            let responses = Arc::new(Mutex::new(responses));
+            let requests = Arc::new(AtomicU64::new(0));
+            let counter = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let responses = responses.clone();
+                    let counter = counter.clone();
+                    tokio::spawn(async move {
+                        let mut stream = BufReader::new(stream);
+                        loop {
+                            let mut content_length = 0;
+                            let mut line = String::new();
+                            loop {
+                                line.clear();
+                                if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                                    return;
+                                }
+                                let lower = line.to_ascii_lowercase();
+                                if let Some(len) = lower.strip_prefix("content-length:") {
+                                    content_length = len.trim().parse().unwrap();
+                                }
+                                if line == "\r\n" {
+                                    break;
+                                }
+                            }
+                            let mut body = vec![0; content_length];
+                            stream.read_exact(&mut body).await.unwrap();
+                            counter.fetch_add(1, Ordering::SeqCst);
+                            let response = responses.lock().unwrap().pop_front();
+                            let Some(response) = response else {
+                                return;
+                            };
+                            stream.get_mut().write_all(response.as_bytes()).await.unwrap();
+                        }
+                    });
+                }
+            });
+            Self { url, requests }
+        }
+    }
+
+    async fn client(stub: &HttpStub, limiter: Arc<RateLimiter>) -> RpcClient {
+        let sender = RateLimitedSender::new(Arc::default(), stub.url.clone(), limiter);
+        let client = RpcClient::new_sender(sender, RpcClientConfig::default());
+        // Skips the version query
+        client.set_node_version(semver::Version::new(1, 16, 0)).await.unwrap();
+        client
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(10, 3, now);
+        // The burst passes at once, the next request waits for a token
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(now), Duration::ZERO);
+        }
+        assert_eq!(limiter.wait_time(now), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(now), Duration::from_millis(100));
+        assert_eq!(limiter.wait_time(now + Duration::from_millis(150)), Duration::from_millis(50));
+        // The bucket doesn't fill up over the burst
+        let now = now + Duration::from_secs(10);
+        for _ in 0..3 {
+            assert_eq!(limiter.reserve(now), Duration::ZERO);
+        }
+        assert!(!limiter.reserve(now).is_zero());
+    }
+
+    #[test]
+    fn test_throttle() {
+        let now = Instant::now();
+        let limiter = RateLimiter::new(8, 8, now);
+        limiter.throttle(Some(Duration::from_secs(2)), now);
+        assert_eq!(limiter.current_rate(now), 4.0);
+        assert_eq!(limiter.wait_time(now), Duration::from_secs(2));
+        let resumed = now + Duration::from_secs(2);
+        assert_eq!(limiter.reserve(resumed), Duration::ZERO);
+        assert_eq!(limiter.reserve(resumed), Duration::from_millis(250));
+
+        for _ in 0..MAX_PENALTY {
+            limiter.throttle(None, resumed);
+        }
+        assert_eq!(limiter.current_rate(resumed), 0.5);
+        // The rate is doubled back periodically
+        let recovered = resumed + DEFAULT_RETRY_AFTER + RATE_RECOVERY;
+        assert_eq!(limiter.current_rate(recovered), 1.0);
+        let recovered = recovered + RATE_RECOVERY * 3;
+        assert_eq!(limiter.current_rate(recovered), 8.0);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after() {
+        let stub = HttpStub::start(vec![
+            (429, vec![("Retry-After", "1")], ""),
+            (200, vec![], r#"{"jsonrpc":"2.0","result":42,"id":0}"#),
+        ])
+        .await;
+        let limiter = Arc::new(RateLimiter::new(100, 100, Instant::now()));
+        let start = Instant::now();
+        assert_eq!(client(&stub, limiter.clone()).await.get_slot().await.unwrap(), 42);
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 2);
+        assert_eq!(limiter.current_rate(Instant::now()), 50.0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let responses = (0..=RATE_LIMITED_RETRIES).map(|_| (429, vec![("Retry-After", "0")], ""));
+        let stub = HttpStub::start(responses.collect()).await;
+        let limiter = Arc::new(RateLimiter::new(1000, 1000, Instant::now()));
+        let err = client(&stub, limiter).await.get_slot().await.unwrap_err();
+        let ClientErrorKind::Reqwest(err) = err.kind() else {
+            panic!("Unexpected error {}", err);
+        };
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert_eq!(stub.requests.load(Ordering::SeqCst), RATE_LIMITED_RETRIES as u64 + 1);
+    }
+
+    #[tokio::test]
+    async fn test_long_retry_after() {
+        let stub = HttpStub::start(vec![
+            (429, vec![("Retry-After", "120")], ""),
+            (200, vec![], r#"{"jsonrpc":"2.0","result":42,"id":0}"#),
+        ])
+        .await;
+        let limiter = Arc::new(RateLimiter::new(100, 100, Instant::now()));
+        let start = Instant::now();
+        // The error is returned at once instead of blocking the request for two minutes
+        let err = client(&stub, limiter.clone()).await.get_slot().await.unwrap_err();
+        let ClientErrorKind::Reqwest(err) = err.kind() else {
+            panic!("Unexpected error {}", err);
+        };
+        assert_eq!(err.status(), Some(StatusCode::TOO_MANY_REQUESTS));
+        assert!(start.elapsed() < MAX_RATE_LIMITED_WAIT);
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 1);
+        // The endpoint stays paused, so the pool prefers the others meanwhile
+        assert!(limiter.wait_time(Instant::now()) > Duration::from_secs(100));
+    }
+
+    #[tokio::test]
+    async fn test_rpc_error() {
+        let body = r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is behind",
+            "data":{"numSlotsBehind":10}},"id":0}"#;
+        let stub = HttpStub::start(vec![(200, vec![], body)]).await;
+        let limiter = Arc::new(RateLimiter::new(100, 1, Instant::now()));
+        let err = client(&stub, limiter).await.get_slot().await.unwrap_err();
+        let ClientErrorKind::RpcError(RpcError::RpcResponseError { code, data, .. }) = err.kind()
+        else {
+            panic!("Unexpected error {}", err);
+        };
+        assert_eq!(*code, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY);
+        assert!(matches!(
+            data,
+            RpcResponseErrorData::NodeUnhealthy {
+                num_slots_behind: Some(10)
+            }
+        ));
+    }
+}
@@ -41,6 +41,10 @@ impl<T> RoundRobin<T> {
         Some((self.pool.get(current_index)?, current_max))
     }
 
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.pool.iter()
+    }
+
     pub fn len(&self) -> usize {
         self.pool.len()
     }
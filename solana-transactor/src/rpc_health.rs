@@ -0,0 +1,233 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+use crate::config::RpcHealthConfig;
+
+/// Weight of the last request in the success rate and the latency
+const EWMA_ALPHA: f64 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CircuitState {
+    /// Requests are sent
+    Closed,
+    /// The endpoint is ejected after consecutive failures
+    Open,
+    /// A single request probes the endpoint after the cooldown
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Circuit {
+    Closed,
+    Open { until: Instant, cooldown: Duration },
+    HalfOpen { since: Instant, cooldown: Duration },
+}
+
+/// Health of an endpoint observed from the requests sent to it
+#[derive(Debug, Clone)]
+pub(crate) struct RpcHealth {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    success_rate: f64,
+    latency_ms: Option<f64>,
+    slot: Option<u64>,
+    circuit: Circuit,
+}
+
+impl Default for RpcHealth {
+    fn default() -> Self {
+        Self {
+            requests: 0,
+            failures: 0,
+            consecutive_failures: 0,
+            success_rate: 1.0,
+            latency_ms: None,
+            slot: None,
+            circuit: Circuit::Closed,
+        }
+    }
+}
+
+impl RpcHealth {
+    /// Records the outcome of a request, the circuit opens after consecutive failures and its
+    /// cooldown doubles on every failed probe
+    pub(crate) fn record(
+        &mut self,
+        success: bool,
+        latency: Duration,
+        now: Instant,
+        config: &RpcHealthConfig,
+    ) {
+        self.requests += 1;
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(avg) => avg + EWMA_ALPHA * (latency_ms - avg),
+            None => latency_ms,
+        });
+        let outcome = if success { 1.0 } else { 0.0 };
+        self.success_rate += EWMA_ALPHA * (outcome - self.success_rate);
+        if success {
+            self.consecutive_failures = 0;
+            self.circuit = Circuit::Closed;
+            return;
+        }
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        let base = Duration::from_secs(config.open_secs);
+        let cooldown = match self.circuit {
+            Circuit::HalfOpen { cooldown, .. } | Circuit::Open { cooldown, .. } => {
+                (cooldown * 2).min(Duration::from_secs(config.max_open_secs)).max(base)
+            }
+            Circuit::Closed if self.consecutive_failures >= config.failure_threshold => base,
+            Circuit::Closed => return,
+        };
+        self.circuit = Circuit::Open {
+            until: now + cooldown,
+            cooldown,
+        };
+    }
+
+    pub(crate) fn record_slot(&mut self, slot: u64) {
+        self.slot = Some(slot);
+    }
+
+    pub(crate) fn slot(&self) -> Option<u64> {
+        self.slot
+    }
+
+    /// Returns true if a request may be sent, a cooled down circuit lets a probe through
+    pub(crate) fn is_available(&self, now: Instant) -> bool {
+        match self.circuit {
+            Circuit::Closed => true,
+            Circuit::Open { until, .. } => now >= until,
+            // The probe may have been dropped without an outcome
+            Circuit::HalfOpen { since, cooldown } => now >= since + cooldown,
+        }
+    }
+
+    /// Marks the request sent, a cooled down circuit becomes half-open until the probe completes
+    pub(crate) fn acquire(&mut self, now: Instant) {
+        if !self.is_available(now) {
+            return;
+        }
+        if let Circuit::Open { cooldown, .. } | Circuit::HalfOpen { cooldown, .. } = self.circuit {
+            self.circuit = Circuit::HalfOpen {
+                since: now,
+                cooldown,
+            };
+        }
+    }
+
+    /// Returns the expected time of a successful request in milliseconds
+    pub(crate) fn cost(&self) -> f64 {
+        self.latency_ms.unwrap_or_default() / self.success_rate.max(0.01)
+    }
+
+    /// Returns true if the endpoint is behind the highest slot observed in the pool
+    pub(crate) fn is_lagging(&self, max_slot: Option<u64>, config: &RpcHealthConfig) -> bool {
+        match (self.slot, max_slot) {
+            (Some(slot), Some(max_slot)) => slot + config.max_slot_lag < max_slot,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn stats(&self, url: String, rate: f64) -> RpcStats {
+        RpcStats {
+            url,
+            rate,
+            requests: self.requests,
+            failures: self.failures,
+            success_rate: self.success_rate,
+            latency_ms: self.latency_ms,
+            slot: self.slot,
+            circuit: match self.circuit {
+                Circuit::Closed => CircuitState::Closed,
+                Circuit::Open { .. } => CircuitState::Open,
+                Circuit::HalfOpen { .. } => CircuitState::HalfOpen,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcStats {
+    pub url: String,
+    /// Requests per second currently let through, lowered while the endpoint is rate limited
+    pub rate: f64,
+    pub requests: u64,
+    pub failures: u64,
+    /// Exponentially weighted rate of the successful requests
+    pub success_rate: f64,
+    /// Exponentially weighted latency in milliseconds
+    pub latency_ms: Option<f64>,
+    /// The slot observed by the last probe
+    pub slot: Option<u64>,
+    pub circuit: CircuitState,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RpcPoolStats {
+    pub read: Vec<RpcStats>,
+    pub write: Vec<RpcStats>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let config = RpcHealthConfig {
+            failure_threshold: 2,
+            open_secs: 5,
+            max_open_secs: 15,
+            ..Default::default()
+        };
+        let ms = Duration::from_millis(100);
+        let now = Instant::now();
+        let mut health = RpcHealth::default();
+        health.record(true, ms, now, &config);
+        assert_eq!(health.cost(), 100.0);
+
+        health.record(false, ms, now, &config);
+        assert!(health.is_available(now));
+        health.record(false, ms, now, &config);
+        assert!(!health.is_available(now));
+        assert_eq!(health.stats(String::new(), 0.0).circuit, CircuitState::Open);
+        assert!(health.cost() > 100.0);
+
+        // A single probe after the cooldown, the cooldown doubles when it fails
+        let now = now + Duration::from_secs(5);
+        assert!(health.is_available(now));
+        health.acquire(now);
+        assert!(!health.is_available(now));
+        assert_eq!(health.stats(String::new(), 0.0).circuit, CircuitState::HalfOpen);
+        health.record(false, ms, now, &config);
+        assert!(!health.is_available(now + Duration::from_secs(9)));
+        assert!(health.is_available(now + Duration::from_secs(10)));
+
+        // The cooldown is capped
+        let now = now + Duration::from_secs(10);
+        health.acquire(now);
+        health.record(false, ms, now, &config);
+        assert!(health.is_available(now + Duration::from_secs(15)));
+
+        let now = now + Duration::from_secs(15);
+        health.acquire(now);
+        health.record(true, ms, now, &config);
+        assert_eq!(health.stats(String::new(), 0.0).circuit, CircuitState::Closed);
+        let stats = health.stats(String::new(), 0.0);
+        assert_eq!((stats.requests, stats.failures), (6, 4));
+    }
+
+    #[test]
+    fn test_lagging() {
+        let config = RpcHealthConfig::default();
+        let mut health = RpcHealth::default();
+        assert!(!health.is_lagging(Some(1000), &config));
+        health.record_slot(1000 - config.max_slot_lag);
+        assert!(!health.is_lagging(Some(1000), &config));
+        assert!(health.is_lagging(Some(1001), &config));
+    }
+}
@@ -1,132 +1,255 @@
 use log::error;
 use portable_atomic::{AtomicU64, Ordering};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::commitment_config::CommitmentConfig;
+use solana_client::rpc_client::RpcClientConfig;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
 use std::{
+    cmp::Reverse,
     fmt::Debug,
-    time::{Duration, UNIX_EPOCH},
+    sync::Arc,
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
 use tokio::sync::Mutex;
 use url::Url;
 
-use crate::{config::RpcEntry, round_robin::RoundRobin, TransactorError};
+use crate::{
+    config::{RpcEntry, RpcHealthConfig},
+    pubsub::{PubsubNotifier, SignatureSubscription},
+    rate_limit::{RateLimitedSender, RateLimiter},
+    round_robin::RoundRobin,
+    rpc_health::{RpcHealth, RpcPoolStats},
+    TransactorError,
+};
 
 struct Rpc {
     url: Url,
     last_accessed: AtomicU64,
-    min_timeout: Duration,
+    limiter: Arc<RateLimiter>,
+    http_client: Arc<reqwest::Client>,
     cached_version: Mutex<Option<semver::Version>>,
+    health: Arc<std::sync::Mutex<RpcHealth>>,
+    pubsub: Option<PubsubNotifier>,
+}
+
+impl Rpc {
+    fn new(entry: &RpcEntry) -> Result<Self, TransactorError> {
+        if entry.ratelimit == 0 {
+            return Err(TransactorError::InvalidRpc(entry.url.clone()));
+        }
+        let http_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(3))
+            .build()
+            .map_err(TransactorError::HttpClient)?;
+        Ok(Rpc {
+            url: entry.url.parse().map_err(|_| TransactorError::InvalidRpc(entry.url.clone()))?,
+            cached_version: Mutex::default(),
+            last_accessed: AtomicU64::new(now()),
+            limiter: Arc::new(RateLimiter::new(
+                entry.ratelimit,
+                entry.burst.unwrap_or(1).max(1),
+                Instant::now(),
+            )),
+            http_client: Arc::new(http_client),
+            health: Default::default(),
+            pubsub: entry.ws_url.clone().map(PubsubNotifier::new),
+        })
+    }
+
+    fn client(&self, commitment: CommitmentConfig) -> RpcClient {
+        let sender = RateLimitedSender::new(
+            self.http_client.clone(),
+            self.url.to_string(),
+            self.limiter.clone(),
+        );
+        RpcClient::new_sender(sender, RpcClientConfig::with_commitment(commitment))
+    }
+
+    fn health(&self) -> std::sync::MutexGuard<'_, RpcHealth> {
+        self.health.lock().expect("Lock poisoned")
+    }
 }
 
 #[derive(Clone)]
 pub struct RpcPool {
     read_rpcs: RoundRobin<Rpc>,
     write_rpcs: RoundRobin<Rpc>,
+    health_config: RpcHealthConfig,
 }
 
 impl RpcPool {
     pub fn new(read_rpcs: &[RpcEntry], write_rpcs: &[RpcEntry]) -> Result<Self, TransactorError> {
+        Self::new_with_health(read_rpcs, write_rpcs, RpcHealthConfig::default())
+    }
+
+    pub fn new_with_health(
+        read_rpcs: &[RpcEntry],
+        write_rpcs: &[RpcEntry],
+        health_config: RpcHealthConfig,
+    ) -> Result<Self, TransactorError> {
         if read_rpcs.is_empty() {
             return Err(TransactorError::NoReadRpcs);
         }
         if write_rpcs.is_empty() {
             return Err(TransactorError::NoWriteRpcs);
         }
-        let read_rpcs = read_rpcs
-            .iter()
-            .map(|rpc_config| {
-                let min_timeout = Duration::from_nanos(1_000_000_000 / rpc_config.ratelimit);
-                Ok(Rpc {
-                    url: rpc_config
-                        .url
-                        .parse()
-                        .map_err(|_| TransactorError::InvalidRpc(rpc_config.url.clone()))?,
-                    cached_version: Mutex::default(),
-                    last_accessed: AtomicU64::new(now()),
-                    min_timeout,
-                })
-            })
-            .collect::<Result<Vec<Rpc>, TransactorError>>()?;
-        let write_rpcs = write_rpcs
-            .iter()
-            .map(|rpc_config| {
-                let min_timeout = Duration::from_nanos(1_000_000_000 / rpc_config.ratelimit);
-                Ok(Rpc {
-                    url: rpc_config
-                        .url
-                        .parse()
-                        .map_err(|_| TransactorError::InvalidRpc(rpc_config.url.clone()))?,
-                    cached_version: Mutex::default(),
-                    last_accessed: AtomicU64::new(now()),
-                    min_timeout,
-                })
-            })
-            .collect::<Result<Vec<Rpc>, TransactorError>>()?;
+        let read_rpcs =
+            read_rpcs.iter().map(Rpc::new).collect::<Result<Vec<Rpc>, TransactorError>>()?;
+        let write_rpcs =
+            write_rpcs.iter().map(Rpc::new).collect::<Result<Vec<Rpc>, TransactorError>>()?;
         Ok(Self {
             read_rpcs: RoundRobin::new(read_rpcs),
             write_rpcs: RoundRobin::new(write_rpcs),
+            health_config,
         })
     }
 
-    pub async fn with_read_rpc<F, T, O>(&self, f: F, commitment: CommitmentConfig) -> O
+    pub async fn with_read_rpc<F, T, O, E>(
+        &self,
+        f: F,
+        commitment: CommitmentConfig,
+    ) -> Result<O, E>
     where
         F: FnOnce(RpcClient) -> T,
-        T: std::future::Future<Output = O>,
+        T: std::future::Future<Output = Result<O, E>>,
     {
-        let _now = now();
-        let (rpc, elapsed) = self
-            .read_rpcs
-            .pull_by_max(|x| _now - x.last_accessed.load(Ordering::Acquire))
-            .await
-            .expect("Empty round robin pool");
-        if elapsed < rpc.min_timeout.as_millis() as u64 {
-            tokio::time::sleep(Duration::from_millis(rpc.min_timeout.as_millis() as u64 - elapsed))
-                .await;
-        }
-        let mut rpc_version = rpc.cached_version.lock().await;
-        let client = RpcClient::new_with_timeout_and_commitment(
-            rpc.url.to_string(),
-            Duration::from_secs(3),
-            commitment,
-        );
-        Self::set_client_rpc_version(&mut rpc_version, &rpc.url, &client).await;
-        let res = f(client).await;
-        rpc.last_accessed.store(now(), Ordering::Release);
-        // rpc_version should be locked until `f` has completed
-        drop(rpc_version);
-        res
+        self.with_rpc(&self.read_rpcs, f, commitment).await
     }
 
-    pub async fn with_write_rpc<F, T, O>(&self, f: F, commitment: CommitmentConfig) -> O
+    pub async fn with_write_rpc<F, T, O, E>(
+        &self,
+        f: F,
+        commitment: CommitmentConfig,
+    ) -> Result<O, E>
     where
         F: FnOnce(RpcClient) -> T,
-        T: std::future::Future<Output = O>,
+        T: std::future::Future<Output = Result<O, E>>,
     {
-        let _now = now();
-        let (rpc, elapsed) = self
-            .write_rpcs
-            .pull_by_max(|x| _now - x.last_accessed.load(Ordering::Acquire))
-            .await
-            .expect("Empty round robin pool");
-        if elapsed < rpc.min_timeout.as_millis() as u64 {
-            tokio::time::sleep(Duration::from_millis(rpc.min_timeout.as_millis() as u64 - elapsed))
-                .await;
-        }
+        self.with_rpc(&self.write_rpcs, f, commitment).await
+    }
+
+    async fn with_rpc<F, T, O, E>(
+        &self,
+        rpcs: &RoundRobin<Rpc>,
+        f: F,
+        commitment: CommitmentConfig,
+    ) -> Result<O, E>
+    where
+        F: FnOnce(RpcClient) -> T,
+        T: std::future::Future<Output = Result<O, E>>,
+    {
+        let rpc = self.select(rpcs);
         let mut rpc_version = rpc.cached_version.lock().await;
-        let client = RpcClient::new_with_timeout_and_commitment(
-            rpc.url.to_string(),
-            Duration::from_secs(3),
-            commitment,
-        );
+        let client = rpc.client(commitment);
         Self::set_client_rpc_version(&mut rpc_version, &rpc.url, &client).await;
+        let start = Instant::now();
         let res = f(client).await;
         rpc.last_accessed.store(now(), Ordering::Release);
+        rpc.health().record(res.is_ok(), start.elapsed(), Instant::now(), &self.health_config);
         // rpc_version should be locked until `f` has completed
         drop(rpc_version);
         res
     }
 
+    /// Selects the endpoint expected to serve the request first. Ejected endpoints are skipped and
+    /// the ones lagging behind are only used if no other is available.
+    fn select<'a>(&self, rpcs: &'a RoundRobin<Rpc>) -> &'a Rpc {
+        let _now = now();
+        let instant = Instant::now();
+        let max_slot = rpcs.iter().filter_map(|rpc| rpc.health().slot()).max();
+        let rpc = rpcs
+            .iter()
+            .map(|rpc| {
+                let health = rpc.health();
+                let elapsed = _now.saturating_sub(rpc.last_accessed.load(Ordering::Acquire));
+                let wait = rpc.limiter.wait_time(instant);
+                let key = (
+                    !health.is_available(instant),
+                    health.is_lagging(max_slot, &self.health_config),
+                    wait.as_secs_f64() * 1000.0 + health.cost(),
+                    Reverse(elapsed),
+                );
+                (key, rpc)
+            })
+            .min_by(|(a, _), (b, _)| {
+                (a.0, a.1).cmp(&(b.0, b.1)).then(a.2.total_cmp(&b.2)).then(a.3.cmp(&b.3))
+            })
+            .map(|(_, rpc)| rpc)
+            .expect("Empty round robin pool");
+        rpc.health().acquire(instant);
+        rpc
+    }
+
+    /// Fetches the slot of every available endpoint, so the lagging ones are avoided and the
+    /// ejected ones are probed
+    pub async fn probe(&self) {
+        for rpc in self.read_rpcs.iter().chain(self.write_rpcs.iter()) {
+            if !rpc.health().is_available(Instant::now()) {
+                continue;
+            }
+            rpc.health().acquire(Instant::now());
+            let client = rpc.client(CommitmentConfig::confirmed());
+            let start = Instant::now();
+            let res = client.get_slot().await;
+            rpc.last_accessed.store(now(), Ordering::Release);
+            let mut health = rpc.health();
+            health.record(res.is_ok(), start.elapsed(), Instant::now(), &self.health_config);
+            match res {
+                Ok(slot) => health.record_slot(slot),
+                Err(err) => log::warn!("Failed to probe rpc: {}, error: {}", rpc.url, err),
+            }
+        }
+    }
+
+    /// Probes the endpoints periodically
+    pub async fn run_probe_loop(self) {
+        let period = Duration::from_secs(self.health_config.probe_period_secs);
+        loop {
+            self.probe().await;
+            tokio::time::sleep(period).await;
+        }
+    }
+
+    /// Subscribes to the signature over the websocket of any connected endpoint, `None` if there
+    /// is no such endpoint
+    pub async fn subscribe_signature(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Option<SignatureSubscription> {
+        let notifiers = self.read_rpcs.iter().chain(self.write_rpcs.iter());
+        for notifier in notifiers.filter_map(|rpc| rpc.pubsub.as_ref()) {
+            if !notifier.is_connected() {
+                continue;
+            }
+            if let Some(subscription) = notifier.subscribe(*signature, commitment).await {
+                return Some(subscription);
+            }
+        }
+        None
+    }
+
+    /// Keeps the websockets of the endpoints connected, the signatures are subscribed to while
+    /// it's running
+    pub async fn run_subscriptions(self) {
+        let rpcs = self.read_rpcs.iter().chain(self.write_rpcs.iter());
+        let runs = rpcs.filter_map(|rpc| Some(rpc.pubsub.as_ref()?.run(rpc.health.clone())));
+        futures::future::join_all(runs).await;
+    }
+
+    pub fn stats(&self) -> RpcPoolStats {
+        let now = Instant::now();
+        let stats = |rpcs: &RoundRobin<Rpc>| {
+            let stats =
+                |rpc: &Rpc| rpc.health().stats(rpc.url.to_string(), rpc.limiter.current_rate(now));
+            rpcs.iter().map(stats).collect()
+        };
+        RpcPoolStats {
+            read: stats(&self.read_rpcs),
+            write: stats(&self.write_rpcs),
+        }
+    }
+
     async fn set_client_rpc_version(
         rpc_version: &mut Option<semver::Version>,
         rpc_url: &Url,
@@ -145,11 +268,11 @@ impl RpcPool {
             };
             rpc_version.replace(version);
         }
-        let version = rpc_version.clone().expect("Expected to be set");
-        client
-            .set_node_version(version)
-            .await
-            .expect("Expected version to be updated well for http client");
+        if let Some(version) = rpc_version.clone() {
+            if client.set_node_version(version).await.is_err() {
+                error!("Failed to set version of rpc client: {}", rpc_url);
+            }
+        }
     }
 
     pub async fn with_read_rpc_loop<F, T, O, E>(&self, f: F, commitment: CommitmentConfig) -> O
@@ -216,3 +339,78 @@ impl RpcPool {
 fn now() -> u64 {
     UNIX_EPOCH.elapsed().expect("Get time failed").as_millis() as u64
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{rate_limit::test::HttpStub, CircuitState};
+
+    #[test]
+    fn test_select() {
+        let entries: Vec<_> = (0..3)
+            .map(|i| RpcEntry {
+                url: format!("http://rpc{}.test", i),
+                ratelimit: 1000,
+                burst: None,
+                ws_url: None,
+            })
+            .collect();
+        let pool = RpcPool::new(&entries, &entries[..1]).unwrap();
+        let config = RpcHealthConfig::default();
+        let rpcs: Vec<_> = pool.read_rpcs.iter().collect();
+        let now = Instant::now();
+        for _ in 0..config.failure_threshold {
+            rpcs[0].health().record(false, Duration::ZERO, now, &config);
+        }
+        rpcs[1].health().record_slot(1000);
+        rpcs[2].health().record_slot(1000 + config.max_slot_lag + 1);
+        rpcs[2].health().record(true, Duration::from_millis(500), now, &config);
+        // The slow endpoint is preferred over the ejected and the lagging ones
+        assert_eq!(pool.select(&pool.read_rpcs).url, rpcs[2].url);
+
+        rpcs[1].health().record_slot(1000 + config.max_slot_lag + 1);
+        rpcs[1].health().record(true, Duration::from_millis(100), now, &config);
+        assert_eq!(pool.select(&pool.read_rpcs).url, rpcs[1].url);
+
+        let stats = pool.stats();
+        assert_eq!(stats.write.len(), 1);
+        assert_eq!(stats.read[0].circuit, CircuitState::Open);
+        assert_eq!(stats.read[1].slot, Some(1051));
+        assert_eq!(stats.read[1].rate, 1000.0);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_version() {
+        let version = r#"{"jsonrpc":"2.0","result":{"solana-core":"1.16.0"},"id":0}"#;
+        let stub = HttpStub::start(vec![
+            (500, vec![], ""),
+            (200, vec![], version),
+            (200, vec![], r#"{"jsonrpc":"2.0","result":7,"id":1}"#),
+        ])
+        .await;
+        let entries = [RpcEntry {
+            url: stub.url.clone(),
+            ratelimit: 100,
+            burst: Some(3),
+            ws_url: None,
+        }];
+        let pool = RpcPool::new(&entries, &entries).unwrap();
+        let slot = pool
+            .with_read_rpc(|client| async move { client.get_slot().await }, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(slot, 7);
+        assert_eq!(stub.requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_invalid_ratelimit() {
+        let entries = [RpcEntry {
+            url: "http://rpc.test".to_owned(),
+            ratelimit: 0,
+            burst: None,
+            ws_url: None,
+        }];
+        assert!(matches!(RpcPool::new(&entries, &entries), Err(TransactorError::InvalidRpc(_))));
+    }
+}
@@ -0,0 +1,235 @@
+#![allow(clippy::result_large_err)]
+use solana_sdk::{
+    clock::Slot, instruction::InstructionError, pubkey::Pubkey, signature::Signature,
+    transaction::TransactionError,
+};
+use std::fmt::{self, Display, Formatter};
+use uuid::Uuid;
+
+use crate::TransactorError;
+
+/// Error codes of the `#[error_code]` enums of anchor programs start at this offset
+pub const ANCHOR_ERROR_CODE_OFFSET: u32 = 6000;
+
+/// Whether sending the instructions again may succeed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureClass {
+    /// The failure depends on the state of the cluster or the endpoints, e.g. an expired
+    /// blockhash, an account lock or an unavailable rpc
+    Retryable,
+    /// The instructions are rejected as they are, e.g. by a program error
+    Permanent,
+}
+
+/// Transaction that has been confirmed without an error, its finalization is awaited in the
+/// background
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LandedTransaction {
+    pub signature: Signature,
+    /// `None` if the status wasn't available after the confirmation
+    pub slot: Option<Slot>,
+}
+
+/// Error returned by a program invoked by an instruction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramError {
+    /// Index of the instruction in the message, including the compute budget ones
+    pub instruction: u8,
+    /// The program that returned the error, it's the innermost one if the error is propagated
+    /// through cross-program invocations
+    pub program_id: Pubkey,
+    pub code: u32,
+}
+
+impl ProgramError {
+    /// Returns the variant index of the error of an anchor program, `None` for the codes of the
+    /// anchor framework
+    pub fn anchor_error_index(&self) -> Option<u32> {
+        self.code.checked_sub(ANCHOR_ERROR_CODE_OFFSET)
+    }
+}
+
+impl Display for ProgramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "program {} failed instruction {} with error {} ({:#x})",
+            self.program_id, self.instruction, self.code, self.code
+        )
+    }
+}
+
+/// Transaction that landed and failed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionFailure {
+    pub signature: Signature,
+    pub slot: Option<Slot>,
+    pub error: TransactionError,
+    /// Decoded from a custom instruction error, `None` if the transaction logs are unavailable
+    pub program_error: Option<ProgramError>,
+}
+
+impl TransactionFailure {
+    pub fn new(
+        signature: Signature,
+        slot: Option<Slot>,
+        error: TransactionError,
+        logs: &[String],
+    ) -> Self {
+        Self {
+            program_error: program_error(&error, logs),
+            signature,
+            slot,
+            error,
+        }
+    }
+}
+
+impl Display for TransactionFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.program_error {
+            Some(program_error) => write!(f, "{}: {}", self.signature, program_error),
+            None => write!(f, "{}: {}", self.signature, self.error),
+        }
+    }
+}
+
+/// Result of sending a message bundle
+#[derive(Debug)]
+pub struct BundleResult {
+    pub id: Uuid,
+    pub result: Result<LandedTransaction, TransactorError>,
+}
+
+impl BundleResult {
+    pub fn is_success(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    pub fn failure_class(&self) -> Option<FailureClass> {
+        self.result.as_ref().err().map(TransactorError::classify)
+    }
+}
+
+/// Returns the landed transactions or the first failure
+pub fn all_landed(results: Vec<BundleResult>) -> Result<Vec<LandedTransaction>, TransactorError> {
+    results.into_iter().map(|result| result.result).collect()
+}
+
+/// Decodes the custom error of the failed instruction with the program that returned it. The
+/// program is taken from the transaction logs, the top-level program of the instruction only
+/// propagates the error of the program it invokes.
+pub fn program_error(error: &TransactionError, logs: &[String]) -> Option<ProgramError> {
+    let TransactionError::InstructionError(index, InstructionError::Custom(code)) = error else {
+        return None;
+    };
+    // The programs fail from the innermost invocation outwards
+    let program_id = logs.iter().find_map(|line| {
+        let (program_id, _) = line.strip_prefix("Program ")?.split_once(" failed: ")?;
+        program_id.parse().ok()
+    })?;
+    Some(ProgramError {
+        instruction: *index,
+        program_id,
+        code: *code,
+    })
+}
+
+pub fn classify_transaction_error(error: &TransactionError) -> FailureClass {
+    match error {
+        TransactionError::AccountInUse
+        | TransactionError::AlreadyProcessed
+        | TransactionError::BlockhashNotFound
+        | TransactionError::ClusterMaintenance
+        | TransactionError::InsufficientFundsForFee
+        | TransactionError::InsufficientFundsForRent { .. }
+        | TransactionError::WouldExceedMaxBlockCostLimit
+        | TransactionError::WouldExceedMaxAccountCostLimit
+        | TransactionError::WouldExceedAccountDataBlockLimit
+        | TransactionError::WouldExceedAccountDataTotalLimit
+        | TransactionError::WouldExceedMaxVoteCostLimit
+        | TransactionError::InstructionError(_, InstructionError::ComputationalBudgetExceeded) => {
+            FailureClass::Retryable
+        }
+        _ => FailureClass::Permanent,
+    }
+}
+
+impl TransactorError {
+    pub fn classify(&self) -> FailureClass {
+        match self {
+            TransactorError::SimulationFailed(error) => classify_transaction_error(error),
+            TransactorError::TransactionFailed(failure) => {
+                classify_transaction_error(&failure.error)
+            }
+            TransactorError::ClientError(_)
+            | TransactorError::NonceAdvanced(_)
+            | TransactorError::Expired(_) => FailureClass::Retryable,
+            // Re-sending can't fix the journal and could duplicate an unrecorded transaction
+            _ => FailureClass::Permanent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_program_error() {
+        let program_id = Pubkey::new_unique();
+        let invoked_id = Pubkey::new_unique();
+        let logs: Vec<_> = [
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_owned(),
+            "Program ComputeBudget111111111111111111111111111111 success".to_owned(),
+            format!("Program {program_id} invoke [1]"),
+            format!("Program {invoked_id} invoke [2]"),
+            "Program log: AnchorError occurred. Error Number: 6005. Error Message: failed: x."
+                .to_owned(),
+            format!("Program {invoked_id} failed: custom program error: 0x1775"),
+            format!("Program {program_id} failed: custom program error: 0x1775"),
+        ]
+        .into();
+
+        // The error of the invoked program is propagated by the top-level one
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(6005));
+        let failure = TransactionFailure::new(Signature::default(), Some(1), error, &logs);
+        let decoded = failure.program_error.clone().unwrap();
+        assert_eq!(decoded.instruction, 1);
+        assert_eq!(decoded.program_id, invoked_id);
+        assert_eq!(decoded.anchor_error_index(), Some(5));
+        let failure = TransactorError::TransactionFailed(failure);
+        assert_eq!(failure.classify(), FailureClass::Permanent);
+
+        let error = TransactionError::InstructionError(1, InstructionError::Custom(6005));
+        assert_eq!(program_error(&error, &logs[..3]), None);
+        let error = TransactionError::InstructionError(0, InstructionError::InvalidArgument);
+        assert_eq!(program_error(&error, &logs), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let retryable = [
+            TransactorError::Expired(Signature::default()),
+            TransactorError::SimulationFailed(TransactionError::AccountInUse),
+            TransactorError::SimulationFailed(TransactionError::InstructionError(
+                0,
+                InstructionError::ComputationalBudgetExceeded,
+            )),
+        ];
+        for error in retryable {
+            assert_eq!(error.classify(), FailureClass::Retryable, "{}", error);
+        }
+        let permanent = [
+            TransactorError::InstructionTooBig,
+            TransactorError::JournalIo(std::io::ErrorKind::Other.into()),
+            TransactorError::SimulationFailed(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(0),
+            )),
+        ];
+        for error in permanent {
+            assert_eq!(error.classify(), FailureClass::Permanent, "{}", error);
+        }
+    }
+}
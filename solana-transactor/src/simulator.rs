@@ -0,0 +1,144 @@
+use futures::future::BoxFuture;
+use solana_client::{
+    client_error::ClientError, rpc_config::RpcSimulateTransactionConfig,
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{
+    address_lookup_table_account::AddressLookupTableAccount,
+    commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0::Message, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+
+use crate::{
+    compute_budget::set_compute_unit_limit, config::SimulationConfig, ix_compiler::MAX_CU,
+    rpc_pool::RpcPool, TransactorError,
+};
+
+/// Simulates messages without signing them
+pub trait MessageSimulator: Send + Sync {
+    fn simulate<'a>(
+        &'a self,
+        message: &'a VersionedMessage,
+    ) -> BoxFuture<'a, Result<RpcSimulateTransactionResult, ClientError>>;
+}
+
+impl MessageSimulator for RpcPool {
+    fn simulate<'a>(
+        &'a self,
+        message: &'a VersionedMessage,
+    ) -> BoxFuture<'a, Result<RpcSimulateTransactionResult, ClientError>> {
+        let tx = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                message.header().num_required_signatures as usize
+            ],
+            message: message.clone(),
+        };
+        Box::pin(self.with_read_rpc(
+            |rpc| async move {
+                let config = RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                };
+                Ok(rpc.simulate_transaction_with_config(&tx, config).await?.value)
+            },
+            CommitmentConfig::confirmed(),
+        ))
+    }
+}
+
+/// Sizes the compute unit limits by the units consumed in the simulation plus a margin
+pub struct Simulator<S = RpcPool> {
+    source: S,
+    config: SimulationConfig,
+}
+
+impl<S: MessageSimulator> Simulator<S> {
+    pub fn new(source: S, config: SimulationConfig) -> Self {
+        Self { source, config }
+    }
+
+    /// Returns the compute units the message needs, the message is simulated with the maximal limit.
+    /// `None` is returned if the RPC doesn't report the consumed units.
+    pub async fn compute_units(
+        &self,
+        message: &VersionedMessage,
+    ) -> Result<Option<u32>, TransactorError> {
+        let mut message = message.clone();
+        set_compute_unit_limit(&mut message, MAX_CU);
+        let result = self.source.simulate(&message).await?;
+        if let Some(err) = result.err {
+            for line in result.logs.unwrap_or_default() {
+                log::debug!("Simulation log: {}", line);
+            }
+            return Err(TransactorError::SimulationFailed(err));
+        }
+        Ok(result.units_consumed.map(|units| self.with_margin(units)))
+    }
+
+    /// Returns the compute units the instruction needs on its own, so the instructions may be
+    /// packed by the measured units
+    pub async fn instruction_units(
+        &self,
+        payer: &Pubkey,
+        ix: &Instruction,
+        alt: &[AddressLookupTableAccount],
+    ) -> Result<Option<u32>, TransactorError> {
+        let ixs = [
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_CU),
+            ix.clone(),
+        ];
+        let message = Message::try_compile(payer, &ixs, alt, Hash::default())?;
+        self.compute_units(&VersionedMessage::V0(message)).await
+    }
+
+    fn with_margin(&self, units: u64) -> u32 {
+        let margin = (units.saturating_mul(self.config.margin_percent as u64) / 100)
+            .max(self.config.min_margin);
+        units.saturating_add(margin).min(MAX_CU as u64) as u32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use solana_sdk::instruction::AccountMeta;
+
+    use super::*;
+    use crate::test_support::simulator;
+
+    #[tokio::test]
+    async fn test_instruction_units() {
+        let payer = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new(payer, true)],
+        );
+        let simulator = simulator(false);
+        let units = simulator.instruction_units(&payer, &ix, &[]).await.unwrap();
+        // The instruction is simulated alone with the maximal limit, then the margin is added
+        assert_eq!(*simulator.source.limits.lock().unwrap(), [MAX_CU]);
+        assert_eq!(units, Some(12000));
+
+        assert!(matches!(
+            self::simulator(true).instruction_units(&payer, &ix, &[]).await,
+            Err(TransactorError::SimulationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_margin() {
+        let simulator = simulator(false);
+        assert_eq!(simulator.with_margin(0), 2000);
+        assert_eq!(simulator.with_margin(100_000), 110_000);
+        assert_eq!(simulator.with_margin(MAX_CU as u64), MAX_CU);
+    }
+}
@@ -0,0 +1,118 @@
+//! Mocks and factories shared by the unit tests
+
+use std::sync::{Arc, Mutex};
+
+use futures::future::BoxFuture;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_response::RpcSimulateTransactionResult,
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    message::{v0::Message, VersionedMessage},
+    pubkey::Pubkey,
+    transaction::TransactionError,
+};
+
+use crate::{
+    compute_budget::SET_COMPUTE_UNIT_LIMIT,
+    fee_oracle::{PriorityFeeOracle, PriorityFeeSource},
+    nonce_pool::NoncePool,
+    simulator::{MessageSimulator, Simulator},
+    NonceConfig, PriorityFeeConfig, SimulationConfig,
+};
+
+/// Returns the given fees and consumes 10000 units per instruction if the limit allows
+#[derive(Default)]
+pub(crate) struct MockRpc {
+    /// The fees returned, the request fails without them
+    pub fees: Option<Vec<u64>>,
+    /// The accounts of the last fee request
+    pub requested: Mutex<Vec<Pubkey>>,
+    /// The compute unit limits of the simulated messages
+    pub limits: Mutex<Vec<u32>>,
+    /// Fails every simulation
+    pub fail: bool,
+}
+
+impl PriorityFeeSource for MockRpc {
+    fn recent_prioritization_fees<'a>(
+        &'a self,
+        accounts: &'a [Pubkey],
+    ) -> BoxFuture<'a, Result<Vec<u64>, ClientError>> {
+        *self.requested.lock().unwrap() = accounts.to_vec();
+        let fees = self
+            .fees
+            .clone()
+            .ok_or_else(|| ClientError::from(ClientErrorKind::Custom("mock".to_owned())));
+        Box::pin(async move { fees })
+    }
+}
+
+impl MessageSimulator for MockRpc {
+    fn simulate<'a>(
+        &'a self,
+        message: &'a VersionedMessage,
+    ) -> BoxFuture<'a, Result<RpcSimulateTransactionResult, ClientError>> {
+        let limit = message
+            .instructions()
+            .iter()
+            .find(|ix| ix.data.first() == Some(&SET_COMPUTE_UNIT_LIMIT))
+            .map(|ix| u32::from_le_bytes(ix.data[1..].try_into().unwrap()))
+            .unwrap();
+        self.limits.lock().unwrap().push(limit);
+        let units = 10000 * (message.instructions().len() as u64 - 1);
+        let err = (self.fail || units > limit as u64).then_some(
+            TransactionError::InstructionError(0, InstructionError::ComputationalBudgetExceeded),
+        );
+        Box::pin(async move {
+            Ok(RpcSimulateTransactionResult {
+                err,
+                logs: None,
+                accounts: None,
+                units_consumed: Some(units),
+                return_data: None,
+            })
+        })
+    }
+}
+
+pub(crate) fn oracle(fees: Option<Vec<u64>>) -> PriorityFeeOracle<MockRpc> {
+    let config = PriorityFeeConfig {
+        percentile: 75,
+        floor: 1000,
+        cap: 100_000,
+        escalation_percent: 50,
+    };
+    let rpc = MockRpc {
+        fees,
+        ..Default::default()
+    };
+    PriorityFeeOracle::new(rpc, config)
+}
+
+pub(crate) fn simulator(fail: bool) -> Simulator<MockRpc> {
+    let config = SimulationConfig {
+        margin_percent: 10,
+        min_margin: 2000,
+    };
+    let rpc = MockRpc {
+        fail,
+        ..Default::default()
+    };
+    Simulator::new(rpc, config)
+}
+
+pub(crate) fn nonce_pool(accounts: u16) -> Arc<NoncePool> {
+    let config = NonceConfig {
+        authority: Pubkey::new_unique(),
+        accounts,
+    };
+    Arc::new(NoncePool::new(config))
+}
+
+/// Compiles the instructions into a v0 message without lookup tables
+pub(crate) fn message(payer: &Pubkey, ixs: &[Instruction]) -> VersionedMessage {
+    VersionedMessage::V0(Message::try_compile(payer, ixs, &[], Hash::default()).unwrap())
+}
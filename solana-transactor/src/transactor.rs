@@ -1,16 +1,24 @@
 #![allow(clippy::large_enum_variant)]
 #![allow(clippy::too_many_arguments)]
+#![allow(clippy::result_large_err)]
 
 use futures::StreamExt;
-use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_client::{
+    client_error::ClientError,
+    rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
 use solana_sdk::{
     address_lookup_table_account::AddressLookupTableAccount,
+    clock::Slot,
     commitment_config::CommitmentConfig,
+    compute_budget::ComputeBudgetInstruction,
     hash::Hash,
-    message::VersionedMessage,
+    message::{v0::Message, VersionedMessage},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
-    transaction::VersionedTransaction,
+    signer::Signer,
+    transaction::{TransactionError, VersionedTransaction},
 };
 use std::{
     collections::HashMap,
@@ -28,33 +36,67 @@ use tokio::{
 };
 use uuid::Uuid;
 
+/// Compute units of creating and initializing a nonce account
+const NONCE_CREATE_COMPUTE_UNITS: u32 = 5000;
+/// Attempts to get the status of a confirmed transaction
+const LANDED_STATUS_ATTEMPTS: usize = 5;
+/// Attempts to send a durable message, the confirmation is awaited for 5 s after every attempt
+const DURABLE_SEND_ATTEMPTS: usize = 24;
+/// Attempts a durable message is re-sent for before its price is escalated
+const DURABLE_ESCALATION_ATTEMPTS: usize = 3;
+/// Compute units of advancing a nonce with the compute budget instructions
+const NONCE_ADVANCE_COMPUTE_UNITS: u32 = 1000;
+
 use crate::{
-    ix_compiler::{InstructionBundle, IxCompiler},
+    compute_budget::{get_compute_unit_price, set_compute_unit_price},
+    config::TransactorOptions,
+    fee_oracle::{writable_accounts, PriorityFeeOracle},
+    ix_compiler::{lock_groups, InstructionBundle, IxCompiler},
+    journal::{BundleEvent, FileJournal, Journal, JournalEntry},
     log_with_ctx,
+    nonce_pool::{
+        advance_nonce_ix, durable_nonce, nonce_account_size, nonce_authority,
+        nonce_from_account_data, set_nonce, NonceLease, NoncePool,
+    },
+    pubsub::{wait_any, SignatureSubscription},
     rpc_pool::RpcPool,
+    send_result::{all_landed, BundleResult, LandedTransaction, TransactionFailure},
+    simulator::Simulator,
     TransactorError,
 };
 
 #[derive(Clone)]
-pub struct MessageBundle {
+pub struct MessageBundle<'a> {
     pub message: VersionedMessage,
-    pub signers: Arc<Vec<Keypair>>,
+    pub signers: Vec<&'a Keypair>,
     pub payer: Pubkey,
+    /// Signatures of a message signed in advance, e.g. by external signers
+    pub signatures: Option<Vec<Signature>>,
 }
 
-impl MessageBundle {
-    pub fn new(message: &VersionedMessage, signers: &[&Keypair], payer: Pubkey) -> Self {
+impl<'a> MessageBundle<'a> {
+    pub fn new(message: &VersionedMessage, signers: &[&'a Keypair], payer: Pubkey) -> Self {
         Self {
             message: message.to_owned(),
-            signers: Arc::new(
-                signers
-                    .iter()
-                    .map(|x| Keypair::from_bytes(&x.to_bytes()).expect("Always 64 bytes"))
-                    .collect(),
-            ),
+            signers: signers.to_vec(),
             payer,
+            signatures: None,
         }
     }
+
+    /// Takes the transaction signed in advance, its message must use a durable nonce since it can't
+    /// be signed again with another blockhash
+    pub fn signed(tx: VersionedTransaction, payer: Pubkey) -> Result<Self, TransactorError> {
+        if durable_nonce(&tx.message).is_none() {
+            return Err(TransactorError::NotDurable);
+        }
+        Ok(Self {
+            message: tx.message,
+            signers: Vec::new(),
+            payer,
+            signatures: Some(tx.signatures),
+        })
+    }
 }
 
 enum ChannelMessage {
@@ -62,10 +104,11 @@ enum ChannelMessage {
     Stop,
 }
 
+/// The confirmed transaction is re-sent as it is if it's not finalized, so no other version of
+/// the bundle can land
 struct FinalizationTask {
     log_ctx: Option<String>,
-    signature: Signature,
-    bundle: MessageBundle,
+    transaction: VersionedTransaction,
     id: Uuid,
     start: Instant,
 }
@@ -73,24 +116,243 @@ struct FinalizationTask {
 #[derive(Clone)]
 pub struct SolanaTransactor {
     rpc_pool: RpcPool,
+    fee_oracle: Option<Arc<PriorityFeeOracle>>,
+    simulator: Option<Arc<Simulator>>,
+    nonce_pool: Option<Arc<NoncePool>>,
+    journal: Option<Arc<dyn Journal>>,
+    group_by_locks: bool,
     finalize_channel: Arc<UnboundedSender<ChannelMessage>>,
     handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Probing and subscription tasks of the rpc pool
+    pool_handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl SolanaTransactor {
     pub async fn start(rpc_pool: RpcPool) -> Result<Self, TransactorError> {
+        Self::start_with_options(rpc_pool, TransactorOptions::default()).await
+    }
+
+    /// Starts the transactor estimating compute unit prices of the messages by the recent
+    /// prioritization fees, see [PriorityFeeOracle], and sizing their compute unit limits by
+    /// simulation, see [Simulator], sending them with durable nonces, see [NoncePool], and
+    /// recording them to the journal file, see [FileJournal], if configured
+    pub async fn start_with_options(
+        rpc_pool: RpcPool,
+        options: TransactorOptions,
+    ) -> Result<Self, TransactorError> {
+        let journal = match &options.journal {
+            Some(config) => Some(Arc::new(FileJournal::open(config)?) as Arc<dyn Journal>),
+            None => None,
+        };
+        Self::start_with_journal(rpc_pool, options, journal).await
+    }
+
+    /// Starts the transactor recording the bundles to the given journal instead of the configured
+    /// one. The unfinished bundles of the journal are resumed.
+    pub async fn start_with_journal(
+        rpc_pool: RpcPool,
+        options: TransactorOptions,
+        journal: Option<Arc<dyn Journal>>,
+    ) -> Result<Self, TransactorError> {
         let (sender, receiver) = unbounded_channel();
+        let fee_oracle = options
+            .priority_fee
+            .map(|config| Arc::new(PriorityFeeOracle::new(rpc_pool.clone(), config)));
+        let simulator =
+            options.simulation.map(|config| Arc::new(Simulator::new(rpc_pool.clone(), config)));
+        let nonce_pool = options.nonce.map(|config| Arc::new(NoncePool::new(config)));
         let s = Self {
             rpc_pool,
+            fee_oracle,
+            simulator,
+            nonce_pool,
+            journal,
+            group_by_locks: options.group_by_locks,
             finalize_channel: Arc::new(sender),
             handle: Default::default(),
+            pool_handles: Default::default(),
         };
         let s2 = s.clone();
         let handle = tokio::task::spawn(async move { s2.run_finalizer_loop(receiver).await });
         *s.handle.lock().await = Some(handle);
+        *s.pool_handles.lock().await = vec![
+            tokio::task::spawn(s.rpc_pool.clone().run_probe_loop()),
+            tokio::task::spawn(s.rpc_pool.clone().run_subscriptions()),
+        ];
+        s.resume_unfinished()?;
         Ok(s)
     }
 
+    /// Returns the journal entries of the bundle, empty without the journal
+    pub fn bundle_history(&self, id: Uuid) -> Result<Vec<JournalEntry>, TransactorError> {
+        match &self.journal {
+            Some(journal) => journal.history(id),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn record(&self, id: Uuid, event: BundleEvent) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(JournalEntry::new(id, event)) {
+                log::error!("Failed to record bundle {}: {}", id, e);
+            }
+        }
+    }
+
+    fn record_sent<T: Display>(
+        &self,
+        id: Uuid,
+        tx: &VersionedTransaction,
+        bundle: &MessageBundle<'_>,
+        log_ctx: &Option<T>,
+        finalize: bool,
+    ) {
+        let event = BundleEvent::Sent {
+            transaction: tx.clone(),
+            payer: bundle.payer,
+            log_ctx: log_ctx.as_ref().map(|c| c.to_string()),
+            finalize,
+        };
+        self.record(id, event);
+    }
+
+    /// Resumes the bundles left unfinished by the previous run. Their transactions can't be signed
+    /// again, so a bundle fails once its last transaction can't land anymore. A durable bundle
+    /// holds its nonce account again until it's confirmed or fails.
+    fn resume_unfinished(&self) -> Result<(), TransactorError> {
+        let Some(journal) = &self.journal else {
+            return Ok(());
+        };
+        for history in journal.unfinished()? {
+            let Some(id) = history.first().map(JournalEntry::id) else {
+                continue;
+            };
+            log::info!("Resuming bundle {}", id);
+            let lease = self.resume_lease(id, &history);
+            tokio::task::spawn(self.clone().resume_bundle(id, history, lease));
+        }
+        Ok(())
+    }
+
+    /// Leases the nonce account of the last transaction of the bundle if it's one of the pool
+    fn resume_lease(&self, id: Uuid, history: &[JournalEntry]) -> Option<NonceLease> {
+        let nonce_pool = self.nonce_pool.as_ref()?;
+        let nonce_account = history.iter().rev().find_map(|entry| match &entry.event {
+            BundleEvent::Sent { transaction, .. } => durable_nonce(&transaction.message),
+            _ => None,
+        })?;
+        if !nonce_pool.accounts().contains(&nonce_account) {
+            return None;
+        }
+        let lease = nonce_pool.try_lease(&nonce_account);
+        if lease.is_none() {
+            log::warn!("Nonce account {} of bundle {} is already leased", nonce_account, id);
+        }
+        lease
+    }
+
+    /// The nonce lease is held until the bundle is confirmed or fails
+    async fn resume_bundle(self, id: Uuid, history: Vec<JournalEntry>, _lease: Option<NonceLease>) {
+        let mut signatures = Vec::new();
+        let mut last_sent = None;
+        let mut confirmed = None;
+        for entry in &history {
+            match &entry.event {
+                BundleEvent::Sent {
+                    transaction,
+                    log_ctx,
+                    finalize,
+                    ..
+                } => {
+                    signatures.extend(transaction.signatures.first().copied());
+                    last_sent = Some((transaction, log_ctx, *finalize));
+                }
+                BundleEvent::Confirmed(signature) => confirmed = Some(*signature),
+                BundleEvent::Finalized(_) | BundleEvent::Failed(_) => {}
+            }
+        }
+        let Some((tx, log_ctx, finalize)) = last_sent else {
+            return;
+        };
+        let signature = match confirmed {
+            Some(signature) => signature,
+            None => match self.resume_confirmation(log_ctx, tx, &signatures).await {
+                Ok(signature) => {
+                    log_with_ctx!(debug, log_ctx, "Resumed bundle {} confirmed {}", id, signature);
+                    self.record(id, BundleEvent::Confirmed(signature));
+                    signature
+                }
+                Err(e) => {
+                    log_with_ctx!(warn, log_ctx, "Failed to resume bundle {}: {}", id, e);
+                    self.record(id, BundleEvent::Failed(e.to_string()));
+                    return;
+                }
+            },
+        };
+        if finalize {
+            let transaction = history
+                .iter()
+                .find_map(|entry| match &entry.event {
+                    BundleEvent::Sent { transaction, .. }
+                        if transaction.signatures.first() == Some(&signature) =>
+                    {
+                        Some(transaction.clone())
+                    }
+                    _ => None,
+                })
+                .unwrap_or_else(|| tx.clone());
+            self.finalize_channel
+                .send(ChannelMessage::Task(FinalizationTask {
+                    log_ctx: log_ctx.clone(),
+                    transaction,
+                    id,
+                    start: Instant::now(),
+                }))
+                .expect("Channel error");
+        }
+    }
+
+    /// Re-sends the last signed transaction of the bundle until any of its transactions is
+    /// confirmed or the last one can't land anymore
+    async fn resume_confirmation(
+        &self,
+        log_ctx: &Option<String>,
+        tx: &VersionedTransaction,
+        signatures: &[Signature],
+    ) -> Result<Signature, TransactorError> {
+        loop {
+            // Checked before the statuses, so a transaction landing meanwhile isn't missed
+            let can_land = self.can_land(&tx.message).await?;
+            for signature in signatures {
+                if self.check_tx_status(signature, CommitmentConfig::confirmed()).await {
+                    return Ok(*signature);
+                }
+            }
+            if !can_land {
+                return Err(TransactorError::Expired(tx.signatures[0]));
+            }
+            self.send_transaction(log_ctx, tx).await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Returns false once the blockhash of the message has expired or its nonce has been advanced
+    async fn can_land(&self, message: &VersionedMessage) -> Result<bool, TransactorError> {
+        let blockhash = message.recent_blockhash();
+        match durable_nonce(message) {
+            Some(nonce_account) => Ok(self.get_nonce(&nonce_account).await? == *blockhash),
+            None => Ok(self
+                .rpc_pool
+                .with_read_rpc_loop(
+                    |rpc| async move {
+                        rpc.is_blockhash_valid(blockhash, CommitmentConfig::confirmed()).await
+                    },
+                    CommitmentConfig::confirmed(),
+                )
+                .await),
+        }
+    }
+
     pub fn rpc_pool(&self) -> &RpcPool {
         &self.rpc_pool
     }
@@ -115,80 +377,450 @@ impl SolanaTransactor {
     }
 
     async fn check_tx_status(&self, signature: &Signature, commitment: CommitmentConfig) -> bool {
+        self.get_tx_status(signature, commitment).await.is_some()
+    }
+
+    /// Returns the slot and the error of the transaction if it has landed at the commitment
+    async fn get_tx_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Option<(Slot, Option<TransactionError>)> {
         loop {
             match self
                 .rpc_pool
                 .with_read_rpc(
                     |rpc| async move {
-                        rpc.get_signature_status_with_commitment(signature, commitment)
-                            .await
-                            .map_err(|e| (e, rpc.url()))
+                        rpc.get_signature_statuses(&[*signature]).await.map_err(|e| (e, rpc.url()))
                     },
                     commitment,
                 )
                 .await
             {
-                Ok(Some(_)) => {
-                    return true;
+                Ok(response) => {
+                    return response
+                        .value
+                        .into_iter()
+                        .flatten()
+                        .find(|status| status.satisfies_commitment(commitment))
+                        .map(|status| (status.slot, status.err));
                 }
                 Err((e, url)) => {
                     log::warn!("Failed to check tx status: {} ({})", e, url);
                 }
+            }
+        }
+    }
+
+    /// Returns the logs of the landed transaction, `None` if they're unavailable
+    async fn get_tx_logs(&self, signature: &Signature) -> Option<Vec<String>> {
+        let tx = self
+            .rpc_pool
+            .with_read_rpc(
+                |rpc| async move {
+                    let config = RpcTransactionConfig {
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                        ..Default::default()
+                    };
+                    rpc.get_transaction_with_config(signature, config).await
+                },
+                CommitmentConfig::confirmed(),
+            )
+            .await;
+        match tx {
+            Ok(tx) => tx.transaction.meta?.log_messages.into(),
+            Err(e) => {
+                log::warn!("Failed to get tx logs: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Returns the confirmed transaction with its slot, the one failed on-chain as an error
+    async fn landed(&self, signature: Signature) -> Result<LandedTransaction, TransactorError> {
+        let mut status = None;
+        // The status may lag behind on the read rpcs
+        for _ in 0..LANDED_STATUS_ATTEMPTS {
+            status = self.get_tx_status(&signature, CommitmentConfig::confirmed()).await;
+            if status.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        match status {
+            Some((slot, Some(error))) => {
+                let logs = self.get_tx_logs(&signature).await.unwrap_or_default();
+                Err(TransactorError::TransactionFailed(TransactionFailure::new(
+                    signature,
+                    Some(slot),
+                    error,
+                    &logs,
+                )))
+            }
+            status => Ok(LandedTransaction {
+                signature,
+                slot: status.map(|(slot, _)| slot),
+            }),
+        }
+    }
+
+    /// Waits for the signature to land at the commitment. The subscription notifies as soon as it
+    /// lands, the status is polled after the delay without it. A subscribed signature is polled
+    /// once, in case it landed before the subscription.
+    async fn await_signature(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+        subscription: &mut Option<SignatureSubscription>,
+        polled: &mut bool,
+        delay: Duration,
+    ) -> bool {
+        if let Some(subscription) = subscription {
+            if subscription.wait(delay).await {
+                return true;
+            }
+            if !subscription.is_closed() && *polled {
+                return false;
+            }
+        } else {
+            tokio::time::sleep(delay).await;
+        }
+        *polled = true;
+        self.check_tx_status(signature, commitment).await
+    }
+
+    /// Sends the transaction to several write RPCs, returns once it has been accepted
+    async fn send_transaction<T: Display>(
+        &self,
+        log_ctx: &Option<T>,
+        tx: &VersionedTransaction,
+    ) -> Signature {
+        let mut i = 0;
+        loop {
+            let tx = tx.clone();
+            match self
+                .rpc_pool
+                .with_write_rpc(
+                    |rpc| async move {
+                        rpc.send_transaction_with_config(
+                            &tx,
+                            RpcSendTransactionConfig {
+                                skip_preflight: true,
+                                ..Default::default()
+                            },
+                        )
+                        .await
+                        .map_err(|e| (e, rpc.url()))
+                    },
+                    CommitmentConfig::confirmed(),
+                )
+                .await
+            {
+                Ok(s) if (i >= self.rpc_pool.num_write_rpcs() || i >= 2) => {
+                    break s;
+                }
                 Ok(_) => {
-                    return false;
+                    i += 1;
+                }
+                Err((e, url)) => {
+                    log_with_ctx!(warn, log_ctx, "Failed to send tx: {} ({})", e, url);
                 }
             }
         }
     }
 
+    /// Sends the durable message until it's confirmed, for `DURABLE_SEND_ATTEMPTS` attempts at
+    /// most. The message is signed once per nonce and re-sent as it is. To raise the price of the
+    /// message signed here every `DURABLE_ESCALATION_ATTEMPTS` attempts, the nonce is advanced
+    /// first, so the previous version can't land anymore, and the message signed with the next
+    /// nonce is recorded as a new attempt. Once the attempts are exhausted the message is
+    /// `Expired`, its last version still may land until the nonce account is leased again, since
+    /// the next message using it advances the nonce first.
+    async fn send_durable<T: Display>(
+        &self,
+        log_ctx: Option<T>,
+        bundle: &MessageBundle<'_>,
+        id: Uuid,
+        nonce_account: Pubkey,
+        finalize: bool,
+    ) -> Result<VersionedTransaction, TransactorError> {
+        let mut nonce = *bundle.message.recent_blockhash();
+        let mut price = match (&self.fee_oracle, &bundle.signatures) {
+            (Some(oracle), None) => {
+                let min_price = get_compute_unit_price(&bundle.message).unwrap_or_default();
+                Some(oracle.estimate(&writable_accounts(&bundle.message), min_price).await)
+            }
+            _ => None,
+        };
+        let mut tx = self.sign_durable(&log_ctx, bundle, id, nonce, price)?;
+        self.record_sent(id, &tx, bundle, &log_ctx, finalize);
+        let mut sent = vec![tx.clone()];
+        let start = Instant::now();
+        let mut subscription: Option<SignatureSubscription> = None;
+        let mut polled = false;
+        for attempt in 1..=DURABLE_SEND_ATTEMPTS {
+            let signature = self.send_transaction(&log_ctx, &tx).await;
+            let subscribed = match &subscription {
+                Some(subscription) => !subscription.is_closed(),
+                None => false,
+            };
+            if !subscribed {
+                subscription = self
+                    .rpc_pool
+                    .subscribe_signature(&signature, CommitmentConfig::confirmed())
+                    .await;
+                polled = false;
+            }
+            log_with_ctx!(
+                debug,
+                log_ctx,
+                "Sent durable bundle {} with sig {}, nonce account {}",
+                id,
+                signature,
+                nonce_account
+            );
+            let confirmed = if self
+                .await_signature(
+                    &signature,
+                    CommitmentConfig::confirmed(),
+                    &mut subscription,
+                    &mut polled,
+                    Duration::from_secs(5),
+                )
+                .await
+            {
+                Some(tx.clone())
+            } else if self.get_nonce(&nonce_account).await? != nonce {
+                // The nonce is advanced by one of the versions or by another transaction
+                match self.landed_transaction(&sent).await {
+                    Some(tx) => Some(tx),
+                    None => return Err(TransactorError::NonceAdvanced(signature)),
+                }
+            } else {
+                None
+            };
+            if let Some(tx) = confirmed {
+                log_with_ctx!(
+                    debug,
+                    log_ctx,
+                    "Bundle {} confirmed {} after {} s, finalizing...",
+                    id,
+                    tx.signatures[0],
+                    start.elapsed().as_secs()
+                );
+                return Ok(tx);
+            }
+            if let (Some(oracle), Some(price)) = (&self.fee_oracle, price.as_mut()) {
+                if attempt % DURABLE_ESCALATION_ATTEMPTS == 0 {
+                    let escalated = oracle.escalate(*price);
+                    let Some(next_nonce) = self
+                        .advance_nonce(&log_ctx, bundle, nonce_account, nonce, escalated)
+                        .await?
+                    else {
+                        continue;
+                    };
+                    // The nonce is advanced by the advancing transaction or by a landed version
+                    if let Some(tx) = self.landed_transaction(&sent).await {
+                        return Ok(tx);
+                    }
+                    *price = escalated;
+                    nonce = next_nonce;
+                    tx = self.sign_durable(&log_ctx, bundle, id, nonce, Some(*price))?;
+                    self.record_sent(id, &tx, bundle, &log_ctx, finalize);
+                    sent.push(tx.clone());
+                    subscription = None;
+                }
+            }
+        }
+        // Checked once more, a version may have landed meanwhile
+        match self.landed_transaction(&sent).await {
+            Some(tx) => Ok(tx),
+            None => Err(TransactorError::Expired(tx.signatures[0])),
+        }
+    }
+
+    /// Signs the durable message with the nonce and the price if it's not signed in advance
+    fn sign_durable<T: Display>(
+        &self,
+        log_ctx: &Option<T>,
+        bundle: &MessageBundle<'_>,
+        id: Uuid,
+        nonce: Hash,
+        price: Option<u64>,
+    ) -> Result<VersionedTransaction, TransactorError> {
+        if let Some(signatures) = &bundle.signatures {
+            return Ok(VersionedTransaction {
+                signatures: signatures.clone(),
+                message: bundle.message.clone(),
+            });
+        }
+        let mut msg = bundle.message.clone();
+        msg.set_recent_blockhash(nonce);
+        if let Some(price) = price {
+            if set_compute_unit_price(&mut msg, price) {
+                log_with_ctx!(debug, log_ctx, "Bundle {} compute unit price: {}", id, price);
+            }
+        }
+        VersionedTransaction::try_new(msg, &bundle.signers).map_err(TransactorError::FailedToSign)
+    }
+
+    /// Advances the nonce with a transaction of the authority signing the bundle, paying the
+    /// price. Returns the next nonce, `None` if the authority isn't a signer or the nonce isn't
+    /// advanced within `DURABLE_ESCALATION_ATTEMPTS` attempts.
+    async fn advance_nonce<T: Display>(
+        &self,
+        log_ctx: &Option<T>,
+        bundle: &MessageBundle<'_>,
+        nonce_account: Pubkey,
+        nonce: Hash,
+        price: u64,
+    ) -> Result<Option<Hash>, TransactorError> {
+        let authority = nonce_authority(&bundle.message);
+        let Some(authority) = bundle.signers.iter().find(|s| Some(s.pubkey()) == authority) else {
+            return Ok(None);
+        };
+        let ixs = [
+            advance_nonce_ix(&nonce_account, &authority.pubkey()),
+            ComputeBudgetInstruction::set_compute_unit_limit(NONCE_ADVANCE_COMPUTE_UNITS),
+            ComputeBudgetInstruction::set_compute_unit_price(price),
+        ];
+        let msg = Message::try_compile(&authority.pubkey(), &ixs, &[], nonce)?;
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(msg), &[*authority])
+            .map_err(TransactorError::FailedToSign)?;
+        for _ in 0..DURABLE_ESCALATION_ATTEMPTS {
+            let signature = self.send_transaction(log_ctx, &tx).await;
+            log_with_ctx!(debug, log_ctx, "Sent nonce {} advance {}", nonce_account, signature);
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            let next_nonce = self.get_nonce(&nonce_account).await?;
+            if next_nonce != nonce {
+                return Ok(Some(next_nonce));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the first of the transactions confirmed
+    async fn landed_transaction(
+        &self,
+        transactions: &[VersionedTransaction],
+    ) -> Option<VersionedTransaction> {
+        for tx in transactions {
+            if self.check_tx_status(&tx.signatures[0], CommitmentConfig::confirmed()).await {
+                return Some(tx.clone());
+            }
+        }
+        None
+    }
+
+    /// Returns the durable nonce stored in the nonce account
+    pub async fn get_nonce(&self, nonce_account: &Pubkey) -> Result<Hash, TransactorError> {
+        let account = self
+            .rpc_pool
+            .with_read_rpc_loop(
+                |rpc| async move {
+                    rpc.get_account_with_commitment(nonce_account, CommitmentConfig::confirmed())
+                        .await
+                },
+                CommitmentConfig::confirmed(),
+            )
+            .await;
+        account
+            .value
+            .and_then(|account| nonce_from_account_data(&account.data))
+            .ok_or(TransactorError::InvalidNonceAccount(*nonce_account))
+    }
+
+    /// Creates the missing nonce accounts of the pool, the payer has to be the nonce authority
+    pub async fn create_nonce_accounts(&self, payer: &Keypair) -> Result<(), TransactorError> {
+        let Some(nonce_pool) = &self.nonce_pool else {
+            return Ok(());
+        };
+        if nonce_pool.authority() != &payer.pubkey() {
+            return Err(TransactorError::NonceAuthority(*nonce_pool.authority()));
+        }
+        let (accounts, lamports) = self
+            .rpc_pool
+            .with_read_rpc_loop(
+                |rpc| async move {
+                    let mut accounts = Vec::new();
+                    for chunk in nonce_pool.accounts().chunks(MAX_MULTIPLE_ACCOUNTS) {
+                        accounts.extend(rpc.get_multiple_accounts(chunk).await?);
+                    }
+                    let lamports =
+                        rpc.get_minimum_balance_for_rent_exemption(nonce_account_size()).await?;
+                    Ok::<_, ClientError>((accounts, lamports))
+                },
+                CommitmentConfig::confirmed(),
+            )
+            .await;
+        let instructions: Vec<_> = accounts
+            .iter()
+            .enumerate()
+            .filter(|(_, account)| account.is_none())
+            .flat_map(|(i, _)| nonce_pool.create_instructions(&payer.pubkey(), lamports, i as u16))
+            .map(|ix| InstructionBundle::new(ix, NONCE_CREATE_COMPUTE_UNITS))
+            .collect();
+        if instructions.is_empty() {
+            return Ok(());
+        }
+        log::info!("Creating {} nonce accounts", instructions.len() / 2);
+        let mut ix_compiler = IxCompiler::new(payer.pubkey(), None);
+        let mut messages = Vec::new();
+        for ix in instructions {
+            messages.extend(ix_compiler.compile::<&str>(
+                None,
+                ix.instruction,
+                &[],
+                ix.compute_units,
+            )?);
+        }
+        messages.extend(ix_compiler.flush()?);
+        let bundles: Vec<_> =
+            messages.iter().map(|msg| MessageBundle::new(msg, &[payer], payer.pubkey())).collect();
+        all_landed(self.send::<&str>(None, &bundles, false).await)?;
+        Ok(())
+    }
+
+    /// Returns the confirmed transaction
     async fn send_with_level_confirmed<T: Display>(
         self,
         log_ctx: Option<T>,
-        bundle: &MessageBundle,
+        bundle: &MessageBundle<'_>,
         id: Uuid,
-    ) -> Result<Signature, TransactorError> {
+        finalize: bool,
+    ) -> Result<VersionedTransaction, TransactorError> {
+        if let Some(nonce_account) = durable_nonce(&bundle.message) {
+            return self.send_durable(log_ctx, bundle, id, nonce_account, finalize).await;
+        }
         let mut current_blockhash = self.get_blockhash().await;
         let mut queue = HashMap::new();
+        let mut sent = HashMap::new();
         let start = Instant::now();
+        let mut price = match &self.fee_oracle {
+            Some(oracle) => {
+                let min_price = get_compute_unit_price(&bundle.message).unwrap_or_default();
+                Some(oracle.estimate(&writable_accounts(&bundle.message), min_price).await)
+            }
+            None => None,
+        };
         loop {
-            let signers_ref: Vec<_> = bundle.signers.iter().collect();
             let mut msg = bundle.message.clone();
             msg.set_recent_blockhash(current_blockhash);
-            let tx = VersionedTransaction::try_new(msg, &signers_ref)
-                .map_err(TransactorError::FailedToSign)?;
-            let mut i = 0;
-            let signature = loop {
-                let tx = tx.clone();
-                match self
-                    .rpc_pool
-                    .with_write_rpc(
-                        |rpc| async move {
-                            rpc.send_transaction_with_config(
-                                &tx,
-                                RpcSendTransactionConfig {
-                                    skip_preflight: true,
-                                    ..Default::default()
-                                },
-                            )
-                            .await
-                            .map_err(|e| (e, rpc.url()))
-                        },
-                        CommitmentConfig::confirmed(),
-                    )
-                    .await
-                {
-                    Ok(s) if (i >= self.rpc_pool.num_write_rpcs() || i >= 2) => {
-                        break s;
-                    }
-                    Ok(_) => {
-                        i += 1;
-                    }
-                    Err((e, url)) => {
-                        log_with_ctx!(warn, log_ctx, "Failed to send tx: {} ({})", e, url);
-                    }
+            if let Some(price) = price {
+                if set_compute_unit_price(&mut msg, price) {
+                    log_with_ctx!(debug, log_ctx, "Bundle {} compute unit price: {}", id, price);
                 }
-            };
-            queue.insert(signature, Instant::now());
+            }
+            let tx = VersionedTransaction::try_new(msg, &bundle.signers)
+                .map_err(TransactorError::FailedToSign)?;
+            // Recorded before sending, so a crash can't lose a transaction that may land
+            self.record_sent(id, &tx, bundle, &log_ctx, finalize);
+            let signature = self.send_transaction(&log_ctx, &tx).await;
+            sent.insert(signature, tx);
+            let subscription =
+                self.rpc_pool.subscribe_signature(&signature, CommitmentConfig::confirmed()).await;
+            queue.insert(signature, (Instant::now(), subscription, false));
             log_with_ctx!(
                 debug,
                 log_ctx,
@@ -197,29 +829,35 @@ impl SolanaTransactor {
                 signature,
                 queue.len()
             );
-            tokio::time::sleep(Duration::from_secs(5)).await;
-            for signature in queue.clone().keys().copied() {
-                if !queue.contains_key(&signature) {
-                    continue;
-                }
-                if queue[&signature].elapsed() > Duration::from_secs(30) {
-                    queue.remove(&signature);
+            let confirmed = |signature: Signature| {
+                log_with_ctx!(
+                    debug,
+                    log_ctx,
+                    "Bundle {} confirmed {} after {} s, finalizing...",
+                    id,
+                    signature,
+                    start.elapsed().as_secs()
+                );
+                Ok(sent[&signature].clone())
+            };
+            if let Some(signature) = Self::await_notification(&mut queue).await {
+                return confirmed(signature);
+            }
+            queue.retain(|_, (sent, _, _)| sent.elapsed() <= Duration::from_secs(30));
+            for (signature, (_, subscription, polled)) in queue.iter_mut() {
+                // A subscribed signature is polled once, in case it landed before the subscription
+                if subscription.as_ref().is_some_and(|s| !s.is_closed()) && *polled {
                     continue;
                 }
-                if self.check_tx_status(&signature, CommitmentConfig::confirmed()).await {
-                    log_with_ctx!(
-                        debug,
-                        log_ctx,
-                        "Bundle {} confirmed {} after {} s, finalizing...",
-                        id,
-                        signature,
-                        start.elapsed().as_secs()
-                    );
-                    return Ok(signature);
+                *polled = true;
+                if self.check_tx_status(signature, CommitmentConfig::confirmed()).await {
+                    return confirmed(*signature);
                 }
                 tokio::time::sleep(Duration::from_millis(700)).await;
             }
-            tokio::time::sleep(Duration::from_secs(5)).await;
+            if let Some(signature) = Self::await_notification(&mut queue).await {
+                return confirmed(signature);
+            }
             loop {
                 let new_blockhash = self.get_blockhash().await;
                 if new_blockhash != current_blockhash {
@@ -229,54 +867,74 @@ impl SolanaTransactor {
                     tokio::time::sleep(Duration::from_millis(1100)).await;
                 }
             }
+            if let (Some(oracle), Some(price)) = (&self.fee_oracle, price.as_mut()) {
+                *price = oracle.escalate(*price);
+            }
         }
     }
 
+    /// Waits 5 s for a notification of any of the subscribed signatures
+    async fn await_notification(
+        queue: &mut HashMap<Signature, (Instant, Option<SignatureSubscription>, bool)>,
+    ) -> Option<Signature> {
+        let subscriptions = queue.iter_mut().filter_map(|(signature, (_, subscription, _))| {
+            Some((signature, subscription.as_mut()?))
+        });
+        wait_any(subscriptions, Duration::from_secs(5)).await
+    }
+
     async fn finalize(
         self,
         log_ctx: Option<&str>,
-        signature: &Signature,
-        bundle: MessageBundle,
+        tx: &VersionedTransaction,
         id: Uuid,
         start: Instant,
     ) -> Result<(), TransactorError> {
+        let signature = &tx.signatures[0];
+        let mut subscription =
+            self.rpc_pool.subscribe_signature(signature, CommitmentConfig::finalized()).await;
+        let mut polled = false;
         for _ in 0..20 {
-            tokio::time::sleep(Duration::from_secs(5)).await;
-            match self
-                .rpc_pool
-                .with_read_rpc(
-                    |rpc| async move {
-                        rpc.get_signature_status_with_commitment(
-                            signature,
-                            CommitmentConfig::finalized(),
-                        )
-                        .await
-                        .map_err(|e| (e, rpc.url()))
-                    },
+            if self
+                .await_signature(
+                    signature,
                     CommitmentConfig::finalized(),
+                    &mut subscription,
+                    &mut polled,
+                    Duration::from_secs(5),
                 )
                 .await
             {
-                Ok(Some(_)) => {
-                    log_with_ctx!(
-                        debug,
-                        log_ctx,
-                        "Bundle {} finalized {} after {} s",
-                        id,
-                        signature,
-                        start.elapsed().as_secs()
-                    );
-                    return Ok(());
-                }
-                Err((e, url)) => {
-                    log::warn!("Failed to check tx status: {} ({})", e, url);
-                }
-                Ok(None) => {}
+                self.record(id, BundleEvent::Finalized(*signature));
+                log_with_ctx!(
+                    debug,
+                    log_ctx,
+                    "Bundle {} finalized {} after {} s",
+                    id,
+                    signature,
+                    start.elapsed().as_secs()
+                );
+                return Ok(());
             }
         }
         log_with_ctx!(warn, log_ctx, "Failed to finalize bundle {} tx {}", id, signature);
-        let c = self.finalize_channel.clone();
-        self.send_bundle(log_ctx, bundle, id, start, true, c).await?;
+        let log_ctx = log_ctx.map(str::to_owned);
+        // The transaction fails once its blockhash has expired or its nonce has been advanced
+        match self.resume_confirmation(&log_ctx, tx, &[*signature]).await {
+            Ok(_) => self
+                .finalize_channel
+                .send(ChannelMessage::Task(FinalizationTask {
+                    log_ctx,
+                    transaction: tx.clone(),
+                    id,
+                    start,
+                }))
+                .expect("Channel error"),
+            Err(e) => {
+                log_with_ctx!(error, log_ctx, "Failed to resend bundle {}: {}", id, e);
+                self.record(id, BundleEvent::Failed(e.to_string()));
+            }
+        }
         Ok(())
     }
 
@@ -285,13 +943,7 @@ impl SolanaTransactor {
             match msg {
                 ChannelMessage::Task(task) => {
                     self.clone()
-                        .finalize(
-                            task.log_ctx.as_deref(),
-                            &task.signature,
-                            task.bundle,
-                            task.id,
-                            task.start,
-                        )
+                        .finalize(task.log_ctx.as_deref(), &task.transaction, task.id, task.start)
                         .await
                         .expect("Finalize should not fail");
                 }
@@ -303,50 +955,83 @@ impl SolanaTransactor {
     async fn send_bundle<T: Display + Clone>(
         self,
         log_ctx: Option<T>,
-        bundle: MessageBundle,
+        bundle: &MessageBundle<'_>,
         id: Uuid,
         start: Instant,
         finalize: bool,
         finalize_channel: Arc<UnboundedSender<ChannelMessage>>,
-    ) -> Result<Signature, TransactorError> {
-        let signature = self.send_with_level_confirmed(log_ctx.clone(), &bundle, id).await?;
+    ) -> Result<LandedTransaction, TransactorError> {
+        let (landed, transaction) = match self
+            .clone()
+            .send_with_level_confirmed(log_ctx.clone(), bundle, id, finalize)
+            .await
+        {
+            Ok(tx) => (self.landed(tx.signatures[0]).await, tx),
+            Err(e) => {
+                self.record(id, BundleEvent::Failed(e.to_string()));
+                return Err(e);
+            }
+        };
+        let signature = match landed {
+            Ok(ref landed) => landed.signature,
+            Err(e) => {
+                self.record(id, BundleEvent::Failed(e.to_string()));
+                return Err(e);
+            }
+        };
+        self.record(id, BundleEvent::Confirmed(signature));
         if finalize {
             finalize_channel
                 .send(ChannelMessage::Task(FinalizationTask {
                     log_ctx: log_ctx.map(|c| c.to_string()),
-                    signature,
-                    bundle,
+                    transaction,
                     id,
                     start,
                 }))
                 .expect("Channel error");
         }
-        Ok(signature)
+        landed
     }
 
+    /// Sends the bundles one by one, the bundles after a failed one aren't sent
     pub async fn send<T: Display + Clone>(
         &self,
         log_ctx: Option<T>,
-        bundles: &[MessageBundle],
+        bundles: &[MessageBundle<'_>],
         finalize: bool,
-    ) -> Result<(), TransactorError> {
+    ) -> Vec<BundleResult> {
+        let mut results = Vec::with_capacity(bundles.len());
         for bundle in bundles.iter() {
             let id = Uuid::new_v4();
             let start = Instant::now();
-            self.clone()
+            let result = self
+                .clone()
                 .send_bundle(
                     log_ctx.clone(),
-                    bundle.clone(),
+                    bundle,
                     id,
                     start,
                     finalize,
                     self.finalize_channel.clone(),
                 )
-                .await?;
+                .await;
+            let failed = result.is_err();
+            results.push(BundleResult { id, result });
+            if failed {
+                break;
+            }
         }
-        Ok(())
+        results
     }
 
+    /// Packs the instructions into messages and sends them. With the fee oracle every message gets
+    /// the estimated compute unit price, the given `compute_unit_price` is its lower bound then.
+    /// With the simulator the instructions are packed by their simulated compute units. A failed
+    /// simulation of the first instruction of a group is returned before sending, the later ones
+    /// may depend on it and keep the estimated units. With the nonce pool the messages of the
+    /// nonce authority are sent with durable nonces. With `group_by_locks` the instructions writing
+    /// the same accounts are sent one message after another, stopping at a failed one.
+    /// Returns the result of every sent message in the order they're packed.
     pub async fn send_all_instructions<T: Display + Clone>(
         &self,
         log_ctx: Option<T>,
@@ -357,33 +1042,136 @@ impl SolanaTransactor {
         alt: &[AddressLookupTableAccount],
         compute_unit_price: Option<u64>,
         finalize: bool,
-    ) -> Result<(), TransactorError> {
+    ) -> Result<Vec<BundleResult>, TransactorError> {
+        // The price instruction is updated by the fee oracle before sending
+        let compute_unit_price = compute_unit_price.or(self.fee_oracle.as_ref().map(|_| 0));
         let mut ix_compiler = IxCompiler::new(payer, compute_unit_price);
-        let messages: Result<Vec<_>, TransactorError> = instructions
-            .iter()
-            .filter_map(|ix| {
-                ix_compiler
-                    .compile(log_ctx.clone(), ix.instruction.clone(), alt, ix.compute_units)
-                    .transpose()
-            })
-            .collect();
-        let mut messages = messages?;
-        if let Some(msg) = ix_compiler.flush()? {
-            messages.push(msg);
+        // Messages are compiled with the placeholder nonce account, every message leases its own
+        // one before sending
+        let nonce_pool = self.nonce_pool.as_ref().filter(|pool| pool.authority() == &payer);
+        ix_compiler.set_nonce(
+            nonce_pool.and_then(|pool| pool.placeholder()).map(|account| (*account, payer)),
+        );
+        let groups = if self.group_by_locks {
+            lock_groups(instructions, &payer)
+        } else {
+            vec![(0..instructions.len()).collect()]
+        };
+        let mut sequences = Vec::new();
+        for group in groups {
+            let mut messages = Vec::new();
+            for (i, index) in group.into_iter().enumerate() {
+                let ix = instructions[index].instruction.clone();
+                let mut units = instructions[index].compute_units;
+                if let Some(simulator) = &self.simulator {
+                    match simulator.instruction_units(&payer, &ix, alt).await {
+                        Ok(measured) => units = measured.unwrap_or(units),
+                        Err(TransactorError::SimulationFailed(e)) if i > 0 => {
+                            log_with_ctx!(
+                                debug,
+                                log_ctx,
+                                "Keeping the estimated compute units of instruction {}: {}",
+                                index,
+                                e
+                            );
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                messages.extend(ix_compiler.compile(log_ctx.clone(), ix, alt, units)?);
+            }
+            messages.extend(ix_compiler.flush()?);
+            if self.group_by_locks {
+                sequences.push(messages);
+            } else {
+                // The messages are independent without the grouping
+                sequences.extend(messages.into_iter().map(|msg| vec![msg]));
+            }
         }
+        log_with_ctx!(debug, log_ctx, "Sending {} message sequences", sequences.len());
+
+        let results: Vec<Vec<_>> =
+            futures::stream::iter(sequences.into_iter().zip(repeat(log_ctx)))
+                .map(|(messages, log_ctx)| async move {
+                    let mut results = Vec::with_capacity(messages.len());
+                    for msg in messages {
+                        let result = self
+                            .send_message(
+                                log_ctx.clone(),
+                                msg,
+                                nonce_pool,
+                                signers,
+                                payer,
+                                finalize,
+                            )
+                            .await;
+                        let failed = !result.is_success();
+                        results.push(result);
+                        if failed {
+                            break;
+                        }
+                    }
+                    results
+                })
+                .buffered(parallel_limit)
+                .collect()
+                .await;
+        Ok(results.into_iter().flatten().collect())
+    }
 
-        futures::stream::iter(messages.iter().zip(repeat(log_ctx)))
-            .for_each_concurrent(parallel_limit, |(msg, log_ctx)| async move {
-                let bundle = MessageBundle::new(msg, signers, payer);
-                if let Err(e) = self.send(log_ctx.clone(), &[bundle], finalize).await {
-                    log_with_ctx!(error, log_ctx, "Failed to send: {}", e);
+    async fn send_message<T: Display + Clone>(
+        &self,
+        log_ctx: Option<T>,
+        mut msg: VersionedMessage,
+        nonce_pool: Option<&Arc<NoncePool>>,
+        signers: &[&Keypair],
+        payer: Pubkey,
+        finalize: bool,
+    ) -> BundleResult {
+        // The nonce account is held until the message is confirmed or fails
+        let _lease = match nonce_pool {
+            Some(nonce_pool) => match self.lease_nonce(nonce_pool, &mut msg).await {
+                Ok(lease) => Some(lease),
+                Err(e) => {
+                    log_with_ctx!(error, log_ctx, "Failed to lease nonce: {}", e);
+                    return BundleResult {
+                        id: Uuid::new_v4(),
+                        result: Err(e),
+                    };
                 }
-            })
-            .await;
-        Ok(())
+            },
+            None => None,
+        };
+        let bundle = MessageBundle::new(&msg, signers, payer);
+        let result = self
+            .send(log_ctx.clone(), &[bundle], finalize)
+            .await
+            .pop()
+            .expect("Result of the bundle");
+        if let Err(e) = &result.result {
+            log_with_ctx!(error, log_ctx, "Failed to send: {}", e);
+        }
+        result
+    }
+
+    /// Leases a nonce account for the message compiled with the placeholder one
+    async fn lease_nonce(
+        &self,
+        nonce_pool: &Arc<NoncePool>,
+        message: &mut VersionedMessage,
+    ) -> Result<NonceLease, TransactorError> {
+        let lease = nonce_pool.lease().await;
+        let nonce = self.get_nonce(lease.account()).await?;
+        if let Some(placeholder) = nonce_pool.placeholder() {
+            set_nonce(message, placeholder, lease.account(), nonce);
+        }
+        Ok(lease)
     }
 
     pub async fn await_all_tx(self) {
+        for handle in self.pool_handles.lock().await.drain(..) {
+            handle.abort();
+        }
         if let Some(handle) = self.handle.lock().await.take() {
             self.finalize_channel.send(ChannelMessage::Stop).expect("Channel error");
             self.finalize_channel.closed().await;
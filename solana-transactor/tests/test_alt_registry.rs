@@ -0,0 +1,54 @@
+use solana_sdk::{
+    commitment_config::CommitmentConfig, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+    signature::Keypair, signer::Signer,
+};
+use solana_transactor::{
+    alt_manager::AltRegistry, AltRegistryConfig, RpcEntry, RpcPool, SolanaTransactor,
+};
+
+#[tokio::test]
+#[ignore = "Requires a running solana-test-validator"]
+async fn test_alt_registry() {
+    let entries = [RpcEntry {
+        url: "http://127.0.0.1:8899".to_owned(),
+        ratelimit: 100,
+        burst: Some(10),
+        ws_url: None,
+    }];
+    let pool = RpcPool::new(&entries, &entries).unwrap();
+    let transactor = SolanaTransactor::start(pool.clone()).await.unwrap();
+
+    let authority = Keypair::new();
+    let authority_pubkey = authority.pubkey();
+    let airdrop = pool
+        .with_read_rpc(
+            |rpc| async move {
+                let signature = rpc.request_airdrop(&authority_pubkey, LAMPORTS_PER_SOL).await?;
+                rpc.poll_for_signature(&signature).await
+            },
+            CommitmentConfig::confirmed(),
+        )
+        .await;
+    airdrop.unwrap();
+
+    let path = std::env::temp_dir().join(format!("{}.registry", authority_pubkey));
+    let config = AltRegistryConfig {
+        path: path.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    let reopened_authority = Keypair::from_bytes(&authority.to_bytes()).unwrap();
+    let registry = AltRegistry::open(transactor.clone(), authority, config.clone()).await.unwrap();
+    let addresses: Vec<_> = (0..25).map(|_| Pubkey::new_unique()).collect();
+    let tables = registry.tables("protocol", &addresses[..20]).await.unwrap();
+    assert_eq!(tables.len(), 1);
+    assert_eq!(tables[0].addresses, addresses[..20]);
+
+    // The table is reused after reopening and extended by the missing addresses only
+    drop(registry);
+    let registry = AltRegistry::open(transactor, reopened_authority, config).await.unwrap();
+    let extended = registry.tables("protocol", &addresses).await.unwrap();
+    assert_eq!(extended.len(), 1);
+    assert_eq!(extended[0].key, tables[0].key);
+    assert_eq!(extended[0].addresses, addresses);
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use solana_sdk::{
+    commitment_config::CommitmentConfig, native_token::LAMPORTS_PER_SOL, signature::Keypair,
+    signer::Signer, system_transaction,
+};
+use solana_transactor::{RpcEntry, RpcPool};
+
+#[tokio::test]
+#[ignore = "Requires a running solana-test-validator"]
+async fn test_signature_subscription() {
+    let entries = [RpcEntry {
+        url: "http://127.0.0.1:8899".to_owned(),
+        ratelimit: 100,
+        burst: Some(10),
+        ws_url: Some("ws://127.0.0.1:8900".to_owned()),
+    }];
+    let pool = RpcPool::new(&entries, &entries).unwrap();
+    tokio::spawn(pool.clone().run_subscriptions());
+
+    let payer = Keypair::new();
+    let payer_pubkey = payer.pubkey();
+    let airdrop = pool
+        .with_read_rpc(
+            |rpc| async move {
+                let signature = rpc.request_airdrop(&payer_pubkey, LAMPORTS_PER_SOL).await?;
+                rpc.poll_for_signature(&signature).await
+            },
+            CommitmentConfig::confirmed(),
+        )
+        .await;
+    airdrop.unwrap();
+
+    let blockhash = pool
+        .with_read_rpc(|rpc| async move { rpc.get_latest_blockhash().await }, Default::default())
+        .await
+        .unwrap();
+    let tx = system_transaction::transfer(&payer, &Keypair::new().pubkey(), 1000000, blockhash);
+    // Subscribed before the transaction is sent, so the notification can't be missed
+    let mut subscription = None;
+    for _ in 0..20 {
+        subscription =
+            pool.subscribe_signature(&tx.signatures[0], CommitmentConfig::confirmed()).await;
+        if subscription.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    let mut subscription = subscription.expect("Pubsub is not connected");
+    pool.with_write_rpc(|rpc| async move { rpc.send_transaction(&tx).await }, Default::default())
+        .await
+        .unwrap();
+    assert!(subscription.wait(Duration::from_secs(30)).await);
+}
@@ -11,10 +11,14 @@ async fn test_send() {
     let read_rpcs = [RpcEntry {
         url: "https://api.mainnet-beta.solana.com".to_owned(),
         ratelimit: 1,
+        burst: None,
+        ws_url: None,
     }];
     let write_rpcs = [RpcEntry {
         url: "https://api.mainnet-beta.solana.com".to_owned(),
         ratelimit: 1,
+        burst: None,
+        ws_url: None,
     }];
     let pool = RpcPool::new(&read_rpcs, &write_rpcs).unwrap();
     let transactor = SolanaTransactor::start(pool).await.expect("Failed to init transactor");
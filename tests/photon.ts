@@ -49,6 +49,7 @@ describe("photon", () => {
     const executor = anchor.web3.Keypair.fromSecretKey(executor_keypair);
 
     let config;
+    let programData;
     let govProtocolInfo;
     let counter;
     let proposer;
@@ -58,6 +59,7 @@ describe("photon", () => {
     let transmittersRaw = [];
     let nonce = 0;
     let onefuncProtocol;
+    let executedOpHash;
 
     before(async () => {
         let tx = await program.provider.connection.requestAirdrop(
@@ -75,6 +77,10 @@ describe("photon", () => {
             [ROOT, utf8.encode("CONFIG")],
             program.programId
         )[0];
+        programData = web3.PublicKey.findProgramAddressSync(
+            [program.programId.toBuffer()],
+            new web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+        )[0];
         govProtocolInfo = web3.PublicKey.findProgramAddressSync(
             [ROOT, utf8.encode("PROTOCOL"), GOV_PROTOCOL_ID],
             program.programId
@@ -109,6 +115,26 @@ describe("photon", () => {
         )[0];
     });
 
+    // Prints the compute units spent on decoding the gov calldata, they are logged by the program
+    // built with the `measure-cu` feature only
+    async function logDecodingComputeUnits(signature: string) {
+        const tx = await program.provider.connection.getTransaction(signature, {
+            commitment: "confirmed",
+            maxSupportedTransactionVersion: 0,
+        });
+        const logs = tx?.meta?.logMessages ?? [];
+        logs.forEach((log, i) => {
+            if (!log.startsWith("Program log: Decoding ")) {
+                return;
+            }
+            const remaining = logs
+                .slice(i + 1, i + 3)
+                .map(x => Number(x.match(/consumption: (\d+) units remaining/)?.[1]));
+            const label = log.slice("Program log: ".length);
+            console.debug(label + ":", remaining[0] - remaining[1], "CU");
+        });
+    }
+
     async function executeOperation(
         protocolId: Buffer,
         protocolAddr: anchor.web3.PublicKey,
@@ -155,6 +181,10 @@ describe("photon", () => {
             [ROOT, utf8.encode("PROTOCOL"), op.protocolId],
             program.programId
         )[0];
+        let gasVault = web3.PublicKey.findProgramAddressSync(
+            [ROOT, utf8.encode("GAS_VAULT"), op.protocolId],
+            program.programId
+        )[0];
         // Load
         let signature = await program.methods
             .loadOperation(op, op_hash)
@@ -199,6 +229,14 @@ describe("photon", () => {
                 [ROOT, utf8.encode("CONFIG")],
                 program.programId
             )[0];
+            let gov_timelock_pda = web3.PublicKey.findProgramAddressSync(
+                [ROOT, utf8.encode("GOV_TIMELOCK")],
+                program.programId
+            )[0];
+            let pending_change_pda = web3.PublicKey.findProgramAddressSync(
+                [ROOT, utf8.encode("PENDING_GOV"), op_hash],
+                program.programId
+            )[0];
 
             let signature = await program.methods
                 .executeOperation(op_hash)
@@ -207,6 +245,9 @@ describe("photon", () => {
                     opInfo,
                     protocolInfo: govProtocolInfo,
                     callAuthority: govCallAuthority,
+                    gasVault,
+                    loader: executor.publicKey,
+                    systemProgram: web3.SystemProgram.programId,
                 })
                 .remainingAccounts([
                     { pubkey: program.programId, isSigner: false, isWritable: false },
@@ -214,10 +255,13 @@ describe("photon", () => {
                     { pubkey: govProtocolInfo, isSigner: false, isWritable: true },
                     { pubkey: target_protocol_info_pda, isSigner: false, isWritable: true },
                     { pubkey: web3.SystemProgram.programId, isSigner: false, isWritable: true },
+                    { pubkey: gov_timelock_pda, isSigner: false, isWritable: false },
+                    { pubkey: pending_change_pda, isSigner: false, isWritable: true },
                 ])
                 .signers([executor])
                 .rpc();
             console.debug("execute_gov_operation:", signature);
+            await logDecodingComputeUnits(signature);
         } else {
             let signature = await program.methods
                 .executeOperation(op_hash)
@@ -226,6 +270,9 @@ describe("photon", () => {
                     opInfo,
                     protocolInfo,
                     callAuthority,
+                    gasVault,
+                    loader: executor.publicKey,
+                    systemProgram: web3.SystemProgram.programId,
                 })
                 .signers([executor])
                 .remainingAccounts(remainingAccounts)
@@ -234,6 +281,7 @@ describe("photon", () => {
         }
         console.log("Proposal", nonce, "executed");
         nonce++;
+        return op_hash;
     }
 
 
@@ -256,13 +304,15 @@ describe("photon", () => {
                 eob_master_contract_buf,
                 new anchor.BN(CONSENSUS_TARGET_RATE),
                 [transmittersRaw[0]],
-                [executor.publicKey]
+                [executor.publicKey],
+                new anchor.BN(SOLANA_CHAIN_ID)
             )
             .accounts({
                 admin: owner.publicKey,
                 protocolInfo: govProtocolInfo,
                 config,
                 systemProgram: web3.SystemProgram.programId,
+                programData,
             })
             .signers([owner])
             .rpc();
@@ -290,28 +340,118 @@ describe("photon", () => {
         await executeOperation(GOV_PROTOCOL_ID, program.programId, 0x970b6109, params, ONE_FUNC_ID);
     });
 
+    it("govTimelock", async () => {
+        let guardian = anchor.web3.Keypair.generate();
+        let govTimelock = web3.PublicKey.findProgramAddressSync(
+            [ROOT, utf8.encode("GOV_TIMELOCK")],
+            program.programId
+        )[0];
+        let setGovTimelock = (delaySlots: number, operations: number[]) =>
+            program.methods
+                .setGovTimelock(guardian.publicKey, new anchor.BN(delaySlots), operations)
+                .accounts({
+                    admin: owner.publicKey,
+                    config,
+                    govTimelock,
+                    systemProgram: web3.SystemProgram.programId,
+                })
+                .signers([owner])
+                .rpc();
+        let pendingChange = (opHash: Buffer) =>
+            web3.PublicKey.findProgramAddressSync(
+                [ROOT, utf8.encode("PENDING_GOV"), opHash],
+                program.programId
+            )[0];
+        // setConsensusTargetRate(bytes)
+        await setGovTimelock(2, [0x970b6109]);
+
+        let params = setConsensusTargetRate(ONE_FUNC_ID, 7000);
+        let opHash = await executeOperation(
+            GOV_PROTOCOL_ID,
+            program.programId,
+            0x970b6109,
+            params,
+            ONE_FUNC_ID
+        );
+        let protocolInfo = await program.account.protocolInfo.fetch(onefuncProtocol);
+        assert.equal(protocolInfo.consensusTargetRate.toNumber(), 6000);
+
+        await sleep(2000);
+        await program.methods
+            .applyGovChange(opHash)
+            .accounts({
+                executor: executor.publicKey,
+                opInfo: web3.PublicKey.findProgramAddressSync(
+                    [ROOT, utf8.encode("OP"), opHash],
+                    program.programId
+                )[0],
+                config,
+                govInfo: govProtocolInfo,
+                pendingChange: pendingChange(opHash),
+                targetProtocolInfo: onefuncProtocol,
+                payer: executor.publicKey,
+            })
+            .signers([executor])
+            .rpc();
+        protocolInfo = await program.account.protocolInfo.fetch(onefuncProtocol);
+        assert.equal(protocolInfo.consensusTargetRate.toNumber(), 7000);
+
+        params = setConsensusTargetRate(ONE_FUNC_ID, 6000);
+        opHash = await executeOperation(
+            GOV_PROTOCOL_ID,
+            program.programId,
+            0x970b6109,
+            params,
+            ONE_FUNC_ID
+        );
+        await program.methods
+            .vetoGovChange(opHash)
+            .accounts({
+                guardian: guardian.publicKey,
+                govTimelock,
+                pendingChange: pendingChange(opHash),
+                payer: executor.publicKey,
+            })
+            .signers([guardian])
+            .rpc();
+        assert.isNull(await program.provider.connection.getAccountInfo(pendingChange(opHash)));
+
+        await setGovTimelock(0, []);
+        await executeOperation(GOV_PROTOCOL_ID, program.programId, 0x970b6109, params, ONE_FUNC_ID);
+        protocolInfo = await program.account.protocolInfo.fetch(onefuncProtocol);
+        assert.equal(protocolInfo.consensusTargetRate.toNumber(), 6000);
+    });
+
     it("addAllowedProtocolAddress", async () => {
+        let params = addAllowedProtocolAddress(ONE_FUNC_ID, onefunc.programId);
         if (TEST_REMOVE_FUNCS) {
             let addr = anchor.web3.Keypair.generate().publicKey;
-            let params = addAllowedProtocolAddress(ONE_FUNC_ID, addr);
+            let addrParams = addAllowedProtocolAddress(ONE_FUNC_ID, addr);
             await executeOperation(
                 GOV_PROTOCOL_ID,
                 program.programId,
                 0xd296a0ff,
-                params,
+                addrParams,
                 ONE_FUNC_ID
             );
+            await executeOperation(GOV_PROTOCOL_ID, program.programId, 0xd296a0ff, params, ONE_FUNC_ID);
+            let protocolInfo = await program.account.protocolInfo.fetch(onefuncProtocol);
+            assert.ok(protocolInfo.protocolAddress.equals(addr));
+            assert.ok(protocolInfo.extraProtocolAddresses[0].equals(onefunc.programId));
             // removeAllowedProtocolAddress(bytes)
             await executeOperation(
                 GOV_PROTOCOL_ID,
                 program.programId,
                 0x0b0a4ca98,
-                params,
+                addrParams,
                 ONE_FUNC_ID
             );
+            protocolInfo = await program.account.protocolInfo.fetch(onefuncProtocol);
+            assert.ok(protocolInfo.protocolAddress.equals(onefunc.programId));
+            assert.ok(protocolInfo.extraProtocolAddresses[0].equals(web3.PublicKey.default));
+        } else {
+            await executeOperation(GOV_PROTOCOL_ID, program.programId, 0xd296a0ff, params, ONE_FUNC_ID);
         }
-        let params = addAllowedProtocolAddress(ONE_FUNC_ID, onefunc.programId);
-        await executeOperation(GOV_PROTOCOL_ID, program.programId, 0xd296a0ff, params, ONE_FUNC_ID);
     });
 
     it("addExecutor", async () => {
@@ -470,7 +610,7 @@ describe("photon", () => {
     it("executeOperation by code", async () => {
         let keys = [{ isSigner: false, isWritable: true, pubkey: counter }];
         // This operation results in the `receive_photon_msg` method invocation with a PhotonMsgWithSelector
-        await executeOperation(
+        executedOpHash = await executeOperation(
             ONE_FUNC_ID,
             onefunc.programId,
             0x01020304,
@@ -532,6 +672,44 @@ describe("photon", () => {
             "Unexpected data"
         );
         expect((event.data.nonce as anchor.BN).toNumber()).eq(1, "Unexpected nonce");
+        expect((event.data.proposer as web3.PublicKey).equals(proposer)).eq(
+            true,
+            "Unexpected proposer"
+        );
+        expect(event.data.callerProgram).eq(null, "Unexpected callerProgram");
+    });
+
+    it("views", async () => {
+        const opInfo = (opHash: Buffer) =>
+            web3.PublicKey.findProgramAddressSync(
+                [ROOT, utf8.encode("OP"), opHash],
+                program.programId
+            )[0];
+        let status = await program.methods
+            .getOpStatus(executedOpHash)
+            .accounts({ opInfo: opInfo(executedOpHash) })
+            .view();
+        assert.deepEqual(status, { executed: {} });
+        status = await program.methods
+            .getOpStatus(Buffer.alloc(32))
+            .accounts({ opInfo: opInfo(Buffer.alloc(32)) })
+            .view();
+        assert.deepEqual(status, { none: {} });
+
+        const info = await program.methods
+            .getProtocolInfo(ONE_FUNC_ID)
+            .accounts({ protocolInfo: onefuncProtocol })
+            .view();
+        expect(info.isInit).eq(true);
+        expect(info.consensusTargetRate.toNumber()).eq(CONSENSUS_TARGET_RATE);
+        assert.deepEqual(
+            info.transmitters.map(x => "0x" + Buffer.from(x).toString("hex")),
+            transmittersRaw.map(x => "0x" + Buffer.from(x).toString("hex"))
+        );
+        expect(info.protocolAddresses[0].equals(onefunc.programId)).eq(true);
+
+        const nextNonce = await program.methods.getNextNonce().accounts({ config }).view();
+        expect(nextNonce.toNumber()).eq(2, "Unexpected next nonce");
     });
 
     it("propose with selector too big", async () => {
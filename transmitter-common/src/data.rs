@@ -1,13 +1,16 @@
 use hex;
 use photon::{
+    gov,
     protocol_data::FunctionSelector,
-    util::{u128_to_bytes32, u64_to_bytes32},
+    util::{u128_to_bytes32, u64_to_bytes32, EthAddress},
 };
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
 use solana_sdk::{bs58, pubkey::Pubkey};
 use std::fmt::{Display, Formatter};
 
+use super::error::DataError;
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct ProtocolId(pub ProtocolIdImpl);
 pub type ProtocolIdImpl = [u8; 32];
@@ -57,6 +60,7 @@ pub enum TransmitterMsgVersioned {
 pub enum TransmitterMsgImpl {
     Propose(Propose),
     ProposalExecuted(ProposalExecuted),
+    GovOperationApplied(GovOperationApplied),
     #[serde(rename = "signedOperation")]
     SignedOperationData(SignedOperation),
 }
@@ -84,13 +88,17 @@ pub struct TransmitterSignature {
     pub s: Vec<u8>,
 }
 
-impl From<TransmitterSignature> for photon::protocol_data::TransmitterSignature {
-    fn from(value: TransmitterSignature) -> Self {
-        photon::protocol_data::TransmitterSignature {
+impl TryFrom<TransmitterSignature> for photon::protocol_data::TransmitterSignature {
+    type Error = DataError;
+    fn try_from(value: TransmitterSignature) -> Result<Self, Self::Error> {
+        if value.r.len() != 32 || value.s.len() != 32 {
+            return Err(DataError::TransmitterSignature);
+        }
+        Ok(photon::protocol_data::TransmitterSignature {
             r: value.r,
             s: value.s,
             v: value.v,
-        }
+        })
     }
 }
 
@@ -160,7 +168,7 @@ impl OperationData {
 }
 
 impl TryFrom<OperationData> for photon::protocol_data::OperationData {
-    type Error = Vec<u8>;
+    type Error = DataError;
     fn try_from(value: OperationData) -> Result<Self, Self::Error> {
         Ok(photon::protocol_data::OperationData {
             protocol_id: <Vec<u8>>::from(value.protocol_id.0),
@@ -170,8 +178,10 @@ impl TryFrom<OperationData> for photon::protocol_data::OperationData {
             src_op_tx_id: value.src_op_tx_id,
             nonce: value.nonce,
             dest_chain_id: value.dest_chain_id,
-            protocol_addr: Pubkey::try_from(value.protocol_addr)?,
-            function_selector: FunctionSelector::try_from(value.function_selector.as_slice())?,
+            protocol_addr: Pubkey::try_from(value.protocol_addr)
+                .map_err(|_| DataError::ProtocolAddress)?,
+            function_selector: FunctionSelector::try_from(value.function_selector.as_slice())
+                .map_err(|_| DataError::FunctionSelector)?,
             params: value.params,
             reserved: value.reserved,
         })
@@ -271,3 +281,240 @@ pub struct ProposalExecuted {
     pub op_hash: OpHash,
     pub executor: Pubkey,
 }
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GovOperationApplied {
+    #[serde(skip)]
+    pub need_check: bool,
+    pub last_watched_block: String,
+    pub op_hash: OpHash,
+    #[serde(with = "protocol_id_serialization")]
+    pub protocol_id: ProtocolId,
+    pub change: GovChange,
+}
+
+/// The serializable counterpart of `photon::gov::GovChange`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(
+    tag = "operation",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum GovChange {
+    AddAllowedProtocol {
+        old_consensus_target_rate: u64,
+        new_consensus_target_rate: u64,
+        old_transmitters: Vec<EthAddress>,
+        new_transmitters: Vec<EthAddress>,
+    },
+    AddAllowedProtocolAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveAllowedProtocolAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddAllowedProposerAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveAllowedProposerAddress {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddExecutor {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    RemoveExecutor {
+        old: Vec<Pubkey>,
+        new: Vec<Pubkey>,
+    },
+    AddTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    RemoveTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    UpdateTransmitters {
+        old: Vec<EthAddress>,
+        new: Vec<EthAddress>,
+    },
+    SetConsensusTargetRate {
+        old: u64,
+        new: u64,
+    },
+    SetRateLimit {
+        old: RateLimitSettings,
+        new: RateLimitSettings,
+    },
+    SetExclusiveWindow {
+        old: u32,
+        new: u32,
+    },
+    SetExecutorReward {
+        old: u64,
+        new: u64,
+    },
+    SetProtocolOwner {
+        old: ProtocolOwnerSettings,
+        new: ProtocolOwnerSettings,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitSettings {
+    pub window_slots: u64,
+    pub max_ops: u64,
+    #[serde(with = "u128_serialization")]
+    pub max_value: u128,
+    pub value_offset: u32,
+}
+
+impl From<gov::RateLimitSettings> for RateLimitSettings {
+    fn from(value: gov::RateLimitSettings) -> Self {
+        RateLimitSettings {
+            window_slots: value.window_slots,
+            max_ops: value.max_ops,
+            max_value: value.max_value,
+            value_offset: value.value_offset,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolOwnerSettings {
+    pub owner: Option<Pubkey>,
+    pub max_proposers: u32,
+}
+
+impl From<gov::ProtocolOwnerSettings> for ProtocolOwnerSettings {
+    fn from(value: gov::ProtocolOwnerSettings) -> Self {
+        ProtocolOwnerSettings {
+            owner: value.owner,
+            max_proposers: value.max_proposers,
+        }
+    }
+}
+
+impl From<gov::GovChange> for GovChange {
+    fn from(value: gov::GovChange) -> Self {
+        match value {
+            gov::GovChange::AddAllowedProtocol {
+                old_consensus_target_rate,
+                new_consensus_target_rate,
+                old_transmitters,
+                new_transmitters,
+            } => GovChange::AddAllowedProtocol {
+                old_consensus_target_rate,
+                new_consensus_target_rate,
+                old_transmitters,
+                new_transmitters,
+            },
+            gov::GovChange::AddAllowedProtocolAddress { old, new } => {
+                GovChange::AddAllowedProtocolAddress { old, new }
+            }
+            gov::GovChange::RemoveAllowedProtocolAddress { old, new } => {
+                GovChange::RemoveAllowedProtocolAddress { old, new }
+            }
+            gov::GovChange::AddAllowedProposerAddress { old, new } => {
+                GovChange::AddAllowedProposerAddress { old, new }
+            }
+            gov::GovChange::RemoveAllowedProposerAddress { old, new } => {
+                GovChange::RemoveAllowedProposerAddress { old, new }
+            }
+            gov::GovChange::AddExecutor { old, new } => GovChange::AddExecutor { old, new },
+            gov::GovChange::RemoveExecutor { old, new } => GovChange::RemoveExecutor { old, new },
+            gov::GovChange::AddTransmitters { old, new } => GovChange::AddTransmitters { old, new },
+            gov::GovChange::RemoveTransmitters { old, new } => {
+                GovChange::RemoveTransmitters { old, new }
+            }
+            gov::GovChange::UpdateTransmitters { old, new } => {
+                GovChange::UpdateTransmitters { old, new }
+            }
+            gov::GovChange::SetConsensusTargetRate { old, new } => {
+                GovChange::SetConsensusTargetRate { old, new }
+            }
+            gov::GovChange::SetRateLimit { old, new } => GovChange::SetRateLimit {
+                old: old.into(),
+                new: new.into(),
+            },
+            gov::GovChange::SetExclusiveWindow { old, new } => {
+                GovChange::SetExclusiveWindow { old, new }
+            }
+            gov::GovChange::SetExecutorReward { old, new } => {
+                GovChange::SetExecutorReward { old, new }
+            }
+            gov::GovChange::SetProtocolOwner { old, new } => GovChange::SetProtocolOwner {
+                old: old.into(),
+                new: new.into(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn op_data(function_selector: Vec<u8>) -> OperationData {
+        OperationData {
+            protocol_addr: Pubkey::new_unique().to_bytes().to_vec(),
+            function_selector,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_operation_data_conversion() {
+        // Every selector up to three bytes long either fails with a typed error or is encoded back
+        // into the same bytes, so the op hash matches the one calculated from the raw data. Type
+        // bytes above 3 are rejected the same way as 3.
+        let short_selectors = (0..=3).flat_map(|t| {
+            (0..=u8::MAX).flat_map(move |len| {
+                [vec![t], vec![t, len]]
+                    .into_iter()
+                    .chain((0..=u8::MAX).map(move |b| vec![t, len, b]))
+            })
+        });
+        for selector in short_selectors {
+            let op_data = op_data(selector.clone());
+            match photon::protocol_data::OperationData::try_from(op_data.clone()) {
+                Ok(converted) => {
+                    assert_eq!(converted.function_selector.to_bytes().unwrap(), selector);
+                    assert_eq!(
+                        converted.op_hash_with_message().unwrap(),
+                        op_data.op_hash_with_message()
+                    );
+                }
+                Err(err) => assert_eq!(err, DataError::FunctionSelector),
+            }
+        }
+
+        let mut invalid_address = op_data(vec![0, 0]);
+        invalid_address.protocol_addr.pop();
+        assert_eq!(
+            photon::protocol_data::OperationData::try_from(invalid_address).err(),
+            Some(DataError::ProtocolAddress)
+        );
+    }
+
+    #[test]
+    fn test_transmitter_signature_conversion() {
+        for (r_len, s_len) in [(0, 0), (31, 32), (32, 33), (64, 0), (32, 32)] {
+            let signature = TransmitterSignature {
+                v: 27,
+                r: vec![1; r_len],
+                s: vec![2; s_len],
+            };
+            let converted = photon::protocol_data::TransmitterSignature::try_from(signature);
+            assert_eq!(converted.is_ok(), r_len == 32 && s_len == 32);
+        }
+    }
+}
@@ -7,3 +7,13 @@ pub enum ExtensionError {
     #[error("Signing error")]
     Sign,
 }
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DataError {
+    #[error("Invalid protocol address")]
+    ProtocolAddress,
+    #[error("Invalid function selector")]
+    FunctionSelector,
+    #[error("Invalid transmitter signature")]
+    TransmitterSignature,
+}
@@ -1,7 +1,5 @@
 use serde::Deserialize;
 
-use super::SOLANA_CHAIN_ID;
-
 #[derive(Clone, Debug, Deserialize)]
 pub struct MongodbConfig {
     pub user: String,
@@ -12,6 +10,6 @@ pub struct MongodbConfig {
 }
 pub const MDB_LAST_BLOCK_COLLECTION: &str = "last_processed_blocks";
 
-pub fn mdb_solana_chain_id() -> String {
-    SOLANA_CHAIN_ID.to_string()
+pub fn mdb_solana_chain_id(chain_id: u128) -> String {
+    chain_id.to_string()
 }
@@ -12,6 +12,15 @@ pub trait ProtocolExtension: RefUnwindSafe {
         function_selector: &[u8],
         params: &[u8],
     ) -> Result<Vec<AccountMeta>, ExtensionError>;
+    /// Returns the accounts derived from the operation hash, they follow the ones of `get_accounts`
+    fn get_op_accounts(
+        &self,
+        _op_hash: &[u8; 32],
+        _function_selector: &[u8],
+        _params: &[u8],
+    ) -> Result<Vec<AccountMeta>, ExtensionError> {
+        Ok(Vec::new())
+    }
     fn sign_transaction(
         &self,
         function_selector: &[u8],
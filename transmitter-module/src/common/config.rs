@@ -1,6 +1,6 @@
 use serde::{de::Error, Deserialize, Deserializer};
 use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
-use solana_transactor::RpcEntry;
+use solana_transactor::{RpcEntry, RpcHealthConfig};
 
 #[derive(Clone, Debug, Deserialize)]
 pub(crate) struct SolanaClientConfig {
@@ -8,6 +8,10 @@ pub(crate) struct SolanaClientConfig {
     pub(crate) commitment: CommitmentConfig,
     pub(crate) read_rpcs: Vec<RpcEntry>,
     pub(crate) write_rpcs: Vec<RpcEntry>,
+    #[serde(deserialize_with = "deserialize_chain_id")]
+    pub(crate) chain_id: u128,
+    #[serde(default)]
+    pub(crate) rpc_health: RpcHealthConfig,
 }
 
 fn deserialize_commitment<'de, D>(deserializer: D) -> Result<CommitmentConfig, D::Error>
@@ -19,6 +23,14 @@ where
     Ok(CommitmentConfig { commitment })
 }
 
+fn deserialize_chain_id<'de, D>(deserializer: D) -> Result<u128, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let chain_id = String::deserialize(deserializer)?;
+    chain_id.parse().map_err(|err| Error::custom(format!("Malformed chain_id: {}", err)))
+}
+
 #[derive(Deserialize)]
 pub(crate) struct SolanaListenerConfig {
     #[serde(flatten)]
@@ -143,7 +143,9 @@ fn handle_irrelevant_log(this_program_str: &str, log: &str) -> (Option<String>,
 #[cfg(test)]
 mod test {
     use crate::common::solana_logs::parse_logs;
+    use anchor_lang::prelude::Pubkey;
     use photon::{ProposeEvent, ID as PROGRAM_ID};
+    use std::str::FromStr;
 
     #[test]
     fn test_logs_parsing() {
@@ -153,7 +155,7 @@ mod test {
             "Program log: Share message invoked",
             "Program pccm961CjaR7T7Hcht9omrXQb9w54ntJo95FFT7N9AJ invoke [2]",
             "Program log: Instruction: Propose",
-            "Program data: 8vb9LnW1kqUgAAAAb25lZnVuY19fX19fX19fX19fX19fX19fX19fX19fX18IAAAAAAAAAG2BAAAAAAAAAAAAAAAAAAADAAAAAQIDAwAAAAECAwMAAAABAgM=",
+            "Program data: 8vb9LnW1kqUgAAAAb25lZnVuY19fX19fX19fX19fX19fX19fX19fX19fX18IAAAAAAAAAG2BAAAAAAAAAAAAAAAAAAADAAAAAQIDAwAAAAECAwMAAAABAgMHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwHMICik2na9dkcdEfmdwME/wQD4AheMAQsbqyCOvRpRrg==",
             "Program pccm961CjaR7T7Hcht9omrXQb9w54ntJo95FFT7N9AJ consumed 16408 of 181429 compute units",
             "Program pccm961CjaR7T7Hcht9omrXQb9w54ntJo95FFT7N9AJ success",
             "Program EjpcUpcuJV2Mq9vjELMZHhgpvJ4ggoWtUYCTFqw6D9CZ consumed 35308 of 200000 compute units",
@@ -167,6 +169,11 @@ mod test {
         assert_eq!(propose_event.dst_chain_id, 33133);
         assert_eq!(propose_event.params, vec![1, 2, 3]);
         assert_eq!(propose_event.protocol_id.as_slice(), b"onefunc_________________________");
+        assert_eq!(propose_event.proposer, Pubkey::new_from_array([7; 32]));
+        assert_eq!(
+            propose_event.caller_program,
+            Some(Pubkey::from_str("EjpcUpcuJV2Mq9vjELMZHhgpvJ4ggoWtUYCTFqw6D9CZ").unwrap())
+        );
     }
 
     #[test]
@@ -29,6 +29,7 @@ impl SolanaEventListener {
     }
 }
 
+#[derive(Clone)]
 pub(crate) struct LogsBunch {
     pub need_check: bool,
     pub tx_signature: String,
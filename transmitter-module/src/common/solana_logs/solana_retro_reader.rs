@@ -49,8 +49,11 @@ impl SolanaRetroReader {
         };
 
         debug!("Found tx_read_from, start backward reading until: {}", tx_read_from);
-        let rpc_pool =
-            RpcPool::new(&solana_config.client.read_rpcs, &solana_config.client.write_rpcs)?;
+        let rpc_pool = RpcPool::new_with_health(
+            &solana_config.client.read_rpcs,
+            &solana_config.client.write_rpcs,
+            solana_config.client.rpc_health.clone(),
+        )?;
 
         let mut tx_read_from = Some(Signature::from_str(&tx_read_from).map_err(|err| {
             error!("Failed to decode tx_start_from: {}", err);
@@ -124,7 +127,9 @@ impl SolanaRetroReader {
                 return Some(tx_read_from.clone());
             }
         }
-        if let Ok(result @ Some(_)) = self.get_last_processed_block(mongodb_config).await {
+        let chain_id = solana_config.client.chain_id;
+        if let Ok(result @ Some(_)) = self.get_last_processed_block(mongodb_config, chain_id).await
+        {
             return result;
         }
         None
@@ -227,6 +232,7 @@ impl SolanaRetroReader {
     async fn get_last_processed_block(
         &self,
         mongodb_config: &MongodbConfig,
+        chain_id: u128,
     ) -> Result<Option<String>, EventListenerError> {
         let mut client_options =
             ClientOptions::parse_async(&mongodb_config.uri).await.map_err(|err| {
@@ -249,7 +255,7 @@ impl SolanaRetroReader {
         let collection = db.collection::<Document>(MDB_LAST_BLOCK_COLLECTION);
 
         let last_block: &str = &self.mongodb_config.key;
-        let chain_id = mdb_solana_chain_id();
+        let chain_id = mdb_solana_chain_id(chain_id);
         let doc = collection
             .find_one(doc! { "direction": "from", "chain": chain_id }, FindOneOptions::default())
             .await
@@ -2,7 +2,7 @@ use async_signal::{Signal, Signals};
 use futures_util::StreamExt;
 use log::{error, info};
 use solana_sdk::{commitment_config::CommitmentConfig, signer::Signer};
-use solana_transactor::{RpcPool, SolanaTransactor};
+use solana_transactor::{NonceConfig, RpcPool, SolanaTransactor, TransactorOptions};
 use std::io;
 use tokio::{
     select,
@@ -51,11 +51,26 @@ impl ExecutorApp {
         let (service_sender, service_receiver) = unbounded_channel();
         let (last_block_sender, last_block_receiver) = unbounded_channel();
         let executor = config.solana.payer.pubkey();
-        let transactor = SolanaTransactor::start(RpcPool::new(
-            &config.solana.client.read_rpcs,
-            &config.solana.client.write_rpcs,
-        )?)
+        let transactor = SolanaTransactor::start_with_options(
+            RpcPool::new_with_health(
+                &config.solana.client.read_rpcs,
+                &config.solana.client.write_rpcs,
+                config.solana.client.rpc_health.clone(),
+            )?,
+            TransactorOptions {
+                priority_fee: Some(config.solana.priority_fee.clone()),
+                simulation: config.solana.simulation.clone(),
+                nonce: config.solana.nonce_accounts.map(|accounts| NonceConfig {
+                    authority: executor,
+                    accounts,
+                }),
+                journal: config.solana.journal.clone(),
+                // The instructions of an operation depend on each other
+                group_by_locks: false,
+            },
+        )
         .await?;
+        transactor.create_nonce_accounts(&config.solana.payer).await?;
         let balance = transactor
             .rpc_pool()
             .with_read_rpc_loop(
@@ -81,8 +96,9 @@ impl ExecutorApp {
 
     fn trace_config(config: &ExecutorConfig) {
         info!(
-            "solana_commitment: {}, executor: {}",
+            "solana_commitment: {}, solana_chain_id: {}, executor: {}",
             config.solana.client.commitment.commitment,
+            config.solana.client.chain_id,
             config.solana.payer.pubkey(),
         );
         for rpc in &config.solana.client.read_rpcs {
@@ -91,6 +107,10 @@ impl ExecutorApp {
         for rpc in &config.solana.client.write_rpcs {
             info!("solana_write_rpc: {}, rate_limit: {}", rpc.url, rpc.ratelimit);
         }
+        info!("solana_priority_fee: {:?}", config.solana.priority_fee);
+        info!("solana_simulation: {:?}", config.solana.simulation);
+        info!("solana_nonce_accounts: {:?}", config.solana.nonce_accounts);
+        info!("solana_journal: {:?}", config.solana.journal);
 
         info!(
             "mongodb. uri: {}, user: {}, db: {}, key: {}",
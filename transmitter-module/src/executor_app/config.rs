@@ -2,6 +2,7 @@ use config::{Config, File};
 use log::{error, info};
 use serde::{de::Error, Deserialize, Deserializer};
 use solana_sdk::{self, bs58, signature::Keypair};
+use solana_transactor::{JournalConfig, PriorityFeeConfig, SimulationConfig};
 
 use transmitter_common::{
     config::ReconnectConfig,
@@ -41,6 +42,17 @@ pub(super) struct SolanaExecutorConfig {
     #[serde(flatten)]
     pub(super) suspending_config: SuspendingConfig,
     pub(super) executor_attempts: usize,
+    #[serde(default)]
+    pub(super) priority_fee: PriorityFeeConfig,
+    /// Compute unit limits are guessed if not set
+    #[serde(default)]
+    pub(super) simulation: Option<SimulationConfig>,
+    /// The number of nonce accounts of the payer, transactions are sent with durable nonces if set
+    #[serde(default)]
+    pub(super) nonce_accounts: Option<u16>,
+    /// Sent transactions are tracked in memory only if not set
+    #[serde(default)]
+    pub(super) journal: Option<JournalConfig>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,4 +87,8 @@ impl ExtensionManager {
     ) -> Option<&'static dyn ProtocolExtension> {
         self.extension_manager_impl.borrow().extensions.get(&protocol_id.0).copied()
     }
+
+    pub(super) fn protocol_ids(&self) -> Vec<ProtocolIdImpl> {
+        self.extension_manager_impl.borrow().extensions.keys().map(|x| **x).collect()
+    }
 }
@@ -36,6 +36,7 @@ impl LastBlockUpdater {
             resender,
             last_block_updater_mongodb: mongo::LastBlockUpdaterMongo::try_new(
                 config.mongodb.clone(),
+                config.solana.client.chain_id,
                 bc_receiver,
             )
             .await?,
@@ -167,11 +168,13 @@ mod mongo {
         block_number_receiver: Mutex<UnboundedReceiver<u64>>,
         db: String,
         last_block_key: String,
+        chain_id: String,
     }
 
     impl LastBlockUpdaterMongo {
         pub(super) async fn try_new(
             mongodb_config: MongodbConfig,
+            chain_id: u128,
             block_number_receiver: UnboundedReceiver<u64>,
         ) -> Result<LastBlockUpdaterMongo, ExecutorError> {
             let mut client_options =
@@ -197,6 +200,7 @@ mod mongo {
                 block_number_receiver: Mutex::new(block_number_receiver),
                 db: mongodb_config.db,
                 last_block_key: mongodb_config.key,
+                chain_id: mdb_solana_chain_id(chain_id),
             })
         }
 
@@ -219,12 +223,11 @@ mod mongo {
         async fn on_last_processed_block(&self, last_processed_block: u64) {
             let db = self.client.database(&self.db);
             let collection = db.collection::<Document>(MDB_LAST_BLOCK_COLLECTION);
-            let chain_id = mdb_solana_chain_id();
             let update_options = UpdateOptions::builder().upsert(true).build();
             let in_ms = transmitter_common::utils::get_time_ms();
             if let Err(err) = collection
                 .update_one(
-                    doc! { "direction": "to", "chain": &chain_id },
+                    doc! { "direction": "to", "chain": &self.chain_id },
                     doc! { "$set": { & self.last_block_key: last_processed_block.to_string(), "updated_at": in_ms as i64 }  },
                     update_options.clone(),
                 )
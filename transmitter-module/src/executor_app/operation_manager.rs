@@ -1,14 +1,33 @@
 use anchor_lang::{
     prelude::{AccountMeta, Pubkey},
-    AccountDeserialize, InstructionData, ToAccountMetas,
+    AccountDeserialize, Discriminator, InstructionData, ToAccountMetas,
 };
 use futures_util::{select, FutureExt, StreamExt};
 use log::*;
-use photon::{photon::ROOT, OpInfo};
+use photon::{
+    error::CustomError,
+    gas_vault::gas_vault_address,
+    migration::{op_info_from_account_data, protocol_info_from_account_data},
+    photon::ROOT,
+    protocol_data::{FunctionSelector, GOV_PROTOCOL_ID},
+    timelock::{pending_gov_change_address, PendingGovChange},
+};
+use solana_client::{
+    client_error::ClientError,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_sdk::{
-    address_lookup_table::AddressLookupTableAccount, instruction::Instruction, signer::Signer,
+    address_lookup_table::AddressLookupTableAccount, instruction::Instruction, rent::Rent,
+    signer::Signer,
+};
+use solana_transactor::{
+    ix_compiler::InstructionBundle,
+    log_with_ctx,
+    send_result::{all_landed, FailureClass},
+    SolanaTransactor, TransactorError,
 };
-use solana_transactor::{ix_compiler::InstructionBundle, log_with_ctx, SolanaTransactor};
+use std::collections::BTreeMap;
 use std::ops::Deref;
 use std::sync::{
     atomic::{AtomicU64, AtomicUsize, Ordering},
@@ -16,12 +35,14 @@ use std::sync::{
 };
 use std::time::Duration;
 use tokio::sync::{
-    mpsc::{Receiver, UnboundedReceiver, UnboundedSender},
+    mpsc::{unbounded_channel, Receiver, UnboundedReceiver, UnboundedSender},
     Mutex, Notify,
 };
-use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
 
-use transmitter_common::data::{OpHash, OperationData, SignedOperation};
+use transmitter_common::data::{
+    OpHash, OperationData, ProtocolId, ProtocolIdImpl, SignedOperation,
+};
 
 use super::{
     error::ExecutorError, extension_manager::ExtensionManager, ExecutorOpStatus, OpAcknowledge,
@@ -29,6 +50,37 @@ use super::{
 };
 use crate::executor_app::config::SolanaExecutorConfig;
 
+const SLOT_DURATION: Duration = Duration::from_millis(400);
+const SIMULATION_RETRY_DELAY: Duration = Duration::from_secs(5);
+const WAIT_DELAY_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Errors of the program that depend on the state of the operation or of the protocol, so the
+/// operation is retried after checking its status again
+const RETRYABLE_PROGRAM_ERRORS: [CustomError; 4] = [
+    CustomError::OpStateInvalid,
+    CustomError::ExecutorIsNotAssigned,
+    CustomError::GovChangeIsLocked,
+    CustomError::OperationNotApproved,
+];
+
+fn is_retryable(err: &TransactorError) -> bool {
+    if let TransactorError::TransactionFailed(failure) = err {
+        if let Some(program_error) = &failure.program_error {
+            if program_error.program_id == photon::ID {
+                return RETRYABLE_PROGRAM_ERRORS
+                    .into_iter()
+                    .any(|error| u32::from(error) == program_error.code);
+            }
+        }
+    }
+    err.classify() == FailureClass::Retryable
+}
+
+/// Windows are rechecked at least every minute
+fn slots_duration(slots: u64) -> Duration {
+    SLOT_DURATION * slots.clamp(1, 150) as u32
+}
+
 pub(super) struct OperationManager {
     op_data_receiver: Mutex<Option<ReceiverStream<SignedOperation>>>,
     op_acknowledge_sender: UnboundedSender<OpAcknowledge>,
@@ -37,11 +89,14 @@ pub(super) struct OperationManager {
     solana_config: SolanaExecutorConfig,
     service_receiver: Mutex<UnboundedReceiver<ServiceCmd>>,
     suspending_ctx: SuspendingCtx,
+    pending_gov_sender: UnboundedSender<OpHash>,
+    pending_gov_receiver: Mutex<Option<UnboundedReceiver<OpHash>>>,
 }
 
 #[derive(Default)]
 struct SuspendingCtx {
     balance: AtomicU64,
+    vault_balances: std::sync::Mutex<BTreeMap<ProtocolIdImpl, u64>>,
     processing_notify: Mutex<Option<Arc<Notify>>>,
     op_proc_counter: AtomicUsize,
 }
@@ -58,6 +113,7 @@ impl OperationManager {
         let extension_mng = ExtensionManager::new(extensions);
         let op_data_receiver: ReceiverStream<SignedOperation> =
             ReceiverStream::new(op_data_receiver);
+        let (pending_gov_sender, pending_gov_receiver) = unbounded_channel();
         Self {
             op_data_receiver: Mutex::new(Some(op_data_receiver)),
             op_acknowledge_sender,
@@ -66,12 +122,15 @@ impl OperationManager {
             solana_config,
             service_receiver: Mutex::new(service_receiver),
             suspending_ctx: SuspendingCtx::default(),
+            pending_gov_sender,
+            pending_gov_receiver: Mutex::new(Some(pending_gov_receiver)),
         }
     }
 
     pub async fn execute(&self) {
         select! {
             _ = self.execute_operations().fuse() => {}
+            _ = self.apply_pending_gov_changes().fuse() => {}
             _ = self.listen_update().fuse() => {}
         }
     }
@@ -129,12 +188,13 @@ impl OperationManager {
         debug!("{}. Operation received", op_hash_str);
         let mut last_op_status = (None, 0);
         loop {
-            if !self.check_balance_and_suspend(&op_hash_str).await {
+            let protocol_id = &op.operation_data.protocol_id;
+            if !self.check_balance_and_suspend(&op_hash_str, protocol_id).await {
                 continue;
             }
 
             self.suspending_ctx.op_proc_counter.fetch_add(1, Ordering::Release);
-            let Ok(mut op_status) = self.get_op_status(op_hash).await else {
+            let Ok((mut op_status, loader)) = self.get_op_status(op_hash).await else {
                 return Ok(());
             };
 
@@ -151,12 +211,49 @@ impl OperationManager {
             debug!("{}. Operation status: {:?}", op_hash_str, op_status);
             if ExecutorOpStatus::Executed == op_status || ExecutorOpStatus::Failed == op_status {
                 self.ack_executed(op.eob_block_number, op_hash, op_status);
+                // The timelocked gov change is applied in the background, so it doesn't hold the
+                // operation slot for the timelock
+                if ExecutorOpStatus::Executed == op_status && protocol_id.0 == *GOV_PROTOCOL_ID {
+                    // The pending change is found on-chain again after the restart, so a closed
+                    // channel doesn't lose it
+                    if let Err(err) = self.pending_gov_sender.send(op_hash) {
+                        log_with_ctx!(
+                            error,
+                            Some(op_hash_str.deref()),
+                            "Failed to send pending gov change: {}",
+                            err
+                        );
+                    }
+                }
                 break;
             }
 
-            let ix_bundle = self.build_ixs(op_hash, op.clone(), op_status)?;
-            const COMPUTE_UNIT_PRICE_LAMPORTS: u64 = 1000;
-            self.transactor
+            match self.get_wait_delay(op_hash, &op, op_status).await {
+                Ok(None) => {}
+                Ok(Some(delay)) => {
+                    // Waiting for a window is not an execution attempt
+                    last_op_status = (None, 0);
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                // The windows are unknown while the RPC fails, so the operation isn't sent blindly
+                Err(ExecutorError::SolanaClientError(err)) => {
+                    log_with_ctx!(
+                        warn,
+                        Some(op_hash_str.deref()),
+                        "Failed to check the windows, retry in {:?}: {}",
+                        WAIT_DELAY_RETRY_DELAY,
+                        err
+                    );
+                    tokio::time::sleep(WAIT_DELAY_RETRY_DELAY).await;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            }
+
+            let ix_bundle = self.build_ixs(op_hash, op.clone(), op_status, loader)?;
+            match self
+                .transactor
                 .send_all_instructions(
                     Some(op_hash_str.deref()),
                     &ix_bundle,
@@ -164,21 +261,200 @@ impl OperationManager {
                     self.solana_config.payer.pubkey(),
                     1,
                     alt,
-                    Some(COMPUTE_UNIT_PRICE_LAMPORTS),
+                    None,
                     false,
                 )
-                .await?;
+                .await
+            {
+                Ok(results) => {
+                    let Some(err) = results.into_iter().find_map(|result| result.result.err())
+                    else {
+                        continue;
+                    };
+                    // The status is checked again and the attempt is counted
+                    if is_retryable(&err) {
+                        log_with_ctx!(
+                            warn,
+                            Some(op_hash_str.deref()),
+                            "Failed to execute, retrying: {}",
+                            err
+                        );
+                        continue;
+                    }
+                    log_with_ctx!(error, Some(op_hash_str.deref()), "Operation rejected: {}", err);
+                    self.ack_executed(op.eob_block_number, op_hash, ExecutorOpStatus::Failed);
+                    break;
+                }
+                // Nothing has been sent, the status is checked again and the attempt is counted
+                Err(TransactorError::SimulationFailed(err)) => {
+                    log_with_ctx!(
+                        warn,
+                        Some(op_hash_str.deref()),
+                        "Simulation failed, retry in {:?}: {}",
+                        SIMULATION_RETRY_DELAY,
+                        err
+                    );
+                    tokio::time::sleep(SIMULATION_RETRY_DELAY).await;
+                }
+                Err(err) => return Err(err.into()),
+            }
         }
         Ok(())
     }
 
+    /// Applies the timelocked gov changes of the executed gov operations concurrently. The changes
+    /// left pending by the previous run are found on startup, as their operations are already
+    /// acknowledged.
+    async fn apply_pending_gov_changes(&self) {
+        let alt = &[][..];
+        let receiver = self.pending_gov_receiver.lock().await.take().unwrap();
+        let pending = self.find_pending_gov_changes().await;
+        if !pending.is_empty() {
+            info!("Found {} pending gov changes", pending.len());
+        }
+        futures_util::stream::iter(pending)
+            .chain(UnboundedReceiverStream::new(receiver))
+            .for_each_concurrent(None, |op_hash| async move {
+                if let Err(e) = self.apply_pending_gov_change(op_hash, alt).await {
+                    error!("{}: Failed to apply pending gov change: {}", hex::encode(op_hash), e);
+                }
+            })
+            .await;
+    }
+
+    /// Returns the hashes of the gov operations whose changes are pending
+    async fn find_pending_gov_changes(&self) -> Vec<OpHash> {
+        let commitment = self.solana_config.client.commitment;
+        let accounts = self
+            .transactor
+            .rpc_pool()
+            .with_read_rpc_loop(
+                |rpc| async move {
+                    let config = RpcProgramAccountsConfig {
+                        filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                            0,
+                            PendingGovChange::discriminator().to_vec(),
+                        ))]),
+                        account_config: RpcAccountInfoConfig {
+                            commitment: Some(commitment),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    };
+                    rpc.get_program_accounts_with_config(&photon::ID, config).await
+                },
+                commitment,
+            )
+            .await;
+        accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                let change = PendingGovChange::try_deserialize(&mut account.data.as_slice());
+                match change.ok().and_then(|change| OpHash::try_from(change.op_hash).ok()) {
+                    Some(op_hash) => Some(op_hash),
+                    None => {
+                        error!("Failed to deserialize pending gov change {}", address);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Applies the gov operation queued by the timelock once its delay is over. Returns as soon as
+    /// the pending change account is closed, so a change vetoed by the guardian is just dropped.
+    async fn apply_pending_gov_change(
+        &self,
+        op_hash: OpHash,
+        alt: &[AddressLookupTableAccount],
+    ) -> Result<(), ExecutorError> {
+        let op_hash_str = hex::encode(op_hash);
+        let pending_change = pending_gov_change_address(&op_hash);
+        let commitment = self.solana_config.client.commitment;
+        let mut attempts = 0;
+        loop {
+            let (account, slot) = self
+                .transactor
+                .rpc_pool()
+                .with_read_rpc_loop(
+                    |rpc| async move {
+                        let account =
+                            rpc.get_account_with_commitment(&pending_change, commitment).await?;
+                        let slot = rpc.get_slot_with_commitment(commitment).await?;
+                        Ok::<_, ClientError>((account.value, slot))
+                    },
+                    commitment,
+                )
+                .await;
+            let Some(account) = account else {
+                return Ok(());
+            };
+            let change =
+                PendingGovChange::try_deserialize(&mut account.data.as_slice()).map_err(|err| {
+                    error!("{}. Failed to deserialize pending gov change: {}", op_hash_str, err);
+                    ExecutorError::MalformedData
+                })?;
+
+            if slot < change.executable_slot {
+                let delay = slots_duration(change.executable_slot - slot);
+                log_with_ctx!(
+                    info,
+                    Some(op_hash_str.deref()),
+                    "Gov operation is timelocked, retry in {:?}",
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+
+            if attempts >= self.solana_config.executor_attempts {
+                log_with_ctx!(
+                    error,
+                    Some(op_hash_str.deref()),
+                    "Failed to apply the timelocked gov operation, it is left pending"
+                );
+                return Ok(());
+            }
+            attempts += 1;
+            let ix_bundle =
+                build_apply_gov_change_ix(self.solana_config.payer.pubkey(), op_hash, &change);
+            if let Err(err) = self
+                .transactor
+                .send_all_instructions(
+                    Some(op_hash_str.deref()),
+                    &[ix_bundle],
+                    &[&self.solana_config.payer],
+                    self.solana_config.payer.pubkey(),
+                    1,
+                    alt,
+                    None,
+                    false,
+                )
+                .await
+                .and_then(all_landed)
+            {
+                log_with_ctx!(
+                    warn,
+                    Some(op_hash_str.deref()),
+                    "Failed to apply the timelocked gov operation: {}",
+                    err
+                );
+            }
+        }
+    }
+
     fn ack_executed(&self, eob_block_number: u64, op_hash: OpHash, op_status: ExecutorOpStatus) {
         self.op_acknowledge_sender
             .send(OpAcknowledge::new(eob_block_number, op_hash, op_status))
             .expect("Expected acknowledge to be sent");
     }
 
-    async fn get_op_status(&self, op_hash: OpHash) -> Result<ExecutorOpStatus, ExecutorError> {
+    /// Returns the operation status along with the executor that loaded the operation, it's the
+    /// payer if the operation is not loaded yet or was loaded before the loader was recorded
+    async fn get_op_status(
+        &self,
+        op_hash: OpHash,
+    ) -> Result<(ExecutorOpStatus, Pubkey), ExecutorError> {
         let (op_info, _) = Pubkey::find_program_address(&[ROOT, b"OP", &op_hash], &photon::ID);
         let op_info_data = self
             .transactor
@@ -192,9 +468,12 @@ impl OperationManager {
             )
             .await
             .value;
+        let payer = self.solana_config.payer.pubkey();
         let op_status = match op_info_data {
-            Some(acc) => match OpInfo::try_deserialize(&mut &acc.data[..]) {
-                Ok(s) => ExecutorOpStatus::from(s.status),
+            Some(acc) => match op_info_from_account_data(&acc.data) {
+                Ok((op_info, _)) => {
+                    (ExecutorOpStatus::from(op_info.status()), op_info.loader().unwrap_or(payer))
+                }
                 Err(e) => {
                     error!(
                         "{}. Failed to deserialize op_info, ({}) skipping...",
@@ -204,11 +483,107 @@ impl OperationManager {
                     return Err(ExecutorError::MalformedData);
                 }
             },
-            None => ExecutorOpStatus::New,
+            None => (ExecutorOpStatus::New, payer),
         };
         Ok(op_status)
     }
 
+    /// Returns the time to wait for before processing the operation. Either another executor is in
+    /// charge of it for the current turn, or executing it is held by the rate limit of its protocol.
+    async fn get_wait_delay(
+        &self,
+        op_hash: OpHash,
+        op: &SignedOperation,
+        op_status: ExecutorOpStatus,
+    ) -> Result<Option<Duration>, ExecutorError> {
+        let (protocol_info, _) = Pubkey::find_program_address(
+            &[ROOT, b"PROTOCOL", &op.operation_data.protocol_id.0],
+            &photon::ID,
+        );
+        let (op_info, _) = Pubkey::find_program_address(&[ROOT, b"OP", &op_hash], &photon::ID);
+        let commitment = self.solana_config.client.commitment;
+        let (accounts, slot) = self
+            .transactor
+            .rpc_pool()
+            .with_read_rpc(
+                |rpc| async move {
+                    let accounts = rpc
+                        .get_multiple_accounts_with_commitment(
+                            &[protocol_info, op_info],
+                            commitment,
+                        )
+                        .await?;
+                    let slot = rpc.get_slot_with_commitment(commitment).await?;
+                    Ok::<_, ClientError>((accounts.value, slot))
+                },
+                commitment,
+            )
+            .await?;
+        let op_hash_str = hex::encode(op_hash);
+        let Some(protocol_info) = &accounts[0] else {
+            log_with_ctx!(error, Some(op_hash_str.deref()), "Protocol info is not found");
+            return Err(ExecutorError::MalformedData);
+        };
+        let protocol_info =
+            protocol_info_from_account_data(&protocol_info.data).map_err(|err| {
+                log_with_ctx!(
+                    error,
+                    Some(op_hash_str.deref()),
+                    "Failed to get protocol info: {}",
+                    err
+                );
+                ExecutorError::MalformedData
+            })?;
+        let op_info = accounts[1]
+            .as_ref()
+            .map(|acc| op_info_from_account_data(&acc.data))
+            .transpose()
+            .map_err(|err| {
+                log_with_ctx!(error, Some(op_hash_str.deref()), "Failed to get op info: {}", err);
+                ExecutorError::MalformedData
+            })?
+            .map(|(op_info, _)| op_info);
+
+        if !protocol_info.is_executor_allowed(&op_hash, &self.solana_config.payer.pubkey(), slot) {
+            let delay = slots_duration(protocol_info.turn_expires_at(slot) - slot);
+            log_with_ctx!(
+                debug,
+                Some(op_hash_str.deref()),
+                "Another executor is in charge of the operation, retry in {:?}",
+                delay
+            );
+            return Ok(Some(delay));
+        }
+
+        if ExecutorOpStatus::Signed == op_status {
+            let recorded_until = op_info.map_or(0, |x| x.held_until());
+            let held_until = protocol_info
+                .rate_limit()
+                .held_until(slot, &op.operation_data.params, recorded_until)
+                .map_err(|err| {
+                    log_with_ctx!(
+                        error,
+                        Some(op_hash_str.deref()),
+                        "Failed to check the rate limit: {}",
+                        err
+                    );
+                    ExecutorError::MalformedData
+                })?;
+            let Some(held_until) = held_until else {
+                return Ok(None);
+            };
+            let delay = slots_duration(held_until.saturating_sub(slot));
+            log_with_ctx!(
+                info,
+                Some(op_hash_str.deref()),
+                "Operation is held by the protocol rate limit, retry in {:?}",
+                delay
+            );
+            return Ok(Some(delay));
+        }
+        Ok(None)
+    }
+
     async fn get_balance(&self) -> Result<u64, ExecutorError> {
         let rpc = self.transactor.rpc_pool();
         let rpc_balance = rpc
@@ -224,7 +599,43 @@ impl OperationManager {
         Ok(rpc_balance)
     }
 
-    async fn check_balance_and_suspend(&self, op_hash: &str) -> bool {
+    /// Returns the balances of the gas vaults of the protocols having extensions registered
+    async fn get_vault_balances(&self) -> Result<BTreeMap<ProtocolIdImpl, u64>, ExecutorError> {
+        let protocol_ids = self.extension_mng.protocol_ids();
+        let vaults: Vec<Pubkey> = protocol_ids.iter().map(|x| gas_vault_address(x)).collect();
+        let commitment = self.solana_config.client.commitment;
+        let accounts = self
+            .transactor
+            .rpc_pool()
+            .with_read_rpc(
+                |rpc| async move {
+                    rpc.get_multiple_accounts_with_commitment(&vaults, commitment).await
+                },
+                commitment,
+            )
+            .await
+            .map_err(|err| {
+                error!("Failed to get gas vault balances: {}", err);
+                ExecutorError::from(err)
+            })?;
+        Ok(protocol_ids
+            .into_iter()
+            .zip(accounts.value)
+            .map(|(protocol_id, vault)| (protocol_id, vault.map_or(0, |x| x.lamports)))
+            .collect())
+    }
+
+    /// Returns the executor balance increased by the lamports the gas vault of the protocol can pay
+    /// out above its rent exempt minimum, since the executor is reimbursed out of the vault for
+    /// processing the operations of the protocol
+    fn get_effective_balance(&self, protocol_id: &ProtocolId) -> u64 {
+        let vault_balances = self.suspending_ctx.vault_balances.lock().expect("Expected to lock");
+        let vault_balance = vault_balances.get(&protocol_id.0).copied().unwrap_or_default();
+        let reimbursable = vault_balance.saturating_sub(Rent::default().minimum_balance(0));
+        self.suspending_ctx.balance.load(Ordering::Acquire).saturating_add(reimbursable)
+    }
+
+    async fn check_balance_and_suspend(&self, op_hash: &str, protocol_id: &ProtocolId) -> bool {
         let suspending_config = &self.solana_config.suspending_config;
 
         if self.suspending_ctx.op_proc_counter.load(Ordering::Acquire)
@@ -235,9 +646,16 @@ impl OperationManager {
                 return false;
             };
             self.suspending_ctx.balance.store(new_balance, Ordering::Release);
+            if let Ok(vault_balances) = self.get_vault_balances().await {
+                for (id, balance) in &vault_balances {
+                    debug!("Gas vault balance of {}: {} lamports", ProtocolId(*id), balance);
+                }
+                *self.suspending_ctx.vault_balances.lock().expect("Expected to lock") =
+                    vault_balances;
+            }
             if new_balance < suspending_config.suspend_balance_lamports {
                 warn!(
-                    "Executor balance is too low: {} lamports. Processing will be suspended.",
+                    "Executor balance is too low: {} lamports. Processing not covered by gas vaults will be suspended.",
                     new_balance
                 );
             } else if new_balance < suspending_config.warn_balance_lamports {
@@ -245,7 +663,7 @@ impl OperationManager {
             }
         }
 
-        let balance = self.suspending_ctx.balance.load(Ordering::Acquire);
+        let balance = self.get_effective_balance(protocol_id);
         if balance >= suspending_config.suspend_balance_lamports {
             return true;
         }
@@ -288,6 +706,7 @@ impl OperationManager {
         op_hash: [u8; 32],
         op: SignedOperation,
         op_status: ExecutorOpStatus,
+        loader: Pubkey,
     ) -> Result<Vec<InstructionBundle>, ExecutorError> {
         let payer = self.solana_config.payer.pubkey();
         Ok(match op_status {
@@ -296,18 +715,26 @@ impl OperationManager {
                 build_sign_tx(payer, op_hash, op.clone())?,
                 build_execute_tx(
                     &self.extension_mng,
-                    self.solana_config.payer.pubkey(),
+                    payer,
+                    loader,
                     op_hash,
                     op.operation_data.clone(),
                 )?,
             ],
             ExecutorOpStatus::Loaded => vec![
                 build_sign_tx(payer, op_hash, op.clone())?,
-                build_execute_tx(&self.extension_mng, payer, op_hash, op.operation_data.clone())?,
+                build_execute_tx(
+                    &self.extension_mng,
+                    payer,
+                    loader,
+                    op_hash,
+                    op.operation_data.clone(),
+                )?,
             ],
             ExecutorOpStatus::Signed => vec![build_execute_tx(
                 &self.extension_mng,
                 payer,
+                loader,
                 op_hash,
                 op.operation_data.clone(),
             )?],
@@ -346,11 +773,7 @@ fn build_load_ix(
     );
     let photon_op_data =
         photon::protocol_data::OperationData::try_from(op_data).map_err(|err| {
-            error!(
-                "{}. Failed to get op_data from op_data_message: {}",
-                op_hash_str,
-                hex::encode(err)
-            );
+            error!("{}. Failed to get op_data from op_data_message: {}", op_hash_str, err);
             ExecutorError::MalformedData
         })?;
     let load_op_data = photon::instruction::LoadOperation {
@@ -381,13 +804,18 @@ fn build_sign_tx(
     }
     .to_account_metas(None);
 
+    let signatures = op
+        .signatures
+        .into_iter()
+        .map(photon::protocol_data::TransmitterSignature::try_from)
+        .collect::<Result<_, _>>()
+        .map_err(|err| {
+            error!("{}. Failed to get signatures: {}", hex::encode(op_hash), err);
+            ExecutorError::MalformedData
+        })?;
     let sign_op_data = photon::instruction::SignOperation {
         op_hash: op_hash.to_vec(),
-        signatures: op
-            .signatures
-            .into_iter()
-            .map(photon::protocol_data::TransmitterSignature::from)
-            .collect(),
+        signatures,
     }
     .data();
     let instruction = Instruction::new_with_bytes(photon::id(), &sign_op_data, accounts);
@@ -397,6 +825,7 @@ fn build_sign_tx(
 fn build_execute_tx(
     extension_mng: &ExtensionManager,
     executor: Pubkey,
+    loader: Pubkey,
     op_hash: [u8; 32],
     op_data: OperationData,
 ) -> Result<InstructionBundle, ExecutorError> {
@@ -417,18 +846,30 @@ fn build_execute_tx(
         op_info: op_info_pda,
         protocol_info: protocol_info_pda,
         call_authority: call_authority_pda,
+        gas_vault: gas_vault_address(&protocol_id.0),
+        loader,
+        system_program: anchor_lang::system_program::ID,
     }
     .to_account_metas(None);
-    let function_selector = &op_data.function_selector;
-
-    if function_selector.len() < 3 {
-        error!("Failed to process function_selector due to its size");
-        return Err(ExecutorError::MalformedData);
-    }
-    let extension_accounts = extension
-        .get_accounts(&function_selector[2..], &op_data.params)
-        .map_err(ExecutorError::from)?;
+    let function_selector = FunctionSelector::try_from(op_data.function_selector.as_slice())
+        .map_err(|err| {
+            error!("{}. Failed to decode function_selector: {}", hex::encode(op_hash), err);
+            ExecutorError::MalformedData
+        })?;
+    let function_selector = match function_selector.selector() {
+        Ok(selector) if !selector.is_empty() => selector,
+        _ => {
+            error!("Failed to process function_selector due to its size");
+            return Err(ExecutorError::MalformedData);
+        }
+    };
+    let extension_accounts =
+        extension.get_accounts(function_selector, &op_data.params).map_err(ExecutorError::from)?;
     accounts.extend(extension_accounts);
+    let op_accounts = extension
+        .get_op_accounts(&op_hash, function_selector, &op_data.params)
+        .map_err(ExecutorError::from)?;
+    accounts.extend(op_accounts);
 
     let exec_op_data = photon::instruction::ExecuteOperation {
         op_hash: op_hash.to_vec(),
@@ -436,6 +877,35 @@ fn build_execute_tx(
     .data();
     let ix = Instruction::new_with_bytes(photon::id(), &exec_op_data, accounts);
     let compute_units =
-        extension.get_compute_budget(&function_selector[2..], &op_data.params).unwrap_or(200000);
+        extension.get_compute_budget(function_selector, &op_data.params).unwrap_or(200000);
     Ok(InstructionBundle::new(ix, compute_units))
 }
+
+fn build_apply_gov_change_ix(
+    executor: Pubkey,
+    op_hash: [u8; 32],
+    change: &PendingGovChange,
+) -> InstructionBundle {
+    let (op_info_pda, _) = Pubkey::find_program_address(&[ROOT, b"OP", &op_hash], &photon::ID);
+    let (config_pda, _) = Pubkey::find_program_address(&[ROOT, b"CONFIG"], &photon::ID);
+    let (gov_info_pda, _) =
+        Pubkey::find_program_address(&[ROOT, b"PROTOCOL", GOV_PROTOCOL_ID], &photon::ID);
+    let (target_protocol_info_pda, _) =
+        Pubkey::find_program_address(&[ROOT, b"PROTOCOL", &change.target_protocol_id], &photon::ID);
+    let accounts = photon::accounts::ApplyGovChange {
+        executor,
+        op_info: op_info_pda,
+        config: config_pda,
+        gov_info: gov_info_pda,
+        pending_change: pending_gov_change_address(&op_hash),
+        target_protocol_info: target_protocol_info_pda,
+        payer: change.payer,
+    }
+    .to_account_metas(None);
+    let data = photon::instruction::ApplyGovChange {
+        op_hash: op_hash.to_vec(),
+    }
+    .data();
+    let instruction = Instruction::new_with_bytes(photon::id(), &data, accounts);
+    InstructionBundle::new(instruction, 400000)
+}
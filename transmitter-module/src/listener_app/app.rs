@@ -1,16 +1,18 @@
 use crate::common::solana_logs::solana_event_listener::SolanaEventListener;
-use log::info;
+use log::{error, info};
+use solana_transactor::RpcPool;
 use tokio::sync::mpsc::unbounded_channel;
 
 use super::{
-    config::ListenConfig, rabbitmq_publisher::RabbitmqPublisher,
-    solana_logs_processor::ProposalEventProcessor,
+    config::ListenConfig, error::ListenError, outbox_reader::OutboxReader,
+    rabbitmq_publisher::RabbitmqPublisher, solana_logs_processor::ProposalEventProcessor,
 };
 
 pub(crate) struct ListenerApp {
     solana_listener: SolanaEventListener,
     rabbitmq_sender: RabbitmqPublisher,
     solana_logs_proc: ProposalEventProcessor,
+    outbox_reader: OutboxReader,
 }
 
 impl ListenerApp {
@@ -20,27 +22,52 @@ impl ListenerApp {
             return;
         };
 
-        let mut app = ListenerApp::new(config);
+        let mut app = match ListenerApp::new(config) {
+            Ok(app) => app,
+            Err(err) => {
+                error!("Failed to create listener app: {}", err);
+                return;
+            }
+        };
         app.execute_impl().await;
     }
 
-    fn new(config: ListenConfig) -> ListenerApp {
+    fn new(config: ListenConfig) -> Result<ListenerApp, ListenError> {
         Self::trace_config(&config);
         let (propose_sender, propose_receiver) = unbounded_channel();
         let (logs_sender, logs_receiver) = unbounded_channel();
-        ListenerApp {
+        let (missing_sender, missing_receiver) = unbounded_channel();
+        let allowed_protocols =
+            ProposalEventProcessor::allowed_protocol_ids(&config.allowed_protocols);
+        let rpc_pool = RpcPool::new_with_health(
+            &config.solana.client.read_rpcs,
+            &config.solana.client.write_rpcs,
+            config.solana.client.rpc_health.clone(),
+        )?;
+        let chain_id = config.solana.client.chain_id;
+        Ok(ListenerApp {
+            outbox_reader: OutboxReader::new(
+                config.solana.client.clone(),
+                rpc_pool,
+                missing_receiver,
+                propose_sender.clone(),
+                allowed_protocols.clone(),
+            ),
             solana_listener: SolanaEventListener::new(config.solana, config.mongodb, logs_sender),
             rabbitmq_sender: RabbitmqPublisher::new(config.rabbitmq, propose_receiver),
             solana_logs_proc: ProposalEventProcessor::new(
                 logs_receiver,
                 propose_sender,
-                config.allowed_protocols,
+                missing_sender,
+                allowed_protocols,
+                chain_id,
             ),
-        }
+        })
     }
 
     fn trace_config(config: &ListenConfig) {
         info!("solana_commitment: {}", config.solana.client.commitment.commitment);
+        info!("solana_chain_id: {}", config.solana.client.chain_id);
 
         for rpc in &config.solana.client.read_rpcs {
             info!("solana_read_rpc: {}, rate_limit: {}", rpc.url, rpc.ratelimit);
@@ -70,6 +97,7 @@ impl ListenerApp {
             _ = self.solana_listener.listen_to_solana() => {}
             _ = self.rabbitmq_sender.publish_to_rabbitmq() => {}
             _ = self.solana_logs_proc.execute() => {}
+            _ = self.outbox_reader.execute() => {}
         }
     }
 }
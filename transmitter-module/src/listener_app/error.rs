@@ -8,4 +8,6 @@ pub(crate) enum ListenError {
     Rabbitmq(#[from] amqprs::error::Error),
     #[error("Mongodb client error")]
     Mongodb(#[from] mongodb::error::Error),
+    #[error("Solana transactor error {0}")]
+    SolanaTransactor(#[from] solana_transactor::TransactorError),
 }
@@ -1,6 +1,7 @@
 mod app;
 mod config;
 mod error;
+mod outbox_reader;
 mod rabbitmq_publisher;
 mod solana_logs_processor;
 
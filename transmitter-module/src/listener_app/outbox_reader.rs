@@ -0,0 +1,138 @@
+use anchor_lang::AccountDeserialize;
+use log::{debug, error, info, warn};
+use photon::outbox::{outbox_address, OutboundProposal};
+use solana_client::{
+    client_error::ClientError, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use solana_transactor::RpcPool;
+use std::{ops::Range, str::FromStr};
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    Mutex,
+};
+
+use super::solana_logs_processor::build_propose;
+use crate::common::config::SolanaClientConfig;
+use transmitter_common::data::Propose;
+
+/// Reconstructs the proposals that have been missed in the logs from their outbox accounts.
+///
+/// Nonces are assigned to proposals one by one, so a gap in the nonces of the intercepted
+/// `ProposeEvent`s means that the logs of some proposals are truncated or missing. The outbox accounts
+/// of the missing nonces are looked up for every allowed protocol, and the proposals found are
+/// published with `need_check` set, the source transaction is taken from the outbox account history.
+/// Proposals made without the outbox mode or already acknowledged can't be reconstructed.
+pub(super) struct OutboxReader {
+    client_config: SolanaClientConfig,
+    rpc_pool: RpcPool,
+    missing_receiver: Mutex<UnboundedReceiver<Range<u64>>>,
+    propose_sender: UnboundedSender<Propose>,
+    allowed_protocols: Vec<Vec<u8>>,
+}
+
+impl OutboxReader {
+    pub(super) fn new(
+        client_config: SolanaClientConfig,
+        rpc_pool: RpcPool,
+        missing_receiver: UnboundedReceiver<Range<u64>>,
+        propose_sender: UnboundedSender<Propose>,
+        allowed_protocols: Vec<Vec<u8>>,
+    ) -> OutboxReader {
+        OutboxReader {
+            client_config,
+            rpc_pool,
+            missing_receiver: Mutex::new(missing_receiver),
+            propose_sender,
+            allowed_protocols,
+        }
+    }
+
+    pub(super) async fn execute(&self) {
+        while let Some(nonces) = self.missing_receiver.lock().await.recv().await {
+            warn!("Proposals are missing in logs, nonces: {:?}", nonces);
+            for nonce in nonces {
+                self.reconstruct(nonce).await;
+            }
+        }
+    }
+
+    async fn reconstruct(&self, nonce: u64) {
+        let addresses: Vec<Pubkey> =
+            self.allowed_protocols.iter().map(|p| outbox_address(p, nonce)).collect();
+        let commitment = self.client_config.commitment;
+        let accounts = self
+            .rpc_pool
+            .with_read_rpc_loop(
+                |rpc| {
+                    let addresses = &addresses;
+                    async move {
+                        let mut accounts = Vec::new();
+                        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+                            let chunk_accounts = rpc
+                                .get_multiple_accounts_with_commitment(chunk, commitment)
+                                .await?;
+                            accounts.extend(chunk_accounts.value);
+                        }
+                        Ok::<_, ClientError>(accounts)
+                    }
+                },
+                commitment,
+            )
+            .await;
+
+        let Some((address, account)) = addresses
+            .iter()
+            .zip(accounts)
+            .find_map(|(address, account)| account.map(|account| (address, account)))
+        else {
+            debug!("No outbox account found for nonce: {}", nonce);
+            return;
+        };
+        let proposal = match OutboundProposal::try_deserialize(&mut account.data.as_slice()) {
+            Ok(proposal) => proposal,
+            Err(err) => {
+                error!("Failed to deserialize outbox account {}: {}", address, err);
+                return;
+            }
+        };
+        let Some(signature) = self.get_proposal_signature(address).await else {
+            error!("Failed to get the proposal transaction of outbox account: {}", address);
+            return;
+        };
+
+        let slot = proposal.slot;
+        info!("Proposal reconstructed from outbox account: {}, nonce: {}", address, nonce);
+        let chain_id = self.client_config.chain_id;
+        let Some(propose) = build_propose(proposal.into(), chain_id, &signature, slot, true) else {
+            return;
+        };
+        if let Err(err) = self.propose_sender.send(propose) {
+            error!("Failed to send proposal through the channel: {}", err);
+        }
+    }
+
+    /// The outbox account is created by the proposal transaction, so it is the oldest one in its history
+    async fn get_proposal_signature(&self, address: &Pubkey) -> Option<Signature> {
+        let commitment = self.client_config.commitment;
+        let signatures = self
+            .rpc_pool
+            .with_read_rpc_loop(
+                |rpc| async move {
+                    let args = GetConfirmedSignaturesForAddress2Config {
+                        commitment: Some(commitment),
+                        ..Default::default()
+                    };
+                    rpc.get_signatures_for_address_with_config(address, args).await
+                },
+                commitment,
+            )
+            .await;
+        signatures
+            .into_iter()
+            .rev()
+            .find(|s| s.err.is_none())
+            .and_then(|s| Signature::from_str(&s.signature).ok())
+    }
+}
@@ -1,7 +1,7 @@
 use log::{debug, error, info};
-use photon::ProposeEvent;
+use photon::{protocol_data::ProposerIdentity, ProposeEvent};
 use solana_sdk::signature::Signature;
-use std::str::FromStr;
+use std::{collections::BTreeMap, ops::Range, str::FromStr, sync::Mutex as SyncMutex};
 use tokio::sync::{
     mpsc::{UnboundedReceiver, UnboundedSender},
     Mutex,
@@ -10,26 +10,38 @@ use tokio::sync::{
 use crate::common::solana_logs::{
     event_processor::EventProcessor, solana_event_listener::LogsBunch,
 };
-use transmitter_common::{
-    data::{default_meta, OperationData, Propose, ProtocolId},
-    SOLANA_CHAIN_ID,
-};
+use transmitter_common::data::{default_meta, OperationData, Propose, ProtocolId};
 
 pub(super) struct ProposalEventProcessor {
     logs_receiver: Mutex<UnboundedReceiver<LogsBunch>>,
     propose_sender: UnboundedSender<Propose>,
+    missing_sender: UnboundedSender<Range<u64>>,
     allowed_protocols: Vec<Vec<u8>>,
+    chain_id: u128,
+    nonce_tracker: SyncMutex<NonceTracker>,
 }
 
 impl ProposalEventProcessor {
     pub(super) fn new(
         logs_receiver: UnboundedReceiver<LogsBunch>,
         propose_sender: UnboundedSender<Propose>,
-        allowed_protocols: Vec<String>,
+        missing_sender: UnboundedSender<Range<u64>>,
+        allowed_protocols: Vec<Vec<u8>>,
+        chain_id: u128,
     ) -> ProposalEventProcessor {
-        info!("Allowed protocols to listen events: {}", allowed_protocols.join(", "));
+        ProposalEventProcessor {
+            logs_receiver: Mutex::new(logs_receiver),
+            propose_sender,
+            missing_sender,
+            allowed_protocols,
+            chain_id,
+            nonce_tracker: SyncMutex::new(NonceTracker::default()),
+        }
+    }
 
-        let allowed_protocols: Vec<Vec<u8>> = allowed_protocols
+    pub(super) fn allowed_protocol_ids(allowed_protocols: &[String]) -> Vec<Vec<u8>> {
+        info!("Allowed protocols to listen events: {}", allowed_protocols.join(", "));
+        allowed_protocols
             .iter()
             .map(|p| {
                 let mut allowed_protocol = vec![0_u8; 32];
@@ -39,13 +51,7 @@ impl ProposalEventProcessor {
                     .copy_from_slice(&bytes_to_write[..length_to_copy]);
                 allowed_protocol
             })
-            .collect();
-
-        ProposalEventProcessor {
-            logs_receiver: Mutex::new(logs_receiver),
-            propose_sender,
-            allowed_protocols,
-        }
+            .collect()
     }
 
     pub(super) async fn execute(&self) {
@@ -59,39 +65,131 @@ impl EventProcessor for ProposalEventProcessor {
     type Event = ProposeEvent;
 
     fn on_event(&self, event: Self::Event, signature: &str, slot: u64, need_check: bool) {
-        if !self.allowed_protocols.contains(&event.protocol_id) {
-            return;
+        // Proposals of all protocols share the nonce sequence, so it's tracked before filtering
+        let missing =
+            self.nonce_tracker.lock().expect("Expected to lock").on_nonce(event.nonce, slot);
+        for nonces in missing {
+            if let Err(err) = self.missing_sender.send(nonces) {
+                error!("Failed to send missing nonces through the channel: {}", err);
+            }
         }
 
-        let Ok(protocol_id) = <[u8; 32]>::try_from(event.protocol_id.clone()).map_err(|_| {
-            error!("Failed to get 32 bytes protocol_id chunk from event data, skip event");
-        }) else {
+        if !self.allowed_protocols.contains(&event.protocol_id) {
             return;
-        };
+        }
 
         debug!("Solana event intercepted: {:?}", event);
         let Ok(signature) = Signature::from_str(signature) else {
             error!("Failed to parse tx_signature from: {}", signature);
             return;
         };
-        if let Err(err) = self.propose_sender.send(Propose {
-            need_check,
-            latest_block_id: signature.to_string(),
-            operation_data: OperationData {
-                src_chain_id: SOLANA_CHAIN_ID,
-                meta: default_meta(),
-                src_block_number: slot,
-                src_op_tx_id: signature.as_ref().to_vec(),
-                protocol_id: ProtocolId(protocol_id),
-                nonce: event.nonce,
-                dest_chain_id: event.dst_chain_id,
-                protocol_addr: event.protocol_address,
-                function_selector: event.function_selector,
-                params: event.params,
-                reserved: <Vec<u8>>::default(),
-            },
-        }) {
+        let Some(propose) = build_propose(event, self.chain_id, &signature, slot, need_check)
+        else {
+            return;
+        };
+        if let Err(err) = self.propose_sender.send(propose) {
             error!("Failed to send proposal through the channel: {}", err);
         }
     }
 }
+
+/// The number of slots the proposal logs may arrive out of the nonce order for, e.g. when the
+/// retrospective reading interleaves with the newer logs. A nonce is considered missing only once
+/// the logs of a proposal this many slots newer than the one next to the gap are intercepted.
+const REORDER_SLOTS: u64 = 150;
+
+/// Orders the intercepted proposal nonces and detects the gaps in their sequence
+#[derive(Default)]
+struct NonceTracker {
+    /// The lowest nonce neither intercepted nor reported missing, unknown until the first proposal
+    next: Option<u64>,
+    /// The slots of the intercepted proposals above `next`, ordered by nonce
+    ahead: BTreeMap<u64, u64>,
+    /// The newest slot intercepted
+    last_slot: u64,
+}
+
+impl NonceTracker {
+    /// Records the nonce of the proposal intercepted at `slot` and returns the ranges of nonces
+    /// that are considered missing since then
+    fn on_nonce(&mut self, nonce: u64, slot: u64) -> Vec<Range<u64>> {
+        self.last_slot = self.last_slot.max(slot);
+        let next = *self.next.get_or_insert(nonce);
+        if nonce >= next {
+            self.ahead.insert(nonce, slot);
+        }
+
+        let mut missing = Vec::new();
+        while let Some((&nonce, &slot)) = self.ahead.first_key_value() {
+            let next = self.next.as_mut().expect("Set above");
+            if nonce > *next {
+                if self.last_slot < slot + REORDER_SLOTS {
+                    break;
+                }
+                missing.push(*next..nonce);
+            }
+            *next = nonce + 1;
+            self.ahead.pop_first();
+        }
+        missing
+    }
+}
+
+/// Builds the outbound operation of the proposal, the sender of the proposal is passed in `reserved`
+pub(super) fn build_propose(
+    event: ProposeEvent,
+    chain_id: u128,
+    signature: &Signature,
+    slot: u64,
+    need_check: bool,
+) -> Option<Propose> {
+    let Ok(protocol_id) = <[u8; 32]>::try_from(event.protocol_id).map_err(|_| {
+        error!("Failed to get 32 bytes protocol_id chunk from proposal data, skip proposal");
+    }) else {
+        return None;
+    };
+    let proposer_identity = ProposerIdentity {
+        proposer: event.proposer,
+        caller_program: event.caller_program,
+    };
+
+    Some(Propose {
+        need_check,
+        latest_block_id: signature.to_string(),
+        operation_data: OperationData {
+            src_chain_id: chain_id,
+            meta: default_meta(),
+            src_block_number: slot,
+            src_op_tx_id: signature.as_ref().to_vec(),
+            protocol_id: ProtocolId(protocol_id),
+            nonce: event.nonce,
+            dest_chain_id: event.dst_chain_id,
+            protocol_addr: event.protocol_address,
+            function_selector: event.function_selector,
+            params: event.params,
+            reserved: proposer_identity.to_reserved(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nonce_tracker() {
+        let mut tracker = NonceTracker::default();
+        assert!(tracker.on_nonce(10, 100).is_empty());
+        // Out of order nonces are no gap while the logs may still be reordered
+        assert!(tracker.on_nonce(12, 101).is_empty());
+        assert!(tracker.on_nonce(11, 90).is_empty());
+        assert!(tracker.on_nonce(15, 110).is_empty());
+        assert!(tracker.on_nonce(13, 110 + REORDER_SLOTS - 1).is_empty());
+        assert_eq!(tracker.on_nonce(16, 110 + REORDER_SLOTS), vec![14..15]);
+        // The nonces before the first one intercepted or already reported are not tracked
+        assert!(tracker.on_nonce(14, 120).is_empty());
+        assert!(tracker.on_nonce(5, 120).is_empty());
+        assert_eq!(tracker.next, Some(17));
+        assert!(tracker.ahead.is_empty());
+    }
+}
@@ -12,9 +12,7 @@ use tokio::{
 };
 
 use transmitter_common::{
-    config::ReconnectConfig,
-    data::{ProposalExecuted, TransmitterMsg, TransmitterMsgImpl},
-    rabbitmq_client::RabbitmqClient,
+    config::ReconnectConfig, data::TransmitterMsg, rabbitmq_client::RabbitmqClient,
 };
 
 use super::error::WatcherError;
@@ -22,8 +20,8 @@ use crate::common::rabbitmq::{ChannelControl, ConnectionControl, RabbitmqPublish
 
 pub(super) struct RabbitmqPublisher {
     config: RabbitmqPublishConfig,
-    op_status_receiver: UnboundedReceiver<ProposalExecuted>,
-    buffered_op_status: Option<ProposalExecuted>,
+    op_status_receiver: UnboundedReceiver<TransmitterMsg>,
+    buffered_op_status: Option<TransmitterMsg>,
     close_notify: Arc<Notify>,
     connection: Mutex<Option<(Connection, Channel)>>,
 }
@@ -31,7 +29,7 @@ pub(super) struct RabbitmqPublisher {
 impl RabbitmqPublisher {
     pub(super) fn new(
         config: RabbitmqPublishConfig,
-        propose_receiver: UnboundedReceiver<ProposalExecuted>,
+        propose_receiver: UnboundedReceiver<TransmitterMsg>,
     ) -> RabbitmqPublisher {
         RabbitmqPublisher {
             config,
@@ -64,11 +62,7 @@ impl RabbitmqPublisher {
         }
     }
 
-    async fn publish_propose(&mut self, proposal: ProposalExecuted) {
-        let transmitter_msg = TransmitterMsg::new(
-            TransmitterMsgImpl::ProposalExecuted(proposal.clone()),
-            proposal.need_check,
-        );
+    async fn publish_propose(&mut self, transmitter_msg: TransmitterMsg) {
         debug!("operation_status to be sent: {:?}", transmitter_msg);
         let Ok(json_data) = serde_json::to_vec(&transmitter_msg).map_err(|err| {
             error!(
@@ -83,12 +77,12 @@ impl RabbitmqPublisher {
         let (_, channel) = guard.as_ref().expect("Expected rabbitmq channel to be set");
         let res = channel.basic_publish(BasicProperties::default(), json_data, args.clone()).await;
         let _ = res.map_err(|err| {
-            self.buffered_op_status = Some(proposal);
+            self.buffered_op_status = Some(transmitter_msg);
             error!("Failed to publish operation_data message, error: {}", err);
         });
     }
 
-    async fn propose_to_progress(&mut self) -> Option<ProposalExecuted> {
+    async fn propose_to_progress(&mut self) -> Option<TransmitterMsg> {
         if self.buffered_op_status.is_some() {
             self.buffered_op_status.take()
         } else {
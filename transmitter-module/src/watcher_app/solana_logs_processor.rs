@@ -8,26 +8,33 @@ use crate::common::solana_logs::{
     event_processor::EventProcessor, solana_event_listener::LogsBunch,
 };
 
-use transmitter_common::data::{OpHash, ProposalExecuted};
+use transmitter_common::data::{
+    GovOperationApplied, OpHash, ProposalExecuted, ProtocolId, TransmitterMsg, TransmitterMsgImpl,
+};
 
 pub(super) struct OperationExecutedEventProcessor {
     logs_receiver: Mutex<UnboundedReceiver<LogsBunch>>,
-    op_status_sender: UnboundedSender<ProposalExecuted>,
+    op_status_sender: UnboundedSender<TransmitterMsg>,
+    gov_event_proc: GovOperationEventProcessor,
 }
 
 impl OperationExecutedEventProcessor {
     pub(super) fn new(
         logs_receiver: UnboundedReceiver<LogsBunch>,
-        op_status_sender: UnboundedSender<ProposalExecuted>,
+        op_status_sender: UnboundedSender<TransmitterMsg>,
     ) -> OperationExecutedEventProcessor {
         OperationExecutedEventProcessor {
             logs_receiver: Mutex::new(logs_receiver),
+            gov_event_proc: GovOperationEventProcessor {
+                gov_change_sender: op_status_sender.clone(),
+            },
             op_status_sender,
         }
     }
 
     pub(super) async fn execute(&self) {
         while let Some(logs_bunch) = self.logs_receiver.lock().await.recv().await {
+            self.gov_event_proc.on_logs(logs_bunch.clone());
             self.on_logs(logs_bunch);
         }
     }
@@ -38,14 +45,52 @@ impl EventProcessor for OperationExecutedEventProcessor {
 
     fn on_event(&self, event: Self::Event, signature: &str, _slot: u64, need_check: bool) {
         debug!("OperationExecuted status event intercepted: {:?}", event);
-        if let Err(err) = self.op_status_sender.send(ProposalExecuted {
+        let proposal_executed = ProposalExecuted {
             need_check,
             last_watched_block: signature.to_string(),
             op_hash: OpHash::try_from(event.op_hash)
                 .expect("op_hash expected to be gotten from proposal_executed event"),
             executor: event.executor,
-        }) {
+        };
+        let msg = TransmitterMsg::new(
+            TransmitterMsgImpl::ProposalExecuted(proposal_executed),
+            need_check,
+        );
+        if let Err(err) = self.op_status_sender.send(msg) {
             error!("Failed to send proposal through the channel: {}", err);
         }
     }
 }
+
+/// Publishes the changes of the protocol settings made by gov operations
+struct GovOperationEventProcessor {
+    gov_change_sender: UnboundedSender<TransmitterMsg>,
+}
+
+impl EventProcessor for GovOperationEventProcessor {
+    type Event = photon::GovOperationApplied;
+
+    fn on_event(&self, event: Self::Event, signature: &str, _slot: u64, need_check: bool) {
+        debug!("GovOperationApplied event intercepted: {:?}", event);
+        let (Ok(op_hash), Ok(protocol_id)) =
+            (OpHash::try_from(event.op_hash), <[u8; 32]>::try_from(event.protocol_id))
+        else {
+            error!("Failed to get 32 bytes op_hash and protocol_id from event data, skip event");
+            return;
+        };
+        let gov_operation_applied = GovOperationApplied {
+            need_check,
+            last_watched_block: signature.to_string(),
+            op_hash,
+            protocol_id: ProtocolId(protocol_id),
+            change: event.change.into(),
+        };
+        let msg = TransmitterMsg::new(
+            TransmitterMsgImpl::GovOperationApplied(gov_operation_applied),
+            need_check,
+        );
+        if let Err(err) = self.gov_change_sender.send(msg) {
+            error!("Failed to send gov change through the channel: {}", err);
+        }
+    }
+}
@@ -66,9 +66,8 @@ impl ProtocolExtension for BridgeExtension {
         function_selector: &[u8],
         params: &[u8],
     ) -> Result<Vec<AccountMeta>, ExtensionError> {
-        let code = &function_selector[..4];
-        Ok(match code {
-            &[0x99, 0x45, 0xe3, 0xd3] => self.get_accounts_redeem(params)?,
+        Ok(match function_selector.get(..4) {
+            Some(&[0x99, 0x45, 0xe3, 0xd3]) => self.get_accounts_redeem(params)?,
             _ => {
                 let selector = String::from_utf8_lossy(function_selector);
                 warn!("Unexpected function selector: {}", selector);
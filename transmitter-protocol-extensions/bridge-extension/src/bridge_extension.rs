@@ -2,6 +2,7 @@ use anchor_spl::associated_token;
 use config::Config;
 use ethabi::ParamType;
 use log::{error, warn};
+use photon::rate_limit::RateLimit;
 use serde::{Deserialize, Deserializer};
 use solana_sdk::{
     hash::Hash, instruction::AccountMeta, pubkey::Pubkey, system_program, transaction::Transaction,
@@ -15,6 +16,10 @@ lazy_static::lazy_static! {
     };
 }
 
+/// The byte offset of the `uint256 amount` within the ABI encoded `redeem` params. It is declared as
+/// the value offset of the bridge protocol rate limit, so the Photon Endpoint caps redeemed amounts.
+pub const REDEEM_AMOUNT_OFFSET: u32 = 32;
+
 #[no_mangle]
 pub fn get_extension() -> &'static dyn ProtocolExtension {
     &*BRIDGE_EXTENTION
@@ -64,9 +69,8 @@ impl ProtocolExtension for BridgeExtension {
         function_selector: &[u8],
         params: &[u8],
     ) -> Result<Vec<AccountMeta>, ExtensionError> {
-        let code = &function_selector[..4];
-        Ok(match code {
-            &[0x99, 0x45, 0xe3, 0xd3] => self.get_accounts_redeem(params)?,
+        Ok(match function_selector.get(..4) {
+            Some(&[0x99, 0x45, 0xe3, 0xd3]) => self.get_accounts_redeem(params)?,
             _ => {
                 let selector = String::from_utf8_lossy(function_selector);
                 warn!("Unexpected function selector: {}", selector);
@@ -113,6 +117,12 @@ impl BridgeExtension {
     }
 
     fn get_accounts_redeem(&self, params: &[u8]) -> Result<Vec<AccountMeta>, ExtensionError> {
+        // The rate limit reads the amount at the declared offset, so it has to be the decoded one
+        let rate_limited_amount =
+            RateLimit::new(1, 0, u128::MAX, REDEEM_AMOUNT_OFFSET).value(params).map_err(|e| {
+                error!("Failed to read redeem amount at {}: {:?}", REDEEM_AMOUNT_OFFSET, e);
+                ExtensionError::Extension
+            })?;
         let (authority, _) =
             Pubkey::find_program_address(&[&self.seed_root, b"AUTHORITY"], &self.bridge_program);
         let (config, _) =
@@ -136,6 +146,14 @@ impl BridgeExtension {
             error!("Failed to parse redeem data: {:?}", e);
             ExtensionError::Extension
         })?;
+        let amount = params[1].clone().into_uint().expect("Already parsed");
+        if amount.min(u128::MAX.into()) != ethabi::Uint::from(rate_limited_amount) {
+            error!(
+                "Redeem amount {} is not the one rate limited at {}: {}",
+                amount, REDEEM_AMOUNT_OFFSET, rate_limited_amount
+            );
+            return Err(ExtensionError::Extension);
+        }
         let user = params[0].clone().into_bytes().expect("Already parsed");
         let user = Pubkey::try_from(user).map_err(|e| {
             error!("Invalid destination vault pubkey: {:?}", e);
@@ -1,8 +1,9 @@
 use log::{debug, error};
 use photon::{
-    gov::{abi_decode_scheme, decode_abi_params, GovOperation},
+    gov::target_protocol_by_code,
     photon::ROOT,
     protocol_data::GOV_PROTOCOL_ID,
+    timelock::{gov_timelock_address, pending_gov_change_address},
 };
 use solana_sdk::{
     hash::Hash, instruction::AccountMeta, pubkey::Pubkey, system_program, transaction::Transaction,
@@ -37,33 +38,9 @@ impl ProtocolExtension for GovExtension {
         let (gov_protocol_pda, _) =
             Pubkey::find_program_address(&[ROOT, b"PROTOCOL", GOV_PROTOCOL_ID], &photon::ID);
 
-        let code = function_selector.chunks(4).next().ok_or_else(|| {
-            error!("Failed to get first chunk of gov selector");
-            ExtensionError::Extension
-        })?;
-        let selector_u32 =
-            u32::from_be_bytes(<[u8; 4]>::try_from(code).expect("Expected gov code to be 4 bytes"));
-        let gov_operation = GovOperation::try_from(selector_u32).map_err(|err| {
-            error!("Failed to get gov_operation from selector: {}", err);
-            ExtensionError::Extension
-        })?;
-
-        let params =
-            decode_abi_params(params, abi_decode_scheme(gov_operation)).map_err(|err| {
-                error!("Failed to decode abi params: {}", err);
-                ExtensionError::Extension
-            })?;
-
-        let target_protocol_id = params
-            .first()
-            .ok_or_else(|| {
-                error!("Failed to get first decoded abi param");
-                ExtensionError::Extension
-            })?
-            .clone()
-            .into_fixed_bytes()
-            .ok_or_else(|| {
-                error!("Failed to convert first decoded abi param as fixed_bytes");
+        let target_protocol_id =
+            target_protocol_by_code(function_selector, params).map_err(|err| {
+                error!("Failed to decode target protocol id from gov params: {}", err);
                 ExtensionError::Extension
             })?;
 
@@ -76,8 +53,8 @@ impl ProtocolExtension for GovExtension {
 
         debug!(
             "target_protocol_id_data: {}, target_protocol_id: {}, config: {}, target_protocol_info: {}",
-            hex::encode(&target_protocol_id),
-            String::from_utf8(target_protocol_id.clone()).unwrap_or("not-utf8".to_string()),
+            hex::encode(target_protocol_id),
+            String::from_utf8(target_protocol_id.to_vec()).unwrap_or("not-utf8".to_string()),
             config_pda,
             target_protocol_info_pda
         );
@@ -88,9 +65,20 @@ impl ProtocolExtension for GovExtension {
             AccountMeta::new(gov_protocol_pda, false),
             AccountMeta::new(target_protocol_info_pda, false),
             AccountMeta::new(system_program::id(), false),
+            AccountMeta::new_readonly(gov_timelock_address(), false),
         ])
     }
 
+    fn get_op_accounts(
+        &self,
+        op_hash: &[u8; 32],
+        _function_selector: &[u8],
+        _params: &[u8],
+    ) -> Result<Vec<AccountMeta>, ExtensionError> {
+        // The operation is queued in the pending change account if it is timelocked
+        Ok(vec![AccountMeta::new(pending_gov_change_address(op_hash), false)])
+    }
+
     fn sign_transaction(
         &self,
         _function_selector: &[u8],
@@ -86,7 +86,7 @@ impl AsyncConsumer for Consumer {
             error!("Unexpected data received");
             return;
         };
-        let chain_id = mdb_solana_chain_id();
+        let chain_id = mdb_solana_chain_id(proposal.operation_data.src_chain_id);
 
         self.collection
             .update_one(
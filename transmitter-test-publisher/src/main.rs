@@ -10,7 +10,7 @@ use ethabi::{ethereum_types, Address, Token, Uint};
 use libsecp256k1::sign;
 use log::{error, info};
 use rand::{distributions::Alphanumeric, random, Rng, RngCore};
-use serde::Deserialize;
+use serde::{de::Error as _, Deserialize, Deserializer};
 use std::{env, time::Duration};
 use thiserror::Error;
 
@@ -30,6 +30,17 @@ pub(crate) enum PublisherError {
 #[derive(Deserialize)]
 struct PublisherConfig {
     rabbitmq: RabbitmqConfig,
+    /// The chain ID of Solana the photon program is initialized with
+    #[serde(deserialize_with = "deserialize_chain_id")]
+    chain_id: u128,
+}
+
+fn deserialize_chain_id<'de, D>(deserializer: D) -> Result<u128, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let chain_id = String::deserialize(deserializer)?;
+    chain_id.parse().map_err(|err| D::Error::custom(format!("Malformed chain_id: {}", err)))
 }
 
 #[tokio::main]
@@ -53,7 +64,7 @@ pub(crate) async fn publish(config: &str, operation: &Operation, times: u64) {
     let protocol_id = ProtocolId(*onefunc::onefunc::PROTOCOL_ID);
     let gov_protocol_id = ProtocolId(*GOV_PROTOCOL_ID);
 
-    let dst_chain_id = photon::photon::SOLANA_CHAIN_ID;
+    let dst_chain_id = config.chain_id;
     let protocol_address: Vec<u8> = onefunc::ID.to_bytes().to_vec();
     let meta: &Meta =
         b"\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00";
@@ -213,6 +224,8 @@ mod test {
     use solana_program::secp256k1_recover::{secp256k1_recover, Secp256k1Pubkey};
     use transmitter_common::data::ProtocolId;
 
+    const SOLANA_CHAIN_ID: u128 = 100000000000000000000;
+
     #[test]
     fn test_signature() {
         env_logger::init();
@@ -256,8 +269,8 @@ mod test {
             protocol_id,
             meta,
             src_block_number: 1,
-            src_chain_id: photon::photon::SOLANA_CHAIN_ID,
-            dest_chain_id: photon::photon::SOLANA_CHAIN_ID,
+            src_chain_id: SOLANA_CHAIN_ID,
+            dest_chain_id: SOLANA_CHAIN_ID,
             nonce: 1,
             src_op_tx_id: tx_id.to_vec(),
             protocol_addr: protocol_address.clone(),
@@ -267,7 +280,7 @@ mod test {
         };
         let op_hash_module = op_data.op_hash_with_message();
         let op_data = photon::protocol_data::OperationData::try_from(op_data).unwrap();
-        let op_hash_contract = op_data.op_hash_with_message();
+        let op_hash_contract = op_data.op_hash_with_message().unwrap();
         assert_eq!(op_hash_contract, op_hash_module);
     }
 
@@ -283,8 +296,8 @@ mod test {
             protocol_id,
             meta,
             src_block_number: 1,
-            src_chain_id: photon::photon::SOLANA_CHAIN_ID,
-            dest_chain_id: photon::photon::SOLANA_CHAIN_ID,
+            src_chain_id: SOLANA_CHAIN_ID,
+            dest_chain_id: SOLANA_CHAIN_ID,
             nonce: 1,
             src_op_tx_id: tx_id.to_vec(),
             protocol_addr: protocol_address.clone(),
@@ -294,7 +307,7 @@ mod test {
         };
         let op_hash_module = op_data.op_hash_with_message();
         let op_data = photon::protocol_data::OperationData::try_from(op_data).unwrap();
-        let op_hash_contract = op_data.op_hash_with_message();
+        let op_hash_contract = op_data.op_hash_with_message().unwrap();
         assert_eq!(op_hash_contract, op_hash_module);
     }
 }